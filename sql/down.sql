@@ -1,8 +1,41 @@
+drop table invitations;
+drop table parent_digests;
+drop table parent_links;
+drop table two_factor_sessions;
+drop table recovery_codes;
+drop table two_factor;
+drop table email_tokens;
+drop table sign_in_attempts;
+drop table banned_words;
+drop table suspensions;
+drop table moderation_actions;
+drop table reports;
+drop table revisions;
+drop table attachments;
+drop table mentions;
+drop table notification_preferences;
+drop table notifications;
 drop table comment_mark;
 drop table post_mark;
 drop table comments;
+drop table tag_subscriptions;
+drop table follows;
+drop table post_views;
+drop table post_audience;
 drop table posts;
 drop table students;
 drop table teachers;
 drop table administrators;
 drop table users;
+drop type post_status;
+drop type audience_kind;
+drop type notification_kind;
+drop type attachment_kind;
+drop type processing_state;
+drop type report_reason;
+drop type report_target;
+drop type moderation_action;
+drop type appeal_status;
+drop type word_language;
+drop type word_severity;
+drop type token_purpose;
@@ -2,7 +2,8 @@ CREATE TYPE user_specs AS ENUM (
   'Teacher',
   'Student',
   'Administrator',
-  'Other'
+  'Other',
+  'Parent'
 );
 
 CREATE TYPE subject AS ENUM (
@@ -23,6 +24,89 @@ CREATE TYPE subject AS ENUM (
   'Education'
 );
 
+CREATE TYPE post_status AS ENUM (
+  'Draft',
+  'Scheduled',
+  'Published',
+  'Archived'
+);
+
+CREATE TYPE audience_kind AS ENUM (
+  'Public',
+  'Class',
+  'Grade',
+  'Teachers',
+  'Administrators'
+);
+
+CREATE TYPE notification_kind AS ENUM (
+  'Reply',
+  'CommentOnPost',
+  'Like',
+  'Mention',
+  'Follow',
+  'Moderation',
+  'ParentLink',
+  'Digest'
+);
+
+CREATE TYPE attachment_kind AS ENUM (
+  'Image',
+  'Document'
+);
+
+CREATE TYPE processing_state AS ENUM (
+  'Pending',
+  'Ready',
+  'Failed'
+);
+
+CREATE TYPE report_reason AS ENUM (
+  'Spam',
+  'Harassment',
+  'Inappropriate',
+  'Misinformation',
+  'Other'
+);
+
+CREATE TYPE report_target AS ENUM (
+  'Post',
+  'Comment',
+  'Profile'
+);
+
+CREATE TYPE moderation_action AS ENUM (
+  'Dismiss',
+  'Hide',
+  'Delete',
+  'Warn',
+  'Suspend',
+  'Restore'
+);
+
+CREATE TYPE token_purpose AS ENUM (
+  'EmailVerification',
+  'PasswordReset'
+);
+
+CREATE TYPE word_language AS ENUM (
+  'Uz',
+  'Ru',
+  'En'
+);
+
+CREATE TYPE word_severity AS ENUM (
+  'Mask',
+  'Moderate',
+  'Reject'
+);
+
+CREATE TYPE appeal_status AS ENUM (
+  'Pending',
+  'Accepted',
+  'Rejected'
+);
+
 CREATE TABLE users(
     uuid UUID PRIMARY KEY NOT NULL UNIQUE,
     username NAME NOT NULL UNIQUE,
@@ -33,7 +117,9 @@ CREATE TABLE users(
     user_specs user_specs NOT NULL,
     email VARCHAR(255) NOT NULL,
     birth_date DATE NOT NULL,
-    phone_number VARCHAR(13)
+    phone_number VARCHAR(13),
+    registered_at TimeStamp NOT NULL DEFAULT NOW(),
+    email_verified BOOLEAN NOT NULL DEFAULT FALSE
 );
 
 CREATE TABLE students(
@@ -56,21 +142,61 @@ CREATE TABLE posts(
     uuid UUID PRIMARY KEY NOT NULL,
     title TEXT NOT NULL,
     content TEXT NOT NULL,
+    content_html TEXT NOT NULL,
     published_at TimeStamp NOT NULL DEFAULT NOW(),
     edited BOOLEAN NOT NULL,
     edited_at TimeStamp,
     author NAME NOT NULL REFERENCES users(username),
-    tags TEXT[] NOT NULL
+    tags TEXT[] NOT NULL,
+    status post_status NOT NULL DEFAULT 'Published',
+    publish_at TimeStamp DEFAULT NULL,
+    expires_at TimeStamp DEFAULT NULL,
+    pinned BOOLEAN NOT NULL DEFAULT FALSE,
+    version INTEGER NOT NULL DEFAULT 1,
+    hidden BOOLEAN NOT NULL DEFAULT FALSE,
+    pending BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE TABLE post_audience(
+    post UUID NOT NULL REFERENCES posts(uuid),
+    kind audience_kind NOT NULL,
+    class_num SMALLINT,
+    class_char VARCHAR(1),
+    subject subject
+);
+
+CREATE TABLE post_views(
+    username NAME NOT NULL REFERENCES users(username),
+    post UUID NOT NULL REFERENCES posts(uuid),
+    seen_at TimeStamp NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (username, post)
 );
 
+CREATE TABLE follows(
+    follower NAME NOT NULL REFERENCES users(username),
+    followed NAME NOT NULL REFERENCES users(username),
+    followed_at TimeStamp NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (follower, followed)
+);
+
+CREATE TABLE tag_subscriptions(
+    username NAME NOT NULL REFERENCES users(username),
+    tag TEXT NOT NULL,
+    subscribed_at TimeStamp NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (username, tag)
+);
 
 CREATE TABLE comments(
     uuid UUID PRIMARY KEY NOT NULL,
     written_under UUID NOT NULL REFERENCES posts(uuid),
     content TEXT NOT NULL,
+    content_html TEXT NOT NULL,
     published_at TimeStamp NOT NULL DEFAULT NOW(),
     edited BOOLEAN NOT NULL DEFAULT FALSE,
     edited_at TimeStamp DEFAULT NULL,
+    version INTEGER NOT NULL DEFAULT 1,
+    hidden BOOLEAN NOT NULL DEFAULT FALSE,
+    pending BOOLEAN NOT NULL DEFAULT FALSE,
     author NAME NOT NULL REFERENCES users(username),
     replys_for UUID REFERENCES comments(uuid)
 );
@@ -90,3 +216,165 @@ CREATE TABLE comment_mark(
     liked BOOLEAN NOT NULL
 );
 
+CREATE TABLE notifications(
+    uuid UUID PRIMARY KEY NOT NULL,
+    recipient NAME NOT NULL REFERENCES users(username),
+    kind notification_kind NOT NULL,
+    actor NAME REFERENCES users(username),
+    resource UUID,
+    details TEXT,
+    created_at TimeStamp NOT NULL DEFAULT NOW(),
+    read_at TimeStamp DEFAULT NULL
+);
+
+CREATE TABLE notification_preferences(
+    username NAME NOT NULL REFERENCES users(username),
+    kind notification_kind NOT NULL,
+    enabled BOOLEAN NOT NULL,
+    PRIMARY KEY (username, kind)
+);
+
+CREATE TABLE mentions(
+    resource UUID NOT NULL,
+    post UUID NOT NULL REFERENCES posts(uuid),
+    mentioned NAME NOT NULL REFERENCES users(username),
+    span_start INTEGER NOT NULL,
+    span_end INTEGER NOT NULL
+);
+
+CREATE TABLE attachments(
+    uuid UUID PRIMARY KEY NOT NULL,
+    post UUID NOT NULL REFERENCES posts(uuid),
+    filename TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    kind attachment_kind NOT NULL,
+    size BIGINT NOT NULL,
+    storage_key TEXT NOT NULL,
+    processing processing_state NOT NULL,
+    uploaded_at TimeStamp NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE revisions(
+    resource UUID NOT NULL,
+    number INTEGER NOT NULL,
+    title TEXT,
+    content TEXT NOT NULL,
+    edited_by NAME NOT NULL REFERENCES users(username),
+    created_at TimeStamp NOT NULL,
+    PRIMARY KEY (resource, number)
+);
+
+CREATE TABLE reports(
+    uuid UUID PRIMARY KEY NOT NULL,
+    reporter NAME REFERENCES users(username),
+    target_kind report_target NOT NULL,
+    target TEXT NOT NULL,
+    reason report_reason NOT NULL,
+    details TEXT,
+    created_at TimeStamp NOT NULL DEFAULT NOW(),
+    resolved_at TimeStamp DEFAULT NULL,
+    resolution moderation_action DEFAULT NULL
+);
+
+CREATE UNIQUE INDEX open_reports ON reports(reporter, target_kind, target) WHERE resolved_at IS NULL;
+CREATE UNIQUE INDEX open_policy_reports ON reports(target_kind, target) WHERE resolved_at IS NULL AND reporter IS NULL;
+
+CREATE TABLE moderation_actions(
+    uuid UUID PRIMARY KEY NOT NULL,
+    moderator NAME NOT NULL REFERENCES users(username),
+    target_kind report_target NOT NULL,
+    target TEXT NOT NULL,
+    action moderation_action NOT NULL,
+    note TEXT,
+    created_at TimeStamp NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE suspensions(
+    uuid UUID PRIMARY KEY NOT NULL,
+    username NAME NOT NULL REFERENCES users(username),
+    reason TEXT NOT NULL,
+    issued_by NAME NOT NULL REFERENCES users(username),
+    created_at TimeStamp NOT NULL DEFAULT NOW(),
+    until TimeStamp DEFAULT NULL,
+    lifted_at TimeStamp DEFAULT NULL,
+    appeal TEXT DEFAULT NULL,
+    appeal_status appeal_status DEFAULT NULL,
+    appealed_at TimeStamp DEFAULT NULL
+);
+
+CREATE TABLE banned_words(
+    word TEXT NOT NULL,
+    language word_language NOT NULL,
+    severity word_severity NOT NULL,
+    PRIMARY KEY (word, language)
+);
+
+CREATE TABLE sign_in_attempts(
+    username NAME NOT NULL,
+    ip TEXT,
+    succeeded BOOLEAN NOT NULL,
+    attempted_at TimeStamp NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX sign_in_attempts_by_username ON sign_in_attempts(username, attempted_at);
+CREATE INDEX sign_in_attempts_by_ip ON sign_in_attempts(ip, attempted_at);
+
+CREATE TABLE email_tokens(
+    token_hash TEXT PRIMARY KEY NOT NULL,
+    username NAME NOT NULL REFERENCES users(username),
+    purpose token_purpose NOT NULL,
+    email VARCHAR(255) NOT NULL,
+    created_at TimeStamp NOT NULL DEFAULT NOW(),
+    expires_at TimeStamp NOT NULL,
+    used_at TimeStamp DEFAULT NULL
+);
+
+CREATE TABLE two_factor(
+    username NAME PRIMARY KEY NOT NULL REFERENCES users(username),
+    secret TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    enabled_at TimeStamp DEFAULT NULL,
+    last_step BIGINT DEFAULT NULL
+);
+
+CREATE TABLE recovery_codes(
+    code_hash TEXT PRIMARY KEY NOT NULL,
+    username NAME NOT NULL REFERENCES users(username),
+    used_at TimeStamp DEFAULT NULL
+);
+
+CREATE TABLE two_factor_sessions(
+    token_hash TEXT PRIMARY KEY NOT NULL,
+    username NAME NOT NULL REFERENCES users(username),
+    expires_at TimeStamp NOT NULL
+);
+
+CREATE TABLE parent_links(
+    parent NAME NOT NULL REFERENCES users(username),
+    student NAME NOT NULL REFERENCES users(username),
+    requested_at TimeStamp NOT NULL DEFAULT NOW(),
+    confirmed_by NAME REFERENCES users(username),
+    confirmed_at TimeStamp DEFAULT NULL,
+    PRIMARY KEY (parent, student)
+);
+
+CREATE TABLE parent_digests(
+    parent NAME PRIMARY KEY NOT NULL REFERENCES users(username),
+    sent_at TimeStamp NOT NULL
+);
+
+CREATE TABLE invitations(
+    uuid UUID PRIMARY KEY NOT NULL,
+    code_hash TEXT NOT NULL UNIQUE,
+    created_by NAME NOT NULL REFERENCES users(username),
+    user_specs user_specs,
+    class_num SMALLINT,
+    class_char VARCHAR(1),
+    subject subject,
+    job_title TEXT,
+    max_uses INTEGER NOT NULL,
+    uses INTEGER NOT NULL DEFAULT 0,
+    expires_at TimeStamp NOT NULL,
+    created_at TimeStamp NOT NULL DEFAULT NOW(),
+    revoked BOOLEAN NOT NULL DEFAULT FALSE
+);
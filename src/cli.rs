@@ -0,0 +1,39 @@
+use crate::{dto::ImportCredentials, services::imports};
+use std::io::{Error, ErrorKind};
+
+const USAGE: &str = "usage: news_service import-users <file.csv> [--dry-run] [--invite]";
+
+/// Runs the command given on the command line, `None` if there is none and
+/// the server should start.
+pub async fn run(args: &[String]) -> Option<std::io::Result<()>> {
+    match args.first().map(String::as_str) {
+        Some("import-users") => Some(import_users(&args[1..]).await),
+        Some(_) => Some(Err(Error::new(ErrorKind::InvalidInput, USAGE))),
+        None => None,
+    }
+}
+
+/// Prints the import report as JSON. `--invite` mails the users links to
+/// choose their passwords instead of listing generated ones.
+async fn import_users(args: &[String]) -> std::io::Result<()> {
+    let mut path = None;
+    let mut dry_run = false;
+    let mut credentials = ImportCredentials::Passwords;
+    for arg in args {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            "--invite" => credentials = ImportCredentials::Invitations,
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => return Err(Error::new(ErrorKind::InvalidInput, USAGE)),
+        }
+    }
+    let path = path.ok_or(Error::new(ErrorKind::InvalidInput, USAGE))?;
+    let csv = std::fs::read(path)?;
+    match imports::import(&csv, dry_run, credentials).await {
+        Ok(report) => {
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            Ok(())
+        }
+        Err(err) => Err(Error::other(format!("{:?}", err))),
+    }
+}
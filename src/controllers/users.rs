@@ -1,12 +1,28 @@
 use super::Controller;
 use crate::{
-    dto::SingDTO,
-    models::user::UserModel,
-    prelude::{EditError, Resource},
-    repositories::users::{queries::ChangeQueryParam, UserRepo},
+    dto::{NotificationDTO, SingDTO},
+    models::{notification::NotificationKind, user::UserModel},
+    prelude::{EditError, Resource, ResourceKind},
+    repositories::{
+        comments::{CommentsRepo, PublishError},
+        follows::FollowsRepo,
+        subscriptions::SubscriptionsRepo,
+        suspensions::SuspensionsRepo,
+        users::{queries::ChangeQueryParam, UserRepo},
+    },
+    services::{
+        mentions,
+        notifications::{self, notify},
+        rate_limits,
+        realtime::{self, Channel, MarkEvent, RealtimeEventKind},
+        sign_in,
+        two_factor::{self, TwoFactorError},
+    },
     validators::repository_query::users::{ValidatedChangeQueryParam, ValidationError},
 };
+use chrono::NaiveDateTime;
 use serde::Serialize;
+use uuid::Uuid;
 
 pub struct UserController {
     username: String,
@@ -23,28 +39,137 @@ impl Controller for UserController {
     }
 }
 
+/// Whether the username or the password is wrong isn't told apart.
+/// `TwoFactorRequired` comes only after the password matched, when the code
+/// is missing or wrong.
 #[derive(Serialize)]
 pub enum SingError {
-    WrongUsername,
-    WrongPassword,
+    WrongCredentials,
+    LockedOut { retry_after: u64 },
+    TwoFactorRequired,
+}
+
+/// The post the resource is, or is written under.
+async fn post_of(resource: &dyn Resource) -> Option<Uuid> {
+    match resource.kind() {
+        ResourceKind::Post => Some(resource.uuid()),
+        ResourceKind::Comment => CommentsRepo::get_instance()
+            .await
+            .get_by_uuid(&resource.uuid())
+            .await
+            .map(|comment| comment.under_post().uuid()),
+    }
+}
+
+async fn publish_mark(resource: &dyn Resource, username: String, liked: bool) {
+    if let Some(post) = post_of(resource).await {
+        realtime::publish(
+            Channel::PostComments(post),
+            RealtimeEventKind::NewMark,
+            &MarkEvent {
+                marked: resource.uuid().to_string(),
+                username,
+                liked,
+            },
+        )
+        .await;
+    }
+}
+
+/// Suspended users can still sign in and read.
+#[derive(Serialize)]
+pub enum ActivityError {
+    Suspended {
+        until: Option<NaiveDateTime>,
+        reason: String,
+    },
+}
+
+#[derive(Serialize)]
+pub enum FollowError {
+    UserDoesntExist,
+    FollowsSelf,
+    ProblemsWithDB,
 }
 
 impl UserController {
-    pub async fn sing(sing_data: &SingDTO) -> Result<Self, SingError> {
-        match UserRepo::get_instance()
+    /// Checks the credentials sent along with a request, and the 2FA code if
+    /// the account has it enabled. Failures count towards the lockout of the
+    /// account, the address it comes from and the `login` rate limit.
+    pub async fn sing(sing_data: &SingDTO, ip: Option<&str>) -> Result<Self, SingError> {
+        Self::authenticate(sing_data, ip, false).await
+    }
+
+    /// Signs in from `ip`. Unlike `sing` it puts successful sign-ins in the
+    /// security log.
+    pub async fn sign_in(sing_data: &SingDTO, ip: Option<&str>) -> Result<Self, SingError> {
+        Self::authenticate(sing_data, ip, true).await
+    }
+
+    async fn authenticate(
+        sing_data: &SingDTO,
+        ip: Option<&str>,
+        log_success: bool,
+    ) -> Result<Self, SingError> {
+        if let Some(retry_after) = sign_in::retry_after(&sing_data.username, ip).await {
+            return Err(SingError::LockedOut { retry_after });
+        }
+        let found = UserRepo::get_instance()
             .await
             .get_by_username(&sing_data.username)
-            .await
+            .await;
+        if let Err(limited) =
+            rate_limits::login_limited(&sing_data.username, found.as_ref(), ip).await
         {
+            return Err(SingError::LockedOut {
+                retry_after: limited.retry_after,
+            });
+        }
+        let user = found
+            .as_ref()
+            .filter(|user| sing_data.password == user.password());
+        match user {
             Some(user) => {
-                if sing_data.password != user.password() {
-                    return Err(SingError::WrongPassword);
+                match two_factor::check(&user.username(), sing_data.otp.as_deref()).await {
+                    Ok(_) => {}
+                    Err(TwoFactorError::CodeRequired) => return Err(SingError::TwoFactorRequired),
+                    Err(_) => {
+                        Self::count_failure(&sing_data.username, found.as_ref(), ip).await;
+                        return Err(SingError::TwoFactorRequired);
+                    }
+                }
+                if log_success {
+                    sign_in::record(&user.username(), ip, true).await;
                 }
                 Ok(UserController {
                     username: user.username(),
                 })
             }
-            None => Err(SingError::WrongUsername),
+            None => {
+                Self::count_failure(&sing_data.username, found.as_ref(), ip).await;
+                Err(SingError::WrongCredentials)
+            }
+        }
+    }
+
+    async fn count_failure(username: &str, user: Option<&UserModel>, ip: Option<&str>) {
+        sign_in::record(username, ip, false).await;
+        // Going over the quota shows up on the next attempt.
+        let _ = rate_limits::count_login(username, user, ip).await;
+    }
+
+    /// Checked before the user publishes, comments, edits or marks anything.
+    pub async fn ensure_active(&self) -> Result<(), ActivityError> {
+        match SuspensionsRepo::get_instance()
+            .await
+            .get_active(&self.username)
+            .await
+        {
+            Some(suspension) => Err(ActivityError::Suspended {
+                until: suspension.until(),
+                reason: suspension.reason(),
+            }),
+            None => Ok(()),
         }
     }
 
@@ -72,16 +197,99 @@ impl UserController {
         Ok(())
     }
 
-    pub async fn comment(&self, resource: &dyn Resource, content: String) {
-        resource.comment(content, &self);
+    /// Comments held back by the content policy or waiting for approval reach
+    /// no one until a moderator lets them through.
+    pub async fn comment(
+        &self,
+        resource: &dyn Resource,
+        content: String,
+    ) -> Result<(), PublishError> {
+        let comment = resource.comment(content, self)?;
+        if comment.is_hidden() || comment.is_pending() {
+            return Ok(());
+        }
+        mentions::sync(
+            comment.uuid(),
+            comment.under_post().uuid(),
+            &comment.contet(),
+            &self.username(),
+        )
+        .await;
+        realtime::comment_published(&comment, resource).await;
+        notifications::commented(&comment, resource).await;
+        Ok(())
     }
 
     pub async fn like(&self, resource: &dyn Resource) {
-        resource.like(&self)
+        resource.like(&self);
+        publish_mark(resource, self.username(), true).await;
+        notify(NotificationDTO {
+            recipient: resource.author().username(),
+            kind: NotificationKind::Like,
+            actor: Some(self.username()),
+            resource: Some(resource.uuid()),
+            details: None,
+        })
+        .await;
     }
 
     pub async fn dislike(&self, resource: &dyn Resource) {
-        resource.dislike(&self)
+        resource.dislike(&self);
+        publish_mark(resource, self.username(), false).await;
+    }
+
+    async fn followed(&self, username: &str) -> Result<UserModel, FollowError> {
+        if self.username == username {
+            return Err(FollowError::FollowsSelf);
+        }
+        UserRepo::get_instance()
+            .await
+            .get_by_username(username)
+            .await
+            .ok_or(FollowError::UserDoesntExist)
+    }
+
+    pub async fn follow(&self, username: &str) -> Result<(), FollowError> {
+        let followed = self.followed(username).await?;
+        FollowsRepo::get_instance()
+            .await
+            .follow(&self.model().await, &followed)
+            .await
+            .map_err(|_| FollowError::ProblemsWithDB)?;
+        notify(NotificationDTO {
+            recipient: followed.username(),
+            kind: NotificationKind::Follow,
+            actor: Some(self.username()),
+            resource: None,
+            details: None,
+        })
+        .await;
+        Ok(())
+    }
+
+    pub async fn unfollow(&self, username: &str) -> Result<(), FollowError> {
+        let followed = self.followed(username).await?;
+        FollowsRepo::get_instance()
+            .await
+            .unfollow(&self.model().await, &followed)
+            .await
+            .map_err(|_| FollowError::ProblemsWithDB)
+    }
+
+    pub async fn subscribe(&self, tag: &str) -> Result<(), FollowError> {
+        SubscriptionsRepo::get_instance()
+            .await
+            .subscribe(&self.model().await, tag)
+            .await
+            .map_err(|_| FollowError::ProblemsWithDB)
+    }
+
+    pub async fn unsubscribe(&self, tag: &str) -> Result<(), FollowError> {
+        SubscriptionsRepo::get_instance()
+            .await
+            .unsubscribe(&self.model().await, tag)
+            .await
+            .map_err(|_| FollowError::ProblemsWithDB)
     }
 
     pub fn is_owner_of(&self, resource: &dyn Resource) -> bool {
@@ -96,9 +304,13 @@ impl UserController {
         &self,
         resource: &dyn Resource,
         content: String,
+        expected_version: i32,
     ) -> Result<(), crate::prelude::EditError> {
         if self.is_owner_of(resource) {
-            resource.edit(&content, self);
+            resource.edit(&content, self, expected_version)?;
+            if let Some(post) = post_of(resource).await {
+                mentions::sync(resource.uuid(), post, &content, &self.username()).await;
+            }
             return Ok(());
         }
         return Err(EditError::EditsNotAuthor);
@@ -1,10 +1,15 @@
 use crate::models::{
+    attachment::{AttachmentKind, ProcessingState},
     comment::CommentModel,
+    notification::NotificationKind,
     post::PostModel,
+    report::{ModerationAction, ReportReason, ReportTargetKind},
     user::{UserModel, UserType},
 };
-use chrono::NaiveDate;
+use crate::types::{Audience, PostStatus};
+use chrono::{NaiveDate, NaiveDateTime};
 use serde::Deserialize;
+use uuid::Uuid;
 
 pub struct PublishCommentDTO {
     pub content: String,
@@ -23,12 +28,40 @@ pub enum SortDirectionDTO {
 pub struct PublishPostJSON {
     pub title: String,
     pub content: String,
+    pub draft: Option<bool>,
+    pub publish_at: Option<NaiveDateTime>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub audience: Option<Vec<Audience>>,
+}
+
+impl PublishPostJSON {
+    pub fn status(&self) -> PostStatus {
+        match (self.draft, self.publish_at) {
+            (Some(true), _) => PostStatus::Draft,
+            (_, Some(publish_at)) => PostStatus::Scheduled { publish_at },
+            _ => PostStatus::Published,
+        }
+    }
 }
 
 pub struct PublishPostDTO {
     pub content: String,
     pub author: UserModel,
     pub title: String,
+    pub status: PostStatus,
+    pub expires_at: Option<NaiveDateTime>,
+    pub audience: Vec<Audience>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct EditDraftJSON {
+    pub title: Option<String>,
+    pub content: Option<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct PublishDraftJSON {
+    pub publish_at: Option<NaiveDateTime>,
 }
 
 #[derive(Clone, Deserialize)]
@@ -41,11 +74,174 @@ pub struct UserRegistrationDTO {
     pub about: String,
     pub password: String,
     pub email: String,
-    pub phone_number: String,
+    pub phone_number: Option<String>,
+    /// Required when registration is invite-only, `user_specs` then has to
+    /// be what the invitation was made for.
+    #[serde(default)]
+    pub invitation: Option<String>,
 }
 
+/// An invitation for `max_uses` registrations, one if it's left out. Users
+/// registering with it get `user_specs`, or pick any type but administrator
+/// if it's left out.
+#[derive(Deserialize, Clone)]
+pub struct InvitationJSON {
+    #[serde(default)]
+    pub user_specs: Option<UserType>,
+    #[serde(default)]
+    pub max_uses: Option<i32>,
+    pub expires_at: NaiveDateTime,
+}
+
+/// Accounts with 2FA enabled send the current code from their authenticator,
+/// one of their recovery codes or the token of a 2FA session as `otp`. Codes
+/// are accepted once, so clients making several requests open a session.
 #[derive(Clone, Deserialize)]
 pub struct SingDTO {
     pub username: String,
     pub password: String,
+    #[serde(default)]
+    pub otp: Option<String>,
+}
+
+pub struct AttachmentDTO {
+    pub uuid: Uuid,
+    pub post: Uuid,
+    pub filename: String,
+    pub content_type: String,
+    pub kind: AttachmentKind,
+    pub size: i64,
+    pub storage_key: String,
+    pub processing: ProcessingState,
+}
+
+pub struct RevisionDTO {
+    pub resource: Uuid,
+    pub title: Option<String>,
+    pub content: String,
+    pub edited_by: String,
+    pub created_at: NaiveDateTime,
+}
+
+pub struct NotificationDTO {
+    pub recipient: String,
+    pub kind: NotificationKind,
+    pub actor: Option<String>,
+    pub resource: Option<Uuid>,
+    pub details: Option<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub enum SubscriptionJSON {
+    PostComments(String),
+    Notifications,
+    Tag(String),
+}
+
+/// Posts and comments are named by uuid, profiles by username.
+#[derive(Deserialize, Clone)]
+pub enum ReportTarget {
+    Post(String),
+    Comment(String),
+    Profile(String),
+}
+
+impl ReportTarget {
+    pub fn kind(&self) -> ReportTargetKind {
+        match self {
+            ReportTarget::Post(_) => ReportTargetKind::Post,
+            ReportTarget::Comment(_) => ReportTargetKind::Comment,
+            ReportTarget::Profile(_) => ReportTargetKind::Profile,
+        }
+    }
+
+    pub fn id(&self) -> String {
+        match self {
+            ReportTarget::Post(id) | ReportTarget::Comment(id) | ReportTarget::Profile(id) => {
+                id.clone()
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ReportJSON {
+    pub target: ReportTarget,
+    pub reason: ReportReason,
+    pub details: Option<String>,
+}
+
+pub struct ReportDTO {
+    pub reporter: Option<String>,
+    pub target: ReportTarget,
+    pub reason: ReportReason,
+    pub details: Option<String>,
+}
+
+/// Rejected items are deleted, `note` tells the author why.
+#[derive(Deserialize, Clone)]
+pub struct PremoderationJSON {
+    pub approve: bool,
+    pub note: Option<String>,
+}
+
+/// `until` only matters for `Suspend`, leaving it out bans the author.
+#[derive(Deserialize, Clone)]
+pub struct ModerationJSON {
+    pub action: ModerationAction,
+    pub note: Option<String>,
+    pub until: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct SuspendJSON {
+    pub username: String,
+    pub reason: String,
+    pub until: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct AppealJSON {
+    pub text: String,
+}
+
+/// `login` is either a username or an email address.
+#[derive(Deserialize, Clone)]
+pub struct PasswordResetRequestJSON {
+    pub login: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct PasswordResetJSON {
+    pub token: String,
+    pub password: String,
+}
+
+/// How users created by an import get to their accounts.
+#[derive(Deserialize, Clone, Copy, Default)]
+pub enum ImportCredentials {
+    /// Generated passwords, listed in the import report.
+    #[default]
+    Passwords,
+    /// A mailed link to choose a password.
+    Invitations,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ImportJSON {
+    pub csv: String,
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub credentials: ImportCredentials,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct TwoFactorCodeJSON {
+    pub code: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct AppealDecisionJSON {
+    pub accepted: bool,
 }
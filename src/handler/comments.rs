@@ -0,0 +1,80 @@
+use super::rate_limits::{client_ip, sing_failed_responce};
+use super::{
+    posts::viewer,
+    preconditions::{cached_responce, CachedRoute, Conditions},
+};
+use crate::{
+    dto::SingDTO,
+    prelude::Editable,
+    repositories::{comments::CommentsRepo, posts::PostsRepo},
+    services::two_factor,
+};
+use actix_web::{
+    get,
+    http::header::{IfModifiedSince, IfNoneMatch},
+    web::{Header, Json, Path},
+    HttpRequest, HttpResponse, Responder, Scope,
+};
+use std::str::FromStr;
+use uuid::Uuid;
+
+pub fn comments_scope() -> Scope {
+    Scope::new("/comments").service(get_comment)
+}
+
+/// Comments are visible to whoever can see the post they are written under.
+#[get("/{comment_uuid}")]
+async fn get_comment(
+    req: HttpRequest,
+    path: Path<String>,
+    sing_dto: Option<Json<SingDTO>>,
+    if_none_match: Option<Header<IfNoneMatch>>,
+    if_modified_since: Option<Header<IfModifiedSince>>,
+) -> impl Responder {
+    let viewer = match viewer(sing_dto, client_ip(&req).as_deref()).await {
+        Ok(viewer) => viewer,
+        Err(err) => return sing_failed_responce(err),
+    };
+    let comment_uuid = match Uuid::from_str(&path) {
+        Ok(uuid) => uuid,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+    let comment = match CommentsRepo::get_instance()
+        .await
+        .get_by_uuid(&comment_uuid)
+        .await
+    {
+        Some(comment) => comment,
+        None => return HttpResponse::NotFound().finish(),
+    };
+    let private = viewer.is_some();
+    let moderates = match &viewer {
+        Some(viewer) => {
+            viewer.username() == comment.author_username()
+                || two_factor::acts_as_administrator(viewer).await
+        }
+        None => false,
+    };
+    if (comment.is_hidden() || comment.is_pending()) && !moderates {
+        return HttpResponse::NotFound().finish();
+    }
+    if PostsRepo::get_instance()
+        .await
+        .get_visible(comment.under_post().uuid(), viewer)
+        .await
+        .is_none()
+    {
+        return HttpResponse::NotFound().finish();
+    }
+    cached_responce(
+        CachedRoute::Comment,
+        Conditions {
+            if_none_match,
+            if_modified_since,
+        },
+        &comment,
+        Some(comment.version()),
+        Some(comment.last_modified()),
+        private,
+    )
+}
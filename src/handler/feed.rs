@@ -0,0 +1,95 @@
+use super::rate_limits::{client_ip, sing_failed_responce};
+use crate::{
+    controllers::{users::UserController, Controller},
+    dto::SingDTO,
+    models::post::PostModel,
+    repositories::posts::PostsRepo,
+    types::{FeedCursor, FeedRanking},
+};
+use actix_web::{
+    get,
+    web::{Json, Query},
+    HttpRequest, HttpResponse, Responder, Scope,
+};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+lazy_static! {
+    static ref DEFAULT_RANKING: FeedRanking = FeedRanking {
+        recency: ranking_weight("FEED_RECENCY_WEIGHT", 1.0),
+        raiting: ranking_weight("FEED_RAITING_WEIGHT", 1.0),
+        pinned: ranking_weight("FEED_PINNED_WEIGHT", 10.0),
+        seen: ranking_weight("FEED_SEEN_PENALTY", 2.0),
+    };
+}
+
+fn ranking_weight(name: &str, default: f64) -> f64 {
+    dotenv::var(name).map_or(default, |weight| {
+        weight
+            .parse()
+            .ok()
+            .filter(|weight: &f64| weight.is_finite())
+            .unwrap_or_else(|| panic!("{} must be a finite number", name))
+    })
+}
+
+#[derive(Deserialize)]
+struct FeedQueryParams {
+    limit: Option<u8>,
+    cursor: Option<String>,
+    recency_weight: Option<f64>,
+    raiting_weight: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct FeedPage {
+    posts: Vec<PostModel>,
+    next_cursor: Option<String>,
+}
+
+pub fn feed_scope() -> Scope {
+    Scope::new("/feed").service(feed)
+}
+
+#[get("")]
+async fn feed(
+    req: HttpRequest,
+    query: Query<FeedQueryParams>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    let viewer = match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller.model().await,
+        Err(err) => return sing_failed_responce(err),
+    };
+    let cursor = match query.cursor.as_deref().map(FeedCursor::from_str) {
+        Some(Ok(cursor)) => Some(cursor),
+        Some(Err(_)) => return HttpResponse::BadRequest().finish(),
+        None => None,
+    };
+    if [query.recency_weight, query.raiting_weight]
+        .iter()
+        .flatten()
+        .any(|weight| !weight.is_finite())
+    {
+        return HttpResponse::BadRequest().finish();
+    }
+    let ranking = FeedRanking {
+        recency: query.recency_weight.unwrap_or(DEFAULT_RANKING.recency),
+        raiting: query.raiting_weight.unwrap_or(DEFAULT_RANKING.raiting),
+        ..*DEFAULT_RANKING
+    };
+    let limit = query.limit.map_or(25, |limit| limit.into());
+    let page = PostsRepo::get_instance()
+        .await
+        .get_feed(&viewer, &ranking, cursor, limit)
+        .await;
+    let next_cursor = match page.last() {
+        Some((_, cursor)) if page.len() as u32 == limit => Some(cursor.to_string()),
+        _ => None,
+    };
+    HttpResponse::Ok().json(FeedPage {
+        posts: page.into_iter().map(|(post, _)| post).collect(),
+        next_cursor,
+    })
+}
@@ -0,0 +1,63 @@
+use super::rate_limits::{client_ip, sing_failed_responce};
+use super::{
+    posts::{attachment_error_responce, find_attachment, viewer},
+    preconditions::CachedRoute,
+};
+use crate::{
+    dto::SingDTO,
+    models::attachment::ImageVariant,
+    repositories::posts::PostsRepo,
+    services::attachments::{self, AttachmentError},
+};
+use actix_web::{
+    get,
+    http::header::{CacheControl, CacheDirective},
+    web::{Json, Path},
+    HttpRequest, HttpResponse, Responder, Scope,
+};
+use std::str::FromStr;
+
+pub fn media_scope() -> Scope {
+    Scope::new("/media").service(variant)
+}
+
+/// Variants are only cached by the viewer's browser and only briefly, the
+/// post they belong to may be hidden, restricted or deleted later.
+#[get("/{attachment_uuid}/{variant}")]
+async fn variant(
+    req: HttpRequest,
+    path: Path<(String, String)>,
+    sing_dto: Option<Json<SingDTO>>,
+) -> impl Responder {
+    let (attachment_uuid, variant) = path.into_inner();
+    let variant = match ImageVariant::from_str(&variant) {
+        Ok(variant) => variant,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+    let viewer = match viewer(sing_dto, client_ip(&req).as_deref()).await {
+        Ok(viewer) => viewer,
+        Err(err) => return sing_failed_responce(err),
+    };
+    let attachment = match find_attachment(&attachment_uuid).await {
+        Ok(attachment) => attachment,
+        Err(err) => return attachment_error_responce(err),
+    };
+    if PostsRepo::get_instance()
+        .await
+        .get_visible(attachment.post(), viewer)
+        .await
+        .is_none()
+    {
+        return attachment_error_responce(AttachmentError::AttachmentDoesntExist);
+    }
+    match attachments::content(&attachment, variant).await {
+        Ok(content) => HttpResponse::Ok()
+            .content_type(attachment.content_type())
+            .insert_header(CacheControl(vec![
+                CacheDirective::Private,
+                CacheDirective::MaxAge(CachedRoute::Media.max_age()),
+            ]))
+            .body(content),
+        Err(err) => attachment_error_responce(err),
+    }
+}
@@ -1,2 +1,11 @@
+pub mod comments;
+pub mod feed;
+pub mod media;
+pub mod moderation;
+pub mod notifications;
 pub mod posts;
+mod preconditions;
+mod rate_limits;
+pub mod realtime;
+pub mod revisions;
 pub mod users;
@@ -0,0 +1,585 @@
+use super::rate_limits::{client_ip, rate_limited_responce, sing_failed_responce};
+use super::users::parent_link_error_responce;
+use crate::{
+    controllers::{
+        users::{SingError, UserController},
+        Controller,
+    },
+    dto::{
+        AppealDecisionJSON, ImportJSON, InvitationJSON, ModerationJSON, PremoderationJSON,
+        ReportJSON, ReportTarget, SingDTO, SuspendJSON,
+    },
+    models::{
+        banned_word::{BannedWord, Language},
+        report::ReportTargetKind,
+        suspension::SuspensionModel,
+        user::UserModel,
+    },
+    repositories::{
+        banned_words::BannedWordsRepo, invitations::InvitationsRepo, moderation::ModerationRepo,
+        parent_links::ParentLinksRepo, suspensions::SuspensionsRepo,
+    },
+    services::{
+        imports::{self, ImportFailure},
+        invitations::{self, InvitationError},
+        moderation::{self, ModerationError},
+        parents,
+        premoderation::{self, PremoderationError},
+        rate_limits::RateLimited,
+        suspensions::{self, SuspensionError},
+        two_factor,
+    },
+    types::Limit,
+};
+use actix_web::{
+    delete, get, post, put,
+    web::{Json, Path, Query},
+    HttpRequest, HttpResponse, Responder, Scope,
+};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+struct ModerationQueryParams {
+    limit: Option<u8>,
+    page: Option<u32>,
+    target_kind: Option<ReportTargetKind>,
+}
+
+impl ModerationQueryParams {
+    fn limit(&self) -> Limit {
+        let limit = self.limit.map_or(25, |limit| limit.into());
+        Limit {
+            limit,
+            offset: Some(self.page.unwrap_or(0) * limit),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct BannedWordsQueryParams {
+    language: Option<Language>,
+}
+
+#[derive(Serialize)]
+enum AccessError {
+    Sing(SingError),
+    NotAdministrator,
+    TwoFactorRequired,
+}
+
+pub fn moderation_scope() -> Scope {
+    Scope::new("/moderation")
+        .service(report)
+        .service(queue)
+        .service(reports)
+        .service(resolve)
+        .service(actions)
+        .service(active_suspensions)
+        .service(suspend)
+        .service(lift_suspension)
+        .service(appeals)
+        .service(decide_appeal)
+        .service(pending)
+        .service(decide_pending)
+        .service(banned_words)
+        .service(set_banned_word)
+        .service(delete_banned_word)
+        .service(import_users)
+        .service(invitations_list)
+        .service(create_invitation)
+        .service(revoke_invitation)
+        .service(parent_links)
+        .service(confirm_parent_link)
+        .service(remove_parent_link)
+}
+
+/// Users the policy makes use 2FA can't moderate until they enabled it.
+async fn enforce_two_factor(user: UserModel) -> Result<UserModel, AccessError> {
+    if two_factor::required(&user) && !two_factor::is_enabled(&user.username()).await {
+        return Err(AccessError::TwoFactorRequired);
+    }
+    Ok(user)
+}
+
+async fn administrator(sing_dto: &SingDTO, ip: Option<&str>) -> Result<UserModel, AccessError> {
+    let user = UserController::sing(sing_dto, ip)
+        .await
+        .map_err(AccessError::Sing)?
+        .model()
+        .await;
+    if !user.user_specs().is_administrator() {
+        return Err(AccessError::NotAdministrator);
+    }
+    enforce_two_factor(user).await
+}
+
+/// Pending content may be approved by teachers as well.
+async fn approver(sing_dto: &SingDTO, ip: Option<&str>) -> Result<UserModel, AccessError> {
+    let user = UserController::sing(sing_dto, ip)
+        .await
+        .map_err(AccessError::Sing)?
+        .model()
+        .await;
+    let user_specs = user.user_specs();
+    if !user_specs.is_administrator() && !user_specs.is_teacher() {
+        return Err(AccessError::NotAdministrator);
+    }
+    enforce_two_factor(user).await
+}
+
+fn access_error_responce(err: AccessError) -> HttpResponse {
+    match err {
+        AccessError::Sing(SingError::LockedOut { retry_after }) => {
+            rate_limited_responce(RateLimited { retry_after })
+        }
+        AccessError::Sing(_) => HttpResponse::BadRequest().json(err),
+        AccessError::NotAdministrator | AccessError::TwoFactorRequired => {
+            HttpResponse::Forbidden().json(err)
+        }
+    }
+}
+
+fn moderation_error_responce(err: ModerationError) -> HttpResponse {
+    match err {
+        ModerationError::TargetDoesntExist => HttpResponse::NotFound().json(err),
+        ModerationError::AlreadyReported => HttpResponse::Conflict().json(err),
+        ModerationError::ActionNotApplicable => HttpResponse::BadRequest().json(err),
+        ModerationError::ProblemsWithDB => HttpResponse::InternalServerError().json(err),
+    }
+}
+
+fn target(target_kind: ReportTargetKind, target: String) -> ReportTarget {
+    match target_kind {
+        ReportTargetKind::Post => ReportTarget::Post(target),
+        ReportTargetKind::Comment => ReportTarget::Comment(target),
+        ReportTargetKind::Profile => ReportTarget::Profile(target),
+    }
+}
+
+#[post("/reports")]
+async fn report(req: HttpRequest, json: Json<(ReportJSON, SingDTO)>) -> impl Responder {
+    let (report, sing_data) = json.clone();
+    let reporter = match UserController::sing(&sing_data, client_ip(&req).as_deref()).await {
+        Ok(reporter) => reporter.model().await,
+        Err(err) => return sing_failed_responce(err),
+    };
+    match moderation::report(&reporter, report).await {
+        Ok(report) => HttpResponse::Created().json(report),
+        Err(err) => moderation_error_responce(err),
+    }
+}
+
+#[get("/queue")]
+async fn queue(
+    req: HttpRequest,
+    query: Query<ModerationQueryParams>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    if let Err(err) = administrator(&sing_dto, client_ip(&req).as_deref()).await {
+        return access_error_responce(err);
+    }
+    HttpResponse::Ok().json(
+        ModerationRepo::get_instance()
+            .await
+            .get_queue(query.limit())
+            .await,
+    )
+}
+
+#[get("/queue/{target_kind}/{target}")]
+async fn reports(
+    req: HttpRequest,
+    path: Path<(ReportTargetKind, String)>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    if let Err(err) = administrator(&sing_dto, client_ip(&req).as_deref()).await {
+        return access_error_responce(err);
+    }
+    let (target_kind, id) = path.into_inner();
+    HttpResponse::Ok().json(
+        ModerationRepo::get_instance()
+            .await
+            .get_open_reports(&target(target_kind, id))
+            .await,
+    )
+}
+
+#[post("/queue/{target_kind}/{target}")]
+async fn resolve(
+    req: HttpRequest,
+    path: Path<(ReportTargetKind, String)>,
+    json: Json<(ModerationJSON, SingDTO)>,
+) -> impl Responder {
+    let (decision, sing_data) = json.clone();
+    let moderator = match administrator(&sing_data, client_ip(&req).as_deref()).await {
+        Ok(moderator) => moderator,
+        Err(err) => return access_error_responce(err),
+    };
+    let (target_kind, id) = path.into_inner();
+    match moderation::resolve(&moderator, target(target_kind, id), decision).await {
+        Ok(action) => HttpResponse::Accepted().json(action),
+        Err(err) => moderation_error_responce(err),
+    }
+}
+
+/// Log of every moderation action, the latest first.
+#[get("/actions")]
+async fn actions(
+    req: HttpRequest,
+    query: Query<ModerationQueryParams>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    if let Err(err) = administrator(&sing_dto, client_ip(&req).as_deref()).await {
+        return access_error_responce(err);
+    }
+    HttpResponse::Ok().json(
+        ModerationRepo::get_instance()
+            .await
+            .get_actions(query.target_kind, query.limit())
+            .await,
+    )
+}
+
+pub(super) fn suspension_error_responce(err: SuspensionError) -> HttpResponse {
+    match err {
+        SuspensionError::UserDoesntExist
+        | SuspensionError::SuspensionDoesntExist
+        | SuspensionError::NotSuspended
+        | SuspensionError::AppealDoesntExist => HttpResponse::NotFound().json(err),
+        SuspensionError::SuspendsAdministrator => HttpResponse::Forbidden().json(err),
+        SuspensionError::AlreadyAppealed => HttpResponse::Conflict().json(err),
+        SuspensionError::ProblemsWithDB => HttpResponse::InternalServerError().json(err),
+    }
+}
+
+async fn find_suspension(suspension_uuid: &str) -> Result<SuspensionModel, SuspensionError> {
+    let suspension_uuid =
+        Uuid::from_str(suspension_uuid).map_err(|_| SuspensionError::SuspensionDoesntExist)?;
+    SuspensionsRepo::get_instance()
+        .await
+        .get_by_uuid(&suspension_uuid)
+        .await
+        .ok_or(SuspensionError::SuspensionDoesntExist)
+}
+
+#[get("/suspensions")]
+async fn active_suspensions(
+    req: HttpRequest,
+    query: Query<ModerationQueryParams>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    if let Err(err) = administrator(&sing_dto, client_ip(&req).as_deref()).await {
+        return access_error_responce(err);
+    }
+    HttpResponse::Ok().json(
+        SuspensionsRepo::get_instance()
+            .await
+            .get_all_active(query.limit())
+            .await,
+    )
+}
+
+#[post("/suspensions")]
+async fn suspend(req: HttpRequest, json: Json<(SuspendJSON, SingDTO)>) -> impl Responder {
+    let (suspension, sing_data) = json.clone();
+    let moderator = match administrator(&sing_data, client_ip(&req).as_deref()).await {
+        Ok(moderator) => moderator,
+        Err(err) => return access_error_responce(err),
+    };
+    match suspensions::suspend(
+        &moderator,
+        &suspension.username,
+        &suspension.reason,
+        suspension.until,
+    )
+    .await
+    {
+        Ok(suspension) => HttpResponse::Created().json(suspension),
+        Err(err) => suspension_error_responce(err),
+    }
+}
+
+#[delete("/suspensions/{suspension_uuid}")]
+async fn lift_suspension(
+    req: HttpRequest,
+    path: Path<String>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    if let Err(err) = administrator(&sing_dto, client_ip(&req).as_deref()).await {
+        return access_error_responce(err);
+    }
+    let suspension = match find_suspension(&path).await {
+        Ok(suspension) => suspension,
+        Err(err) => return suspension_error_responce(err),
+    };
+    match suspensions::lift(&suspension).await {
+        Ok(_) => HttpResponse::Accepted().finish(),
+        Err(err) => suspension_error_responce(err),
+    }
+}
+
+#[get("/appeals")]
+async fn appeals(
+    req: HttpRequest,
+    query: Query<ModerationQueryParams>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    if let Err(err) = administrator(&sing_dto, client_ip(&req).as_deref()).await {
+        return access_error_responce(err);
+    }
+    HttpResponse::Ok().json(
+        SuspensionsRepo::get_instance()
+            .await
+            .get_pending_appeals(query.limit())
+            .await,
+    )
+}
+
+#[post("/appeals/{suspension_uuid}")]
+async fn decide_appeal(
+    req: HttpRequest,
+    path: Path<String>,
+    json: Json<(AppealDecisionJSON, SingDTO)>,
+) -> impl Responder {
+    let (decision, sing_data) = json.clone();
+    if let Err(err) = administrator(&sing_data, client_ip(&req).as_deref()).await {
+        return access_error_responce(err);
+    }
+    let suspension = match find_suspension(&path).await {
+        Ok(suspension) => suspension,
+        Err(err) => return suspension_error_responce(err),
+    };
+    match suspensions::decide_appeal(&suspension, decision.accepted).await {
+        Ok(_) => HttpResponse::Accepted().finish(),
+        Err(err) => suspension_error_responce(err),
+    }
+}
+
+#[get("/pending")]
+async fn pending(
+    req: HttpRequest,
+    query: Query<ModerationQueryParams>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    let moderator = match approver(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(moderator) => moderator,
+        Err(err) => return access_error_responce(err),
+    };
+    HttpResponse::Ok().json(premoderation::pending(&moderator, None, query.limit()).await)
+}
+
+#[post("/pending/{item_uuid}")]
+async fn decide_pending(
+    req: HttpRequest,
+    path: Path<String>,
+    json: Json<(PremoderationJSON, SingDTO)>,
+) -> impl Responder {
+    let (decision, sing_data) = json.clone();
+    let moderator = match approver(&sing_data, client_ip(&req).as_deref()).await {
+        Ok(moderator) => moderator,
+        Err(err) => return access_error_responce(err),
+    };
+    let item = match Uuid::from_str(&path) {
+        Ok(uuid) => uuid,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+    match premoderation::decide(&moderator, item, decision).await {
+        Ok(_) => HttpResponse::Accepted().finish(),
+        Err(err @ PremoderationError::ItemDoesntExist) => HttpResponse::NotFound().json(err),
+        Err(err @ PremoderationError::NotPending) => HttpResponse::Conflict().json(err),
+        Err(err @ PremoderationError::OwnItem) => HttpResponse::Forbidden().json(err),
+        Err(err @ PremoderationError::ProblemsWithDB) => {
+            HttpResponse::InternalServerError().json(err)
+        }
+    }
+}
+
+#[get("/words")]
+async fn banned_words(
+    req: HttpRequest,
+    query: Query<BannedWordsQueryParams>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    if let Err(err) = administrator(&sing_dto, client_ip(&req).as_deref()).await {
+        return access_error_responce(err);
+    }
+    let repo = BannedWordsRepo::get_instance().await;
+    HttpResponse::Ok().json(match query.language {
+        Some(language) => repo.get_by_language(language).await,
+        None => repo.get_all().await,
+    })
+}
+
+/// Adds a word to the list of its language or changes its severity.
+#[put("/words")]
+async fn set_banned_word(req: HttpRequest, json: Json<(BannedWord, SingDTO)>) -> impl Responder {
+    let (word, sing_data) = json.clone();
+    if let Err(err) = administrator(&sing_data, client_ip(&req).as_deref()).await {
+        return access_error_responce(err);
+    }
+    if word.word.trim_end_matches('*').trim().is_empty() {
+        return HttpResponse::BadRequest().finish();
+    }
+    match BannedWordsRepo::get_instance().await.set(&word).await {
+        Ok(_) => HttpResponse::Accepted().json(word),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[delete("/words/{language}/{word}")]
+async fn delete_banned_word(
+    req: HttpRequest,
+    path: Path<(Language, String)>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    if let Err(err) = administrator(&sing_dto, client_ip(&req).as_deref()).await {
+        return access_error_responce(err);
+    }
+    let (language, word) = path.into_inner();
+    match BannedWordsRepo::get_instance()
+        .await
+        .delete(&word, language)
+        .await
+    {
+        Ok(0) => HttpResponse::NotFound().finish(),
+        Ok(_) => HttpResponse::Accepted().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// Parent links nobody confirmed yet.
+#[get("/parent_links")]
+async fn parent_links(
+    req: HttpRequest,
+    query: Query<ModerationQueryParams>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    if let Err(err) = administrator(&sing_dto, client_ip(&req).as_deref()).await {
+        return access_error_responce(err);
+    }
+    HttpResponse::Ok().json(
+        ParentLinksRepo::get_instance()
+            .await
+            .get_unconfirmed(query.limit())
+            .await,
+    )
+}
+
+#[post("/parent_links/{parent}/{student}")]
+async fn confirm_parent_link(
+    req: HttpRequest,
+    path: Path<(String, String)>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    let moderator = match administrator(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(moderator) => moderator,
+        Err(err) => return access_error_responce(err),
+    };
+    let (parent, student) = path.into_inner();
+    match parents::confirm_link(&moderator, &parent, &student).await {
+        Ok(_) => HttpResponse::Accepted().finish(),
+        Err(err) => parent_link_error_responce(err),
+    }
+}
+
+#[delete("/parent_links/{parent}/{student}")]
+async fn remove_parent_link(
+    req: HttpRequest,
+    path: Path<(String, String)>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    let moderator = match administrator(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(moderator) => moderator,
+        Err(err) => return access_error_responce(err),
+    };
+    let (parent, student) = path.into_inner();
+    match parents::remove_link(&moderator, &parent, &student).await {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(err) => parent_link_error_responce(err),
+    }
+}
+
+/// Registers students and teachers from a CSV, see `imports::import` for its
+/// columns.
+#[post("/users/import")]
+async fn import_users(req: HttpRequest, json: Json<(ImportJSON, SingDTO)>) -> impl Responder {
+    let (import, sing_data) = json.into_inner();
+    if let Err(err) = administrator(&sing_data, client_ip(&req).as_deref()).await {
+        return access_error_responce(err);
+    }
+    match imports::import(import.csv.as_bytes(), import.dry_run, import.credentials).await {
+        Ok(import_report) if import.dry_run => HttpResponse::Ok().json(import_report),
+        Ok(import_report) => HttpResponse::Created().json(import_report),
+        Err(err @ ImportFailure::Unreadable(_)) => HttpResponse::BadRequest().json(err),
+        Err(err @ (ImportFailure::ProblemsWithRandom | ImportFailure::ProblemsWithDB)) => {
+            HttpResponse::InternalServerError().json(err)
+        }
+    }
+}
+
+fn invitation_error_responce(err: InvitationError) -> HttpResponse {
+    match err {
+        InvitationError::InvalidMaxUses | InvitationError::AlreadyExpired => {
+            HttpResponse::BadRequest().json(err)
+        }
+        InvitationError::InvitationDoesntExist => HttpResponse::NotFound().json(err),
+        InvitationError::ProblemsWithRandom | InvitationError::ProblemsWithDB => {
+            HttpResponse::InternalServerError().json(err)
+        }
+    }
+}
+
+#[get("/invitations")]
+async fn invitations_list(
+    req: HttpRequest,
+    query: Query<ModerationQueryParams>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    if let Err(err) = administrator(&sing_dto, client_ip(&req).as_deref()).await {
+        return access_error_responce(err);
+    }
+    HttpResponse::Ok().json(
+        InvitationsRepo::get_instance()
+            .await
+            .get_all(query.limit())
+            .await,
+    )
+}
+
+/// Responds with the code, it isn't shown again.
+#[post("/invitations")]
+async fn create_invitation(
+    req: HttpRequest,
+    json: Json<(InvitationJSON, SingDTO)>,
+) -> impl Responder {
+    let (invitation, sing_data) = json.into_inner();
+    let moderator = match administrator(&sing_data, client_ip(&req).as_deref()).await {
+        Ok(moderator) => moderator,
+        Err(err) => return access_error_responce(err),
+    };
+    match invitations::create(&moderator, invitation).await {
+        Ok(created) => HttpResponse::Created().json(created),
+        Err(err) => invitation_error_responce(err),
+    }
+}
+
+#[delete("/invitations/{invitation_uuid}")]
+async fn revoke_invitation(
+    req: HttpRequest,
+    path: Path<String>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    if let Err(err) = administrator(&sing_dto, client_ip(&req).as_deref()).await {
+        return access_error_responce(err);
+    }
+    let uuid = match Uuid::from_str(&path) {
+        Ok(uuid) => uuid,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+    match invitations::revoke(&uuid).await {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(err) => invitation_error_responce(err),
+    }
+}
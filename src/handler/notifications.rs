@@ -0,0 +1,146 @@
+use super::rate_limits::{client_ip, sing_failed_responce};
+use crate::{
+    controllers::{users::UserController, Controller},
+    dto::SingDTO,
+    models::notification::NotificationPreference,
+    repositories::notifications::NotificationsRepo,
+    types::Limit,
+};
+use actix_web::{
+    get, patch, post,
+    web::{Json, Path, Query},
+    HttpRequest, HttpResponse, Responder, Scope,
+};
+use serde::Deserialize;
+use std::str::FromStr;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+struct NotificationsQueryParams {
+    limit: Option<u8>,
+    page: Option<u32>,
+    unread: Option<bool>,
+}
+
+pub fn notifications_scope() -> Scope {
+    Scope::new("/notifications")
+        .service(notifications)
+        .service(unread_count)
+        .service(read_all)
+        .service(preferences)
+        .service(change_preferences)
+        .service(read)
+}
+
+#[get("")]
+async fn notifications(
+    req: HttpRequest,
+    query: Query<NotificationsQueryParams>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    let controller = match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller,
+        Err(err) => return sing_failed_responce(err),
+    };
+    let limit = query.limit.map_or(25, |limit| limit.into());
+    let limit = Limit {
+        limit,
+        offset: Some(query.page.unwrap_or(0) * limit),
+    };
+    HttpResponse::Ok().json(
+        NotificationsRepo::get_instance()
+            .await
+            .get_for(
+                &controller.model().await.username(),
+                query.unread.unwrap_or(false),
+                limit,
+            )
+            .await,
+    )
+}
+
+#[get("/unread_count")]
+async fn unread_count(req: HttpRequest, sing_dto: Json<SingDTO>) -> impl Responder {
+    match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => HttpResponse::Ok().json(
+            NotificationsRepo::get_instance()
+                .await
+                .count_unread(&controller.model().await.username())
+                .await,
+        ),
+        Err(err) => sing_failed_responce(err),
+    }
+}
+
+#[post("/read")]
+async fn read_all(req: HttpRequest, sing_dto: Json<SingDTO>) -> impl Responder {
+    let controller = match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller,
+        Err(err) => return sing_failed_responce(err),
+    };
+    match NotificationsRepo::get_instance()
+        .await
+        .mark_read(&controller.model().await.username(), None)
+        .await
+    {
+        Ok(_) => HttpResponse::Accepted().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[post("/{notification_uuid}/read")]
+async fn read(req: HttpRequest, path: Path<String>, sing_dto: Json<SingDTO>) -> impl Responder {
+    let controller = match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller,
+        Err(err) => return sing_failed_responce(err),
+    };
+    let notification_uuid = match Uuid::from_str(&path) {
+        Ok(uuid) => uuid,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+    match NotificationsRepo::get_instance()
+        .await
+        .mark_read(
+            &controller.model().await.username(),
+            Some(notification_uuid),
+        )
+        .await
+    {
+        Ok(0) => HttpResponse::NotFound().finish(),
+        Ok(_) => HttpResponse::Accepted().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[get("/preferences")]
+async fn preferences(req: HttpRequest, sing_dto: Json<SingDTO>) -> impl Responder {
+    match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => HttpResponse::Ok().json(
+            NotificationsRepo::get_instance()
+                .await
+                .get_preferences(&controller.model().await.username())
+                .await,
+        ),
+        Err(err) => sing_failed_responce(err),
+    }
+}
+
+#[patch("/preferences")]
+async fn change_preferences(
+    req: HttpRequest,
+    json: Json<(Vec<NotificationPreference>, SingDTO)>,
+) -> impl Responder {
+    let (changes, sing_data) = json.clone();
+    let controller = match UserController::sing(&sing_data, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller,
+        Err(err) => return sing_failed_responce(err),
+    };
+    let username = controller.model().await.username();
+    let repo = NotificationsRepo::get_instance().await;
+    for preference in changes.iter() {
+        if repo.set_preference(&username, preference).await.is_err() {
+            return HttpResponse::InternalServerError().finish();
+        }
+    }
+    HttpResponse::Accepted().finish()
+}
@@ -1,11 +1,44 @@
+use super::preconditions::{cached_responce, CachedRoute, Conditions};
+use super::rate_limits::{client_ip, rate_limited_responce, sing_failed_responce};
 use crate::{
-    dto::SortDirectionDTO,
-    prelude::SortingDirection,
-    repositories::posts::{GetQueryParam, PostsRepo, SortingParam},
+    controllers::{
+        users::{SingError, UserController},
+        Controller,
+    },
+    dto::{EditDraftJSON, PublishDraftJSON, SingDTO, SortDirectionDTO},
+    models::{
+        attachment::{AttachmentModel, ImageVariant},
+        post::PostModel,
+        user::UserModel,
+    },
+    prelude::{Editable, SortingDirection},
+    repositories::{
+        attachments::AttachmentsRepo,
+        posts::{DraftError, GetQueryParam, PostStatusKind, PostsRepo, SortingParam},
+    },
+    services::{
+        attachments::{self, AttachmentError},
+        mentions,
+        rate_limits::{self, Bucket},
+        realtime::post_published,
+        two_factor,
+    },
     types::Limit,
 };
-use actix_web::{get, HttpResponse, Responder, Scope};
+use actix_multipart::{Field, Multipart};
+use actix_web::{
+    delete, get,
+    http::header::{
+        ContentDisposition, DispositionParam, DispositionType, IfModifiedSince, IfNoneMatch,
+    },
+    patch, post,
+    web::{Header, Json, Path},
+    HttpRequest, HttpResponse, Responder, Scope,
+};
+use futures::StreamExt;
 use serde::Deserialize;
+use std::str::FromStr;
+use uuid::Uuid;
 
 #[derive(Deserialize)]
 struct SearchQueryParams {
@@ -14,14 +47,44 @@ struct SearchQueryParams {
     tags: Vec<String>,
     sort_by: Option<SortingParam>,
     direction: Option<SortDirectionDTO>,
+    archived: Option<bool>,
 }
 
 pub fn posts_scope() -> Scope {
-    Scope::new("/posts").service(search)
+    Scope::new("/posts")
+        .service(search)
+        .service(drafts)
+        .service(edit_draft)
+        .service(publish_draft)
+        .service(get_attachment)
+        .service(delete_attachment)
+        .service(upload_attachment)
+        .service(pin)
+        .service(get_post)
+}
+
+pub(super) async fn viewer(
+    sing_dto: Option<Json<SingDTO>>,
+    ip: Option<&str>,
+) -> Result<Option<UserModel>, SingError> {
+    match sing_dto {
+        Some(sing_dto) => Ok(Some(
+            UserController::sing(&sing_dto, ip).await?.model().await,
+        )),
+        None => Ok(None),
+    }
 }
 
 #[get("/search")]
-async fn search(query: actix_web::web::Query<SearchQueryParams>) -> impl Responder {
+async fn search(
+    req: HttpRequest,
+    query: actix_web::web::Query<SearchQueryParams>,
+    sing_dto: Option<Json<SingDTO>>,
+) -> impl Responder {
+    let viewer = match viewer(sing_dto, client_ip(&req).as_deref()).await {
+        Ok(viewer) => viewer,
+        Err(err) => return sing_failed_responce(err),
+    };
     let tags = query.tags.clone();
     let limit = query.limit.map_or(25, |limit| limit.into());
     let limit = Limit {
@@ -40,10 +103,367 @@ async fn search(query: actix_web::web::Query<SearchQueryParams>) -> impl Respond
         SortDirectionDTO::Increment => SortingDirection::Up(sort_by),
         SortDirectionDTO::Decrement => SortingDirection::Down(sort_by),
     };
+    let visibility = match query.archived {
+        Some(true) => GetQueryParam::Archived(true),
+        _ => GetQueryParam::Status(vec![PostStatusKind::Published]),
+    };
     let responce = PostsRepo::get_instance()
         .await
-        .get_many(vec![GetQueryParam::Tags(tags)], limit, ordering_param)
+        .get_many(
+            vec![
+                GetQueryParam::Tags(tags),
+                visibility,
+                GetQueryParam::VisibleFor(viewer),
+            ],
+            limit,
+            ordering_param,
+        )
         .await;
 
     HttpResponse::Ok().json(responce)
 }
+
+#[get("/drafts")]
+async fn drafts(req: HttpRequest, sing_dto: Json<SingDTO>) -> impl Responder {
+    match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(author) => {
+            HttpResponse::Ok().json(PostsRepo::get_instance().await.get_drafts(&author).await)
+        }
+        Err(err) => sing_failed_responce(err),
+    }
+}
+
+async fn find_draft(draft_uuid: &str, author: &UserController) -> Result<PostModel, DraftError> {
+    let draft_uuid = Uuid::from_str(draft_uuid).map_err(|_| DraftError::DraftDoesntExist)?;
+    let draft = PostsRepo::get_instance()
+        .await
+        .get_by_uuid(draft_uuid)
+        .await
+        .ok_or(DraftError::DraftDoesntExist)?;
+    if !author.is_owner_of(&draft) {
+        return Err(DraftError::EditsNotAuthor);
+    }
+    if !draft.status().is_draft() {
+        return Err(DraftError::NotADraft);
+    }
+    Ok(draft)
+}
+
+fn draft_error_responce(err: DraftError) -> HttpResponse {
+    match err {
+        DraftError::DraftDoesntExist => HttpResponse::NotFound().json(err),
+        DraftError::EditsNotAuthor => HttpResponse::Forbidden().json(err),
+        DraftError::NotADraft => HttpResponse::Conflict().json(err),
+        DraftError::Rejected { .. } => HttpResponse::UnprocessableEntity().json(err),
+        DraftError::ProblemsWithDB => HttpResponse::InternalServerError().json(err),
+    }
+}
+
+#[patch("/drafts/{draft_uuid}")]
+async fn edit_draft(
+    req: HttpRequest,
+    path: Path<String>,
+    json: Json<(EditDraftJSON, SingDTO)>,
+) -> impl Responder {
+    let (edit_dto, sing_data) = json.clone();
+    let author = match UserController::sing(&sing_data, client_ip(&req).as_deref()).await {
+        Ok(author) => author,
+        Err(err) => return sing_failed_responce(err),
+    };
+    if let Err(err) = author.ensure_active().await {
+        return HttpResponse::Forbidden().json(err);
+    }
+    let draft = match find_draft(&path, &author).await {
+        Ok(draft) => draft,
+        Err(err) => return draft_error_responce(err),
+    };
+    match PostsRepo::get_instance()
+        .await
+        .edit_draft(draft, edit_dto.title, edit_dto.content, &author)
+        .await
+    {
+        Ok(draft) => HttpResponse::Accepted().json(draft),
+        Err(err) => draft_error_responce(err),
+    }
+}
+
+#[post("/drafts/{draft_uuid}/publish")]
+async fn publish_draft(
+    req: HttpRequest,
+    path: Path<String>,
+    json: Json<(PublishDraftJSON, SingDTO)>,
+) -> impl Responder {
+    let (publish_dto, sing_data) = json.clone();
+    let ip = client_ip(&req);
+    let author = match UserController::sing(&sing_data, ip.as_deref()).await {
+        Ok(author) => author,
+        Err(err) => return sing_failed_responce(err),
+    };
+    if let Err(err) = author.ensure_active().await {
+        return HttpResponse::Forbidden().json(err);
+    }
+    let model = author.model().await;
+    if let Err(err) = rate_limits::check(Bucket::Publish, Some(&model), ip.as_deref()).await {
+        return rate_limited_responce(err);
+    }
+    let draft = match find_draft(&path, &author).await {
+        Ok(draft) => draft,
+        Err(err) => return draft_error_responce(err),
+    };
+    match PostsRepo::get_instance()
+        .await
+        .publish_draft(draft, publish_dto.publish_at, &author)
+        .await
+    {
+        Ok(post) => {
+            if post.status().is_published() && !post.is_hidden() && !post.is_pending() {
+                mentions::sync_post(&post).await;
+                post_published(&post).await;
+            }
+            HttpResponse::Accepted().json(post)
+        }
+        Err(err) => draft_error_responce(err),
+    }
+}
+
+#[get("/{post_uuid}")]
+async fn get_post(
+    req: HttpRequest,
+    path: Path<String>,
+    sing_dto: Option<Json<SingDTO>>,
+    if_none_match: Option<Header<IfNoneMatch>>,
+    if_modified_since: Option<Header<IfModifiedSince>>,
+) -> impl Responder {
+    let viewer = match viewer(sing_dto, client_ip(&req).as_deref()).await {
+        Ok(viewer) => viewer,
+        Err(err) => return sing_failed_responce(err),
+    };
+    let post_uuid = match Uuid::from_str(&path) {
+        Ok(uuid) => uuid,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+    let repo = PostsRepo::get_instance().await;
+    match repo.get_visible(post_uuid, viewer.clone()).await {
+        Some(post) => {
+            if let Some(viewer) = &viewer {
+                let _ = repo.mark_seen(&post, viewer).await;
+            }
+            cached_responce(
+                CachedRoute::Post,
+                Conditions {
+                    if_none_match,
+                    if_modified_since,
+                },
+                &post,
+                Some(post.version()),
+                Some(post.last_modified()),
+                viewer.is_some(),
+            )
+        }
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+#[post("/{post_uuid}/pinned/{pinned}")]
+async fn pin(req: HttpRequest, path: Path<(String, bool)>, json: Json<SingDTO>) -> impl Responder {
+    let (post_uuid, pinned) = path.clone();
+    let controller = match UserController::sing(&json, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller,
+        Err(err) => return sing_failed_responce(err),
+    };
+    let user = controller.model().await;
+    if !user.user_specs().is_administrator() {
+        return HttpResponse::Forbidden().finish();
+    }
+    if two_factor::required(&user) && !two_factor::is_enabled(&user.username()).await {
+        return HttpResponse::Forbidden().json(SingError::TwoFactorRequired);
+    }
+    let post_uuid = match Uuid::from_str(&post_uuid) {
+        Ok(uuid) => uuid,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+    let repo = PostsRepo::get_instance().await;
+    let post = match repo.get_by_uuid(post_uuid).await {
+        Some(post) => post,
+        None => return HttpResponse::NotFound().finish(),
+    };
+    match repo.set_pinned(post, pinned).await {
+        Ok(_) => HttpResponse::Accepted().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+pub(super) fn attachment_error_responce(err: AttachmentError) -> HttpResponse {
+    match err {
+        AttachmentError::PostDoesntExist | AttachmentError::AttachmentDoesntExist => {
+            HttpResponse::NotFound().json(err)
+        }
+        AttachmentError::EditsNotAuthor => HttpResponse::Forbidden().json(err),
+        AttachmentError::AttachmentIsProcessing => HttpResponse::Conflict().json(err),
+        AttachmentError::ProcessingFailed => HttpResponse::UnprocessableEntity().json(err),
+        AttachmentError::TooLarge => HttpResponse::PayloadTooLarge().json(err),
+        AttachmentError::UnsupportedType => HttpResponse::UnsupportedMediaType().json(err),
+        AttachmentError::MissingFile => HttpResponse::BadRequest().json(err),
+        AttachmentError::ProblemsWithStorage | AttachmentError::ProblemsWithDB => {
+            HttpResponse::InternalServerError().json(err)
+        }
+    }
+}
+
+async fn read_field(field: &mut Field, max_size: usize) -> Result<Vec<u8>, HttpResponse> {
+    let mut content = Vec::new();
+    while let Some(chunk) = field.next().await {
+        let chunk = chunk.map_err(|_| HttpResponse::BadRequest().finish())?;
+        if content.len() + chunk.len() > max_size {
+            return Err(attachment_error_responce(AttachmentError::TooLarge));
+        }
+        content.extend_from_slice(&chunk);
+    }
+    Ok(content)
+}
+
+/// Multipart form with `username`, `password` and, with 2FA enabled, `otp`
+/// text fields and a single `file` field.
+#[post("/{post_uuid}/attachments")]
+async fn upload_attachment(
+    req: HttpRequest,
+    path: Path<String>,
+    mut payload: Multipart,
+) -> impl Responder {
+    let mut sing_data = SingDTO {
+        username: String::new(),
+        password: String::new(),
+        otp: None,
+    };
+    let mut file = None;
+    while let Some(field) = payload.next().await {
+        let mut field = match field {
+            Ok(field) => field,
+            Err(_) => return HttpResponse::BadRequest().finish(),
+        };
+        let name = field.name().unwrap_or_default().to_string();
+        let filename = field
+            .content_disposition()
+            .and_then(|disposition| disposition.get_filename())
+            .unwrap_or_default()
+            .to_string();
+        let max_size = match name.as_str() {
+            "file" => attachments::max_size(),
+            _ => 1024,
+        };
+        let content = match read_field(&mut field, max_size).await {
+            Ok(content) => content,
+            Err(responce) => return responce,
+        };
+        match name.as_str() {
+            "username" => sing_data.username = String::from_utf8_lossy(&content).to_string(),
+            "password" => sing_data.password = String::from_utf8_lossy(&content).to_string(),
+            "otp" => sing_data.otp = Some(String::from_utf8_lossy(&content).to_string()),
+            "file" => file = Some((filename, content)),
+            _ => {}
+        }
+    }
+    let author = match UserController::sing(&sing_data, client_ip(&req).as_deref()).await {
+        Ok(author) => author,
+        Err(err) => return sing_failed_responce(err),
+    };
+    if let Err(err) = author.ensure_active().await {
+        return HttpResponse::Forbidden().json(err);
+    }
+    let (filename, content) = match file {
+        Some(file) => file,
+        None => return attachment_error_responce(AttachmentError::MissingFile),
+    };
+    let post = match Uuid::from_str(&path) {
+        Ok(uuid) => PostsRepo::get_instance().await.get_by_uuid(uuid).await,
+        Err(_) => None,
+    };
+    let post = match post {
+        Some(post) => post,
+        None => return attachment_error_responce(AttachmentError::PostDoesntExist),
+    };
+    if !author.is_owner_of(&post) {
+        return attachment_error_responce(AttachmentError::EditsNotAuthor);
+    }
+    match attachments::attach(&post, &filename, content).await {
+        Ok(attachment) => HttpResponse::Created().json(attachment),
+        Err(err) => attachment_error_responce(err),
+    }
+}
+
+pub(super) async fn find_attachment(
+    attachment_uuid: &str,
+) -> Result<AttachmentModel, AttachmentError> {
+    let attachment_uuid =
+        Uuid::from_str(attachment_uuid).map_err(|_| AttachmentError::AttachmentDoesntExist)?;
+    AttachmentsRepo::get_instance()
+        .await
+        .get_by_uuid(&attachment_uuid)
+        .await
+        .ok_or(AttachmentError::AttachmentDoesntExist)
+}
+
+/// Attachments are visible to whoever can see the post they are attached to.
+#[get("/attachments/{attachment_uuid}")]
+async fn get_attachment(
+    req: HttpRequest,
+    path: Path<String>,
+    sing_dto: Option<Json<SingDTO>>,
+) -> impl Responder {
+    let viewer = match viewer(sing_dto, client_ip(&req).as_deref()).await {
+        Ok(viewer) => viewer,
+        Err(err) => return sing_failed_responce(err),
+    };
+    let attachment = match find_attachment(&path).await {
+        Ok(attachment) => attachment,
+        Err(err) => return attachment_error_responce(err),
+    };
+    let post = PostsRepo::get_instance()
+        .await
+        .get_visible(attachment.post(), viewer)
+        .await;
+    if post.is_none() {
+        return attachment_error_responce(AttachmentError::AttachmentDoesntExist);
+    }
+    match attachments::content(&attachment, ImageVariant::Original).await {
+        Ok(content) => HttpResponse::Ok()
+            .content_type(attachment.content_type())
+            .insert_header(ContentDisposition {
+                disposition: DispositionType::Inline,
+                parameters: vec![DispositionParam::Filename(attachment.filename())],
+            })
+            .body(content),
+        Err(err) => attachment_error_responce(err),
+    }
+}
+
+#[delete("/attachments/{attachment_uuid}")]
+async fn delete_attachment(
+    req: HttpRequest,
+    path: Path<String>,
+    json: Json<SingDTO>,
+) -> impl Responder {
+    let author = match UserController::sing(&json, client_ip(&req).as_deref()).await {
+        Ok(author) => author,
+        Err(err) => return sing_failed_responce(err),
+    };
+    let attachment = match find_attachment(&path).await {
+        Ok(attachment) => attachment,
+        Err(err) => return attachment_error_responce(err),
+    };
+    let post = match PostsRepo::get_instance()
+        .await
+        .get_by_uuid(attachment.post())
+        .await
+    {
+        Some(post) => post,
+        None => return attachment_error_responce(AttachmentError::PostDoesntExist),
+    };
+    if !author.is_owner_of(&post) {
+        return attachment_error_responce(AttachmentError::EditsNotAuthor);
+    }
+    match attachments::remove(&attachment).await {
+        Ok(_) => HttpResponse::Accepted().finish(),
+        Err(err) => attachment_error_responce(err),
+    }
+}
@@ -0,0 +1,180 @@
+use crate::utils::config::setting;
+use actix_web::{
+    http::header::{
+        CacheControl, CacheDirective, ContentType, ETag, EntityTag, HttpDate, IfMatch,
+        IfModifiedSince, IfNoneMatch, LastModified,
+    },
+    web::Header,
+    HttpResponse,
+};
+use chrono::NaiveDateTime;
+use lazy_static::lazy_static;
+use openssl::sha::sha256;
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Serialize)]
+pub(super) enum PreconditionError {
+    IfMatchRequired,
+    Stale { current_version: i32 },
+}
+
+/// Entity tag of a version of a post or a comment.
+pub(super) fn version_tag(version: i32) -> EntityTag {
+    EntityTag::new_strong(version.to_string())
+}
+
+/// Version a tag was issued for: `"3"` and `"3-<hash>"` both name version 3.
+fn tagged_version(tag: &EntityTag) -> Option<i32> {
+    if tag.weak {
+        return None;
+    }
+    tag.tag().split('-').next()?.parse().ok()
+}
+
+pub(super) fn precondition_error_responce(err: PreconditionError) -> HttpResponse {
+    match err {
+        PreconditionError::IfMatchRequired => HttpResponse::PreconditionRequired().json(err),
+        PreconditionError::Stale { current_version } => HttpResponse::PreconditionFailed()
+            .insert_header(ETag(version_tag(current_version)))
+            .json(err),
+    }
+}
+
+/// Version the client means to edit, taken from `If-Match`. Edits without it
+/// are refused, `*` stands for whatever version is current. Only the version
+/// part of a tag is compared, so new likes or comments don't fail an edit.
+pub(super) fn expected_version(
+    if_match: Option<Header<IfMatch>>,
+    current_version: i32,
+) -> Result<i32, PreconditionError> {
+    match if_match.map(Header::into_inner) {
+        None => Err(PreconditionError::IfMatchRequired),
+        Some(IfMatch::Any) => Ok(current_version),
+        Some(IfMatch::Items(tags)) => {
+            if tags
+                .iter()
+                .any(|tag| tagged_version(tag) == Some(current_version))
+            {
+                Ok(current_version)
+            } else {
+                Err(PreconditionError::Stale { current_version })
+            }
+        }
+    }
+}
+
+lazy_static! {
+    static ref POST_MAX_AGE: u32 = setting("CACHE_MAX_AGE_POST", 60);
+    static ref COMMENT_MAX_AGE: u32 = setting("CACHE_MAX_AGE_COMMENT", 60);
+    static ref PROFILE_MAX_AGE: u32 = setting("CACHE_MAX_AGE_PROFILE", 300);
+    static ref MEDIA_MAX_AGE: u32 = setting("CACHE_MAX_AGE_MEDIA", 60);
+}
+
+/// Read routes with their own `Cache-Control` policy, max ages are set with
+/// `CACHE_MAX_AGE_*` in .env.
+#[derive(Clone, Copy)]
+pub(super) enum CachedRoute {
+    Post,
+    Comment,
+    Profile,
+    Media,
+}
+
+impl CachedRoute {
+    pub(super) fn max_age(&self) -> u32 {
+        match self {
+            CachedRoute::Post => *POST_MAX_AGE,
+            CachedRoute::Comment => *COMMENT_MAX_AGE,
+            CachedRoute::Profile => *PROFILE_MAX_AGE,
+            CachedRoute::Media => *MEDIA_MAX_AGE,
+        }
+    }
+}
+
+pub(super) struct Conditions {
+    pub if_none_match: Option<Header<IfNoneMatch>>,
+    pub if_modified_since: Option<Header<IfModifiedSince>>,
+}
+
+/// Strong tag of a representation, prefixed with the version when there's one
+/// so it can be sent back in `If-Match`.
+fn content_tag(version: Option<i32>, body: &[u8]) -> EntityTag {
+    let hash: String = sha256(body)[..12]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    EntityTag::new_strong(match version {
+        Some(version) => format!("{version}-{hash}"),
+        None => hash,
+    })
+}
+
+fn seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// `If-None-Match` wins over `If-Modified-Since` when both are sent.
+fn not_modified(
+    conditions: Conditions,
+    tag: &EntityTag,
+    last_modified: Option<SystemTime>,
+) -> bool {
+    if let Some(if_none_match) = conditions.if_none_match {
+        return match if_none_match.into_inner() {
+            IfNoneMatch::Any => true,
+            IfNoneMatch::Items(tags) => tags.iter().any(|item| item.weak_eq(tag)),
+        };
+    }
+    match (conditions.if_modified_since, last_modified) {
+        (Some(since), Some(last_modified)) => {
+            seconds(last_modified) <= seconds(SystemTime::from(since.into_inner().0))
+        }
+        _ => false,
+    }
+}
+
+/// Answers a read with its body, or with 304 when the client's copy is still
+/// current. Responses for a signed in viewer are only cached privately.
+pub(super) fn cached_responce<T: Serialize>(
+    route: CachedRoute,
+    conditions: Conditions,
+    body: &T,
+    version: Option<i32>,
+    last_modified: Option<NaiveDateTime>,
+    private: bool,
+) -> HttpResponse {
+    let body = match serde_json::to_vec(body) {
+        Ok(body) => body,
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+    let tag = content_tag(version, &body);
+    let last_modified: Option<SystemTime> =
+        last_modified.map(|last_modified| last_modified.and_utc().into());
+    let not_modified = not_modified(conditions, &tag, last_modified);
+    let mut responce = if not_modified {
+        HttpResponse::NotModified()
+    } else {
+        HttpResponse::Ok()
+    };
+    responce
+        .insert_header(ETag(tag))
+        .insert_header(CacheControl(vec![
+            if private {
+                CacheDirective::Private
+            } else {
+                CacheDirective::Public
+            },
+            CacheDirective::MaxAge(route.max_age()),
+        ]));
+    if let Some(last_modified) = last_modified {
+        responce.insert_header(LastModified(HttpDate::from(last_modified)));
+    }
+    if not_modified {
+        responce.finish()
+    } else {
+        responce.insert_header(ContentType::json()).body(body)
+    }
+}
@@ -0,0 +1,23 @@
+use crate::{controllers::users::SingError, services::rate_limits::RateLimited};
+use actix_web::{http::header::RETRY_AFTER, HttpRequest, HttpResponse};
+
+/// Address of the connected peer. Forwarding headers are ignored, anyone
+/// could set them to get a fresh quota.
+pub(super) fn client_ip(req: &HttpRequest) -> Option<String> {
+    req.peer_addr().map(|addr| addr.ip().to_string())
+}
+
+pub(super) fn rate_limited_responce(err: RateLimited) -> HttpResponse {
+    HttpResponse::TooManyRequests()
+        .insert_header((RETRY_AFTER, err.retry_after.to_string()))
+        .json(err)
+}
+
+/// Sign-in errors on routes that answer them with 400. Lockouts get 429 on
+/// every route.
+pub(super) fn sing_failed_responce(err: SingError) -> HttpResponse {
+    match err {
+        SingError::LockedOut { retry_after } => rate_limited_responce(RateLimited { retry_after }),
+        _ => HttpResponse::BadRequest().json(err),
+    }
+}
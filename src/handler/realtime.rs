@@ -0,0 +1,163 @@
+use super::rate_limits::{client_ip, rate_limited_responce};
+use crate::{
+    controllers::{
+        users::{SingError, UserController},
+        Controller,
+    },
+    dto::{SingDTO, SubscriptionJSON},
+    models::user::UserModel,
+    repositories::posts::PostsRepo,
+    services::{
+        rate_limits::RateLimited,
+        realtime::{self, Channel},
+    },
+};
+use actix_web::{
+    get, rt,
+    web::{Bytes, Json, Payload},
+    HttpRequest, HttpResponse, Responder, Scope,
+};
+use actix_ws::{Message, Session};
+use futures::{
+    future::{select, Either},
+    StreamExt,
+};
+use serde::Serialize;
+use std::str::FromStr;
+use uuid::Uuid;
+
+#[derive(Serialize)]
+pub enum SubscriptionError {
+    WrongSingData,
+    LockedOut { retry_after: u64 },
+    WrongUuid,
+    PostDoesntExist,
+    WrongFormat,
+}
+
+pub fn realtime_scope() -> Scope {
+    Scope::new("/realtime").service(events).service(websocket)
+}
+
+async fn resolve_channels(
+    subscriptions: Vec<SubscriptionJSON>,
+    viewer: UserModel,
+) -> Result<Vec<Channel>, SubscriptionError> {
+    let mut channels = Vec::new();
+    for subscription in subscriptions {
+        channels.push(match subscription {
+            SubscriptionJSON::PostComments(post_uuid) => {
+                let post_uuid =
+                    Uuid::from_str(&post_uuid).map_err(|_| SubscriptionError::WrongUuid)?;
+                PostsRepo::get_instance()
+                    .await
+                    .get_visible(post_uuid, Some(viewer.clone()))
+                    .await
+                    .ok_or(SubscriptionError::PostDoesntExist)?;
+                Channel::PostComments(post_uuid)
+            }
+            SubscriptionJSON::Notifications => Channel::Notifications(viewer.username()),
+            SubscriptionJSON::Tag(tag) => Channel::Tag(tag),
+        });
+    }
+    Ok(channels)
+}
+
+async fn authorize(
+    subscriptions: Vec<SubscriptionJSON>,
+    sing_data: &SingDTO,
+    ip: Option<&str>,
+) -> Result<Vec<Channel>, SubscriptionError> {
+    let viewer = UserController::sing(sing_data, ip)
+        .await
+        .map_err(|err| match err {
+            SingError::LockedOut { retry_after } => SubscriptionError::LockedOut { retry_after },
+            _ => SubscriptionError::WrongSingData,
+        })?
+        .model()
+        .await;
+    resolve_channels(subscriptions, viewer).await
+}
+
+#[get("/events")]
+async fn events(req: HttpRequest, json: Json<(Vec<SubscriptionJSON>, SingDTO)>) -> impl Responder {
+    let (subscriptions, sing_data) = json.clone();
+    let channels = match authorize(subscriptions, &sing_data, client_ip(&req).as_deref()).await {
+        Ok(channels) => channels,
+        Err(SubscriptionError::LockedOut { retry_after }) => {
+            return rate_limited_responce(RateLimited { retry_after })
+        }
+        Err(err) => return HttpResponse::BadRequest().json(err),
+    };
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .streaming(
+            realtime::subscribe(&channels).map(|event| {
+                Ok::<_, actix_web::Error>(Bytes::from(format!("data: {}\n\n", event)))
+            }),
+        )
+}
+
+/// The first text message of the socket must be the JSON encoded
+/// `(Vec<SubscriptionJSON>, SingDTO)` pair, events are sent after it.
+#[get("/ws")]
+async fn websocket(req: HttpRequest, body: Payload) -> actix_web::Result<HttpResponse> {
+    let (response, mut session, mut messages) = actix_ws::handle(&req, body)?;
+    let ip = client_ip(&req);
+    rt::spawn(async move {
+        let subscriptions = loop {
+            match messages.next().await {
+                Some(Ok(Message::Text(text))) => break text.to_string(),
+                Some(Ok(Message::Ping(bytes))) => {
+                    if session.pong(&bytes).await.is_err() {
+                        return;
+                    }
+                }
+                _ => {
+                    let _ = session.close(None).await;
+                    return;
+                }
+            }
+        };
+        let channels =
+            match serde_json::from_str::<(Vec<SubscriptionJSON>, SingDTO)>(&subscriptions) {
+                Ok((subscriptions, sing_data)) => {
+                    authorize(subscriptions, &sing_data, ip.as_deref()).await
+                }
+                Err(_) => Err(SubscriptionError::WrongFormat),
+            };
+        match channels {
+            Ok(channels) => forward(session, messages, channels).await,
+            Err(err) => {
+                let _ = session.text(serde_json::to_string(&err).unwrap()).await;
+                let _ = session.close(None).await;
+            }
+        }
+    });
+    Ok(response)
+}
+
+async fn forward(
+    mut session: Session,
+    mut messages: actix_ws::MessageStream,
+    channels: Vec<Channel>,
+) {
+    let mut stream = realtime::subscribe(&channels);
+    loop {
+        match select(stream.next(), messages.next()).await {
+            Either::Left((Some(event), _)) => {
+                if session.text(event).await.is_err() {
+                    return;
+                }
+            }
+            Either::Right((Some(Ok(Message::Ping(bytes))), _)) => {
+                if session.pong(&bytes).await.is_err() {
+                    return;
+                }
+            }
+            Either::Right((Some(Ok(Message::Text(_))), _)) => {}
+            _ => break,
+        }
+    }
+    let _ = session.close(None).await;
+}
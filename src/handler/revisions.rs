@@ -0,0 +1,145 @@
+use super::rate_limits::{client_ip, sing_failed_responce};
+use crate::{
+    controllers::{users::UserController, Controller},
+    dto::SingDTO,
+    models::user::UserModel,
+    prelude::Resource,
+    repositories::{comments::CommentsRepo, posts::PostsRepo, revisions::RevisionsRepo},
+    services::{
+        revisions::{self, DiffMode},
+        two_factor,
+    },
+};
+use actix_web::{
+    get,
+    web::{Json, Path, Query},
+    HttpRequest, HttpResponse, Responder, Scope,
+};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+struct DiffQueryParams {
+    from: i32,
+    to: i32,
+    mode: Option<DiffMode>,
+}
+
+#[derive(Serialize)]
+enum RevisionError {
+    ResourceDoesntExist,
+    RevisionDoesntExist,
+    NotAllowed,
+}
+
+pub fn revisions_scope() -> Scope {
+    Scope::new("/revisions")
+        .service(diff)
+        .service(revision)
+        .service(revision_history)
+}
+
+/// The post or comment if the viewer can read it the way the posts and
+/// comments endpoints allow.
+async fn visible_resource(uuid: Uuid, viewer: &UserModel) -> Option<Box<dyn Resource>> {
+    let posts = PostsRepo::get_instance().await;
+    if let Some(comment) = CommentsRepo::get_instance().await.get_by_uuid(&uuid).await {
+        let moderates = viewer.username() == comment.author_username()
+            || two_factor::acts_as_administrator(viewer).await;
+        if (comment.is_hidden() || comment.is_pending()) && !moderates {
+            return None;
+        }
+        posts
+            .get_visible(comment.under_post().uuid(), Some(viewer.clone()))
+            .await?;
+        return Some(Box::new(comment));
+    }
+    let post = posts.get_visible(uuid, Some(viewer.clone())).await?;
+    Some(Box::new(post))
+}
+
+/// History is available to the author of the post or comment and to
+/// administrators.
+async fn allowed_resource(
+    resource_uuid: &str,
+    sing_dto: &SingDTO,
+    ip: Option<&str>,
+) -> Result<Uuid, HttpResponse> {
+    let viewer = match UserController::sing(sing_dto, ip).await {
+        Ok(viewer) => viewer,
+        Err(err) => return Err(sing_failed_responce(err)),
+    };
+    let model = viewer.model().await;
+    let resource = match Uuid::from_str(resource_uuid) {
+        Ok(uuid) => visible_resource(uuid, &model).await,
+        Err(_) => None,
+    };
+    let resource = match resource {
+        Some(resource) => resource,
+        None => return Err(HttpResponse::NotFound().json(RevisionError::ResourceDoesntExist)),
+    };
+    if !viewer.is_owner_of(resource.as_ref()) && !two_factor::acts_as_administrator(&model).await {
+        return Err(HttpResponse::Forbidden().json(RevisionError::NotAllowed));
+    }
+    Ok(resource.uuid())
+}
+
+#[get("/{resource_uuid}")]
+async fn revision_history(
+    req: HttpRequest,
+    path: Path<String>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    match allowed_resource(&path, &sing_dto, client_ip(&req).as_deref()).await {
+        Ok(resource) => {
+            HttpResponse::Ok().json(RevisionsRepo::get_instance().await.get_for(&resource).await)
+        }
+        Err(responce) => responce,
+    }
+}
+
+#[get("/{resource_uuid}/diff")]
+async fn diff(
+    req: HttpRequest,
+    path: Path<String>,
+    query: Query<DiffQueryParams>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    let resource = match allowed_resource(&path, &sing_dto, client_ip(&req).as_deref()).await {
+        Ok(resource) => resource,
+        Err(responce) => return responce,
+    };
+    let repo = RevisionsRepo::get_instance().await;
+    match (
+        repo.get(&resource, query.from).await,
+        repo.get(&resource, query.to).await,
+    ) {
+        (Some(from), Some(to)) => {
+            HttpResponse::Ok().json(revisions::diff(&from, &to, query.mode.unwrap_or_default()))
+        }
+        _ => HttpResponse::NotFound().json(RevisionError::RevisionDoesntExist),
+    }
+}
+
+#[get("/{resource_uuid}/{number}")]
+async fn revision(
+    req: HttpRequest,
+    path: Path<(String, i32)>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    let (resource_uuid, number) = path.into_inner();
+    let resource =
+        match allowed_resource(&resource_uuid, &sing_dto, client_ip(&req).as_deref()).await {
+            Ok(resource) => resource,
+            Err(responce) => return responce,
+        };
+    match RevisionsRepo::get_instance()
+        .await
+        .get(&resource, number)
+        .await
+    {
+        Some(revision) => HttpResponse::Ok().json(revision),
+        None => HttpResponse::NotFound().json(RevisionError::RevisionDoesntExist),
+    }
+}
@@ -1,20 +1,51 @@
+use super::moderation::suspension_error_responce;
+use super::preconditions::{
+    cached_responce, expected_version, precondition_error_responce, version_tag, CachedRoute,
+    Conditions, PreconditionError,
+};
+use super::rate_limits::{client_ip, rate_limited_responce, sing_failed_responce};
 use crate::{
     controllers::{
-        users::{SingError, UserController},
+        users::{FollowError, SingError, UserController},
         Controller,
     },
-    dto::{PublishPostDTO, PublishPostJSON, SingDTO, UserRegistrationDTO},
+    dto::{
+        AppealJSON, PasswordResetJSON, PasswordResetRequestJSON, PublishPostDTO, PublishPostJSON,
+        SingDTO, TwoFactorCodeJSON, UserRegistrationDTO,
+    },
+    models::profile::ProfileModel,
+    prelude::EditError,
     repositories::{
+        comments::PublishError as CommentError,
         find_resources,
-        posts::PostsRepo,
-        users::{queries::ChangeQueryParam, UserRepo},
+        follows::FollowsRepo,
+        mentions::MentionsRepo,
+        parent_links::ParentLinksRepo,
+        posts::{PostsRepo, PublishError},
+        subscriptions::SubscriptionsRepo,
+        suspensions::SuspensionsRepo,
+        users::{queries::ChangeQueryParam, RegistrationError, UserRepo},
     },
+    services::{
+        accounts::{self, AccountError},
+        invitations::{self, SignUpError},
+        mentions,
+        parents::{self, ParentLinkError},
+        premoderation,
+        rate_limits::{self, Bucket, RateLimited},
+        realtime::post_published,
+        sign_in, suspensions,
+        two_factor::{self, TwoFactorError},
+    },
+    types::Limit,
     utils::logger::Logger,
 };
 use actix_web::{
-    get, patch, post,
-    web::{Data, Json, Path},
-    HttpResponse, Responder, Scope,
+    delete, get,
+    http::header::{ETag, IfMatch, IfModifiedSince, IfNoneMatch},
+    patch, post,
+    web::{Data, Header, Json, Path, Query},
+    HttpRequest, HttpResponse, Responder, Scope,
 };
 use serde::Deserialize;
 use std::str::FromStr;
@@ -26,7 +57,39 @@ pub fn user_scope() -> Scope {
         .service(publish_post)
         .service(mark)
         .service(row)
+        .service(edit)
+        .service(change_param)
         .service(register)
+        .service(invitation)
+        .service(mentioned_in)
+        .service(own_suspension)
+        .service(appeal)
+        .service(own_pending)
+        .service(security_log)
+        .service(verify_email)
+        .service(resend_verification)
+        .service(request_password_reset)
+        .service(reset_password)
+        .service(two_factor_status)
+        .service(enroll_two_factor)
+        .service(confirm_two_factor)
+        .service(disable_two_factor)
+        .service(open_two_factor_session)
+        .service(regenerate_recovery_codes)
+        .service(subscriptions)
+        .service(subscribe)
+        .service(unsubscribe)
+        .service(own_children)
+        .service(request_parent_link)
+        .service(remove_child)
+        .service(own_parents)
+        .service(confirm_parent)
+        .service(remove_parent)
+        .service(follow)
+        .service(unfollow)
+        .service(followers)
+        .service(following)
+        .service(profile)
 }
 
 fn log_sing_error(logger: &dyn Logger, username: &str, err: &SingError) {
@@ -37,39 +100,256 @@ fn log_sing_error(logger: &dyn Logger, username: &str, err: &SingError) {
     ));
 }
 
+fn sing_error_responce(err: SingError) -> HttpResponse {
+    match err {
+        SingError::WrongCredentials => HttpResponse::Unauthorized().json(err),
+        SingError::LockedOut { retry_after } => rate_limited_responce(RateLimited { retry_after }),
+        SingError::TwoFactorRequired => HttpResponse::Unauthorized().json(err),
+    }
+}
+
+/// Failed sign-ins count towards the `login` limit of the account and the IP
+/// address, which is checked before the credentials.
 #[get("/")]
-async fn get_user(sing_dto: Json<SingDTO>) -> impl Responder {
-    match UserController::sing(&sing_dto).await {
+async fn get_user(req: HttpRequest, sing_dto: Json<SingDTO>) -> impl Responder {
+    let ip = client_ip(&req);
+    match UserController::sign_in(&sing_dto, ip.as_deref()).await {
         Ok(user) => HttpResponse::Ok().json(user.model().await),
-        Err(err) => HttpResponse::NotFound().json(err),
+        Err(err) => sing_error_responce(err),
+    }
+}
+
+fn sign_up_error_responce(err: SignUpError) -> HttpResponse {
+    match err {
+        SignUpError::RegistrationClosed | SignUpError::InvitationRequired => {
+            HttpResponse::Forbidden().json(err)
+        }
+        SignUpError::Registration(RegistrationError::ProblemsWithDB) => {
+            HttpResponse::InternalServerError().json(err)
+        }
+        _ => HttpResponse::BadRequest().json(err),
     }
 }
 
 #[post("/")]
 async fn register(publish_dto: Json<UserRegistrationDTO>) -> impl Responder {
-    match UserRepo::get_instance()
-        .await
-        .register(publish_dto.clone())
-        .await
-    {
-        Ok(user) => HttpResponse::Created().json(user),
-        Err(err) => HttpResponse::BadRequest().json(err),
+    match invitations::register(publish_dto.into_inner()).await {
+        Ok(user) => {
+            let _ = accounts::send_verification(&user).await;
+            HttpResponse::Created().json(user)
+        }
+        Err(err) => sign_up_error_responce(err),
+    }
+}
+
+/// What users registering with the invitation register as, so the form can
+/// be filled in.
+#[get("/invitations/{code}")]
+async fn invitation(path: Path<String>) -> impl Responder {
+    match invitations::lookup(&path).await {
+        Some(found) => HttpResponse::Ok().json((found.user_specs(), found.expires_at())),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+fn account_error_responce(err: AccountError) -> HttpResponse {
+    match err {
+        AccountError::AlreadyVerified => HttpResponse::Conflict().json(err),
+        AccountError::InvalidToken | AccountError::InvalidPassword(_) => {
+            HttpResponse::BadRequest().json(err)
+        }
+        AccountError::ProblemsWithMail(_) => HttpResponse::ServiceUnavailable().json(err),
+        AccountError::ProblemsWithRandom | AccountError::ProblemsWithDB => {
+            HttpResponse::InternalServerError().json(err)
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct VerifyEmailQueryParams {
+    token: String,
+}
+
+/// Opened from the link in the verification mail.
+#[get("/email/verify")]
+async fn verify_email(query: Query<VerifyEmailQueryParams>) -> impl Responder {
+    match accounts::verify_email(&query.token).await {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(err) => account_error_responce(err),
+    }
+}
+
+#[post("/email/verification")]
+async fn resend_verification(req: HttpRequest, sing_dto: Json<SingDTO>) -> impl Responder {
+    let user = match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller.model().await,
+        Err(err) => return sing_error_responce(err),
+    };
+    match accounts::send_verification(&user).await {
+        Ok(_) => HttpResponse::Accepted().finish(),
+        Err(err) => account_error_responce(err),
+    }
+}
+
+/// Always accepted, so it can't be used to find out who has an account.
+#[post("/password/reset_request")]
+async fn request_password_reset(
+    req: HttpRequest,
+    json: Json<PasswordResetRequestJSON>,
+) -> impl Responder {
+    if let Err(err) = rate_limits::check(Bucket::Login, None, client_ip(&req).as_deref()).await {
+        return rate_limited_responce(err);
+    }
+    accounts::request_password_reset(json.into_inner().login);
+    HttpResponse::Accepted().finish()
+}
+
+#[post("/password/reset")]
+async fn reset_password(json: Json<PasswordResetJSON>) -> impl Responder {
+    let reset = json.into_inner();
+    match accounts::reset_password(&reset.token, reset.password).await {
+        Ok(_) => HttpResponse::Accepted().finish(),
+        Err(err) => account_error_responce(err),
+    }
+}
+
+fn two_factor_error_responce(err: TwoFactorError) -> HttpResponse {
+    match err {
+        TwoFactorError::AlreadyEnabled => HttpResponse::Conflict().json(err),
+        TwoFactorError::NotEnrolled => HttpResponse::NotFound().json(err),
+        TwoFactorError::CodeRequired | TwoFactorError::WrongCode => {
+            HttpResponse::BadRequest().json(err)
+        }
+        TwoFactorError::ProblemsWithRandom
+        | TwoFactorError::ProblemsWithSecret
+        | TwoFactorError::ProblemsWithDB => HttpResponse::InternalServerError().json(err),
+    }
+}
+
+#[get("/two_factor")]
+async fn two_factor_status(req: HttpRequest, sing_dto: Json<SingDTO>) -> impl Responder {
+    match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => {
+            HttpResponse::Ok().json(two_factor::status(&controller.model().await).await)
+        }
+        Err(err) => sing_error_responce(err),
+    }
+}
+
+/// Starts over with a new secret until a code from it is confirmed.
+#[post("/two_factor")]
+async fn enroll_two_factor(req: HttpRequest, sing_dto: Json<SingDTO>) -> impl Responder {
+    let user = match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller.model().await,
+        Err(err) => return sing_error_responce(err),
+    };
+    match two_factor::enroll(&user).await {
+        Ok(enrollment) => HttpResponse::Created().json(enrollment),
+        Err(err) => two_factor_error_responce(err),
+    }
+}
+
+/// Responds with the recovery codes, they aren't shown again.
+#[post("/two_factor/confirm")]
+async fn confirm_two_factor(
+    req: HttpRequest,
+    json: Json<(TwoFactorCodeJSON, SingDTO)>,
+) -> impl Responder {
+    let (confirmation, sing_data) = json.into_inner();
+    let user = match UserController::sing(&sing_data, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller.model().await,
+        Err(err) => return sing_error_responce(err),
+    };
+    match two_factor::confirm(&user, &confirmation.code).await {
+        Ok(codes) => HttpResponse::Ok().json(codes),
+        Err(err) => two_factor_error_responce(err),
+    }
+}
+
+#[delete("/two_factor")]
+async fn disable_two_factor(req: HttpRequest, sing_dto: Json<SingDTO>) -> impl Responder {
+    let user = match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller.model().await,
+        Err(err) => return sing_error_responce(err),
+    };
+    match two_factor::disable(&user).await {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(err) => two_factor_error_responce(err),
+    }
+}
+
+/// A code is good for one request, the session token is sent as `otp`
+/// instead until it expires.
+#[post("/two_factor/session")]
+async fn open_two_factor_session(req: HttpRequest, sing_dto: Json<SingDTO>) -> impl Responder {
+    let user = match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller.model().await,
+        Err(err) => return sing_error_responce(err),
+    };
+    match two_factor::open_session(&user).await {
+        Ok(session) => HttpResponse::Created().json(session),
+        Err(err) => two_factor_error_responce(err),
+    }
+}
+
+#[post("/two_factor/recovery_codes")]
+async fn regenerate_recovery_codes(req: HttpRequest, sing_dto: Json<SingDTO>) -> impl Responder {
+    let user = match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller.model().await,
+        Err(err) => return sing_error_responce(err),
+    };
+    match two_factor::regenerate_recovery_codes(&user).await {
+        Ok(codes) => HttpResponse::Ok().json(codes),
+        Err(err) => two_factor_error_responce(err),
     }
 }
 
 #[post("/post")]
-async fn publish_post(publish_dto: Json<(PublishPostJSON, SingDTO)>) -> impl Responder {
+async fn publish_post(
+    req: HttpRequest,
+    publish_dto: Json<(PublishPostJSON, SingDTO)>,
+) -> impl Responder {
     let (publish_dto, sing_data) = publish_dto.clone();
-    let author = UserController::sing(&sing_data).await;
+    let ip = client_ip(&req);
+    let author = UserController::sing(&sing_data, ip.as_deref()).await;
+    if let Ok(author) = &author {
+        if let Err(err) = author.ensure_active().await {
+            return HttpResponse::Forbidden().json(err);
+        }
+        let model = author.model().await;
+        if let Err(err) = rate_limits::check(Bucket::Publish, Some(&model), ip.as_deref()).await {
+            return rate_limited_responce(err);
+        }
+    }
     let dto = match author {
         Ok(author) => PublishPostDTO {
             content: publish_dto.content.clone(),
             title: publish_dto.title.clone(),
             author: author.model().await,
+            status: publish_dto.status(),
+            expires_at: publish_dto.expires_at,
+            audience: publish_dto.audience.clone().unwrap_or_default(),
         },
+        Err(SingError::LockedOut { retry_after }) => {
+            return rate_limited_responce(RateLimited { retry_after })
+        }
         Err(_) => return HttpResponse::NotFound().finish(),
     };
-    HttpResponse::Created().json(PostsRepo::get_instance().await.publish(dto).await.unwrap())
+    let post = match PostsRepo::get_instance().await.publish(dto).await {
+        Ok(post) => post,
+        Err(err @ PublishError::Rejected { .. }) => {
+            return HttpResponse::UnprocessableEntity().json(err)
+        }
+        Err(err @ PublishError::InvalidAudience) => return HttpResponse::BadRequest().json(err),
+        Err(err @ PublishError::ProblemsWithDB) => {
+            return HttpResponse::InternalServerError().json(err)
+        }
+    };
+    if post.status().is_published() && !post.is_hidden() && !post.is_pending() {
+        mentions::sync_post(&post).await;
+        post_published(&post).await;
+    }
+    HttpResponse::Created().json(post)
 }
 
 fn log_uuid_generating_error(logger: &dyn Logger, uuid_string: &str) {
@@ -85,19 +365,28 @@ fn log_resource_getting_error(logger: &dyn Logger, uuid: &Uuid) {
 
 #[post("/{resource_uuid}/liked/{liked}")]
 async fn mark(
+    req: HttpRequest,
     path: Path<(String, bool)>,
     json: Json<SingDTO>,
     logger: Data<dyn Logger>,
 ) -> impl Responder {
     let (resource_uuid, liked) = (*path).clone();
     let sing_data = json.clone();
-    let user_controller = match UserController::sing(&sing_data).await {
+    let ip = client_ip(&req);
+    let user_controller = match UserController::sing(&sing_data, ip.as_deref()).await {
         Ok(controller) => controller,
         Err(err) => {
             log_sing_error(logger.as_ref(), &sing_data.username, &err);
-            return HttpResponse::BadRequest().json(err);
+            return sing_failed_responce(err);
         }
     };
+    if let Err(err) = user_controller.ensure_active().await {
+        return HttpResponse::Forbidden().json(err);
+    }
+    let model = user_controller.model().await;
+    if let Err(err) = rate_limits::check(Bucket::Mark, Some(&model), ip.as_deref()).await {
+        return rate_limited_responce(err);
+    }
     let resource_uuid = match Uuid::from_str(&resource_uuid) {
         Ok(uuid) => uuid,
         Err(_) => {
@@ -105,7 +394,7 @@ async fn mark(
             return HttpResponse::BadRequest().finish();
         }
     };
-    let resource = match find_resources(resource_uuid).await {
+    let resource = match find_resources(resource_uuid, &model).await {
         Some(resource) => resource,
         None => {
             log_resource_getting_error(logger.as_ref(), &resource_uuid);
@@ -127,6 +416,7 @@ pub struct CommentJSON {
 
 #[post("/comment/{resource_uuid}")]
 async fn row(
+    req: HttpRequest,
     path: Path<String>,
     json: Json<(CommentJSON, SingDTO)>,
     logger: Data<dyn Logger>,
@@ -137,13 +427,21 @@ async fn row(
         "started comment query by {}",
         sing_data.username.clone()
     ));
-    let controller = match UserController::sing(&sing_data).await {
+    let ip = client_ip(&req);
+    let controller = match UserController::sing(&sing_data, ip.as_deref()).await {
         Ok(controller) => controller,
         Err(err) => {
             log_sing_error(logger.as_ref(), &sing_data.username, &err);
-            return HttpResponse::BadRequest().json(err);
+            return sing_failed_responce(err);
         }
     };
+    if let Err(err) = controller.ensure_active().await {
+        return HttpResponse::Forbidden().json(err);
+    }
+    let model = controller.model().await;
+    if let Err(err) = rate_limits::check(Bucket::Comment, Some(&model), ip.as_deref()).await {
+        return rate_limited_responce(err);
+    }
     let resource_uuid = match Uuid::from_str(&resource_uuid) {
         Ok(uuid) => uuid,
         Err(_) => {
@@ -151,14 +449,24 @@ async fn row(
             return HttpResponse::BadRequest().finish();
         }
     };
-    let resource = match find_resources(resource_uuid).await {
+    let resource = match find_resources(resource_uuid, &model).await {
         Some(post) => post,
         None => {
             log_resource_getting_error(logger.as_ref(), &resource_uuid);
             return HttpResponse::NotFound().finish();
         }
     };
-    controller.comment(resource.as_ref(), comment.content).await;
+    match controller.comment(resource.as_ref(), comment.content).await {
+        Ok(_) => (),
+        Err(err @ CommentError::Rejected { .. }) => {
+            return HttpResponse::UnprocessableEntity().json(err)
+        }
+        Err(err @ CommentError::Duplicate) => return HttpResponse::Conflict().json(err),
+        Err(err @ CommentError::ProblemsWithDB) => {
+            return HttpResponse::InternalServerError().json(err)
+        }
+        Err(err) => return HttpResponse::BadRequest().json(err),
+    }
     logger.info(&format!(
         "resource: {}, commented succesfully",
         resource_uuid
@@ -166,15 +474,423 @@ async fn row(
     HttpResponse::Accepted().finish()
 }
 
+#[derive(Deserialize, Clone)]
+pub struct EditJSON {
+    content: String,
+}
+
+/// Edits the content of a post or a comment. `If-Match` must carry the
+/// version the edit is based on.
+#[patch("/edit/{resource_uuid}")]
+async fn edit(
+    req: HttpRequest,
+    path: Path<String>,
+    json: Json<(EditJSON, SingDTO)>,
+    if_match: Option<Header<IfMatch>>,
+    logger: Data<dyn Logger>,
+) -> impl Responder {
+    let resource_uuid = path.clone();
+    let (edit_dto, sing_data) = json.clone();
+    let controller = match UserController::sing(&sing_data, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller,
+        Err(err) => {
+            log_sing_error(logger.as_ref(), &sing_data.username, &err);
+            return sing_failed_responce(err);
+        }
+    };
+    if let Err(err) = controller.ensure_active().await {
+        return HttpResponse::Forbidden().json(err);
+    }
+    let resource_uuid = match Uuid::from_str(&resource_uuid) {
+        Ok(uuid) => uuid,
+        Err(_) => {
+            log_uuid_generating_error(logger.as_ref(), &resource_uuid);
+            return HttpResponse::BadRequest().finish();
+        }
+    };
+    let resource = match find_resources(resource_uuid, &controller.model().await).await {
+        Some(resource) => resource,
+        None => {
+            log_resource_getting_error(logger.as_ref(), &resource_uuid);
+            return HttpResponse::NotFound().finish();
+        }
+    };
+    let expected_version = match expected_version(if_match, resource.version()) {
+        Ok(version) => version,
+        Err(err) => return precondition_error_responce(err),
+    };
+    match controller
+        .edit(resource.as_ref(), edit_dto.content, expected_version)
+        .await
+    {
+        Ok(_) => HttpResponse::Accepted()
+            .insert_header(ETag(version_tag(expected_version + 1)))
+            .finish(),
+        Err(EditError::Stale { current_version }) => {
+            precondition_error_responce(PreconditionError::Stale { current_version })
+        }
+        Err(err @ EditError::EditsNotAuthor) => HttpResponse::Forbidden().json(err),
+        Err(err @ EditError::Rejected { .. }) => HttpResponse::UnprocessableEntity().json(err),
+        Err(err @ EditError::ProblemsWithDB) => HttpResponse::InternalServerError().json(err),
+    }
+}
+
 #[patch("/change")]
-async fn change_param(params: Json<(Vec<ChangeQueryParam>, SingDTO)>) -> impl Responder {
+async fn change_param(
+    req: HttpRequest,
+    params: Json<(Vec<ChangeQueryParam>, SingDTO)>,
+) -> impl Responder {
     let (params, sing_data) = params.clone();
-    let controller = match UserController::sing(&sing_data).await {
+    let controller = match UserController::sing(&sing_data, client_ip(&req).as_deref()).await {
         Ok(controller) => controller,
-        Err(err) => return HttpResponse::BadRequest().json(err),
+        Err(err) => return sing_failed_responce(err),
     };
+    let email_changed = params
+        .iter()
+        .any(|param| matches!(param, ChangeQueryParam::Email(_)));
     match controller.change_parameters(params).await {
-        Ok(_) => HttpResponse::Accepted().finish(),
+        Ok(_) => {
+            if email_changed {
+                let _ = accounts::email_changed(&controller.model().await).await;
+            }
+            HttpResponse::Accepted().finish()
+        }
         Err(errors) => HttpResponse::BadRequest().json(errors),
     }
 }
+
+fn follow_error_responce(err: FollowError) -> HttpResponse {
+    match err {
+        FollowError::UserDoesntExist => HttpResponse::NotFound().json(err),
+        FollowError::FollowsSelf => HttpResponse::BadRequest().json(err),
+        FollowError::ProblemsWithDB => HttpResponse::InternalServerError().json(err),
+    }
+}
+
+pub(super) fn parent_link_error_responce(err: ParentLinkError) -> HttpResponse {
+    match err {
+        ParentLinkError::NotParent | ParentLinkError::NotStudent => {
+            HttpResponse::BadRequest().json(err)
+        }
+        ParentLinkError::UserDoesntExist | ParentLinkError::LinkDoesntExist => {
+            HttpResponse::NotFound().json(err)
+        }
+        ParentLinkError::AlreadyRequested => HttpResponse::Conflict().json(err),
+        ParentLinkError::NotAllowed => HttpResponse::Forbidden().json(err),
+        ParentLinkError::ProblemsWithDB => HttpResponse::InternalServerError().json(err),
+    }
+}
+
+/// The parent's links, confirmed or not.
+#[get("/children")]
+async fn own_children(req: HttpRequest, sing_dto: Json<SingDTO>) -> impl Responder {
+    match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => HttpResponse::Ok().json(
+            ParentLinksRepo::get_instance()
+                .await
+                .get_children(&controller.model().await.username())
+                .await,
+        ),
+        Err(err) => sing_error_responce(err),
+    }
+}
+
+#[post("/children/{student}")]
+async fn request_parent_link(
+    req: HttpRequest,
+    path: Path<String>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    let parent = match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller.model().await,
+        Err(err) => return sing_error_responce(err),
+    };
+    match parents::request_link(&parent, &path).await {
+        Ok(_) => HttpResponse::Created().finish(),
+        Err(err) => parent_link_error_responce(err),
+    }
+}
+
+#[delete("/children/{student}")]
+async fn remove_child(
+    req: HttpRequest,
+    path: Path<String>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    let parent = match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller.model().await,
+        Err(err) => return sing_error_responce(err),
+    };
+    match parents::remove_link(&parent, &parent.username(), &path).await {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(err) => parent_link_error_responce(err),
+    }
+}
+
+/// The student's links, including the ones waiting for them to confirm.
+#[get("/parents")]
+async fn own_parents(req: HttpRequest, sing_dto: Json<SingDTO>) -> impl Responder {
+    match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => HttpResponse::Ok().json(
+            ParentLinksRepo::get_instance()
+                .await
+                .get_parents(&controller.model().await.username())
+                .await,
+        ),
+        Err(err) => sing_error_responce(err),
+    }
+}
+
+#[post("/parents/{parent}/confirm")]
+async fn confirm_parent(
+    req: HttpRequest,
+    path: Path<String>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    let student = match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller.model().await,
+        Err(err) => return sing_error_responce(err),
+    };
+    match parents::confirm_link(&student, &path, &student.username()).await {
+        Ok(_) => HttpResponse::Accepted().finish(),
+        Err(err) => parent_link_error_responce(err),
+    }
+}
+
+#[delete("/parents/{parent}")]
+async fn remove_parent(
+    req: HttpRequest,
+    path: Path<String>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    let student = match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller.model().await,
+        Err(err) => return sing_error_responce(err),
+    };
+    match parents::remove_link(&student, &path, &student.username()).await {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(err) => parent_link_error_responce(err),
+    }
+}
+
+#[post("/{username}/follow")]
+async fn follow(req: HttpRequest, path: Path<String>, sing_dto: Json<SingDTO>) -> impl Responder {
+    let controller = match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller,
+        Err(err) => return sing_failed_responce(err),
+    };
+    match controller.follow(&path).await {
+        Ok(_) => HttpResponse::Accepted().finish(),
+        Err(err) => follow_error_responce(err),
+    }
+}
+
+#[delete("/{username}/follow")]
+async fn unfollow(req: HttpRequest, path: Path<String>, sing_dto: Json<SingDTO>) -> impl Responder {
+    let controller = match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller,
+        Err(err) => return sing_failed_responce(err),
+    };
+    match controller.unfollow(&path).await {
+        Ok(_) => HttpResponse::Accepted().finish(),
+        Err(err) => follow_error_responce(err),
+    }
+}
+
+#[get("/{username}/followers")]
+async fn followers(path: Path<String>) -> impl Responder {
+    match UserRepo::get_instance().await.get_by_username(&path).await {
+        Some(user) => {
+            HttpResponse::Ok().json(FollowsRepo::get_instance().await.get_followers(&user).await)
+        }
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+#[get("/{username}/following")]
+async fn following(path: Path<String>) -> impl Responder {
+    match UserRepo::get_instance().await.get_by_username(&path).await {
+        Some(user) => {
+            HttpResponse::Ok().json(FollowsRepo::get_instance().await.get_following(&user).await)
+        }
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+#[get("/{username}/profile")]
+async fn profile(
+    path: Path<String>,
+    if_none_match: Option<Header<IfNoneMatch>>,
+    if_modified_since: Option<Header<IfModifiedSince>>,
+) -> impl Responder {
+    match UserRepo::get_instance().await.get_by_username(&path).await {
+        Some(user) => cached_responce(
+            CachedRoute::Profile,
+            Conditions {
+                if_none_match,
+                if_modified_since,
+            },
+            &ProfileModel::from_user(&user).await,
+            None,
+            None,
+            false,
+        ),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+#[get("/subscriptions")]
+async fn subscriptions(req: HttpRequest, sing_dto: Json<SingDTO>) -> impl Responder {
+    match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => HttpResponse::Ok().json(
+            SubscriptionsRepo::get_instance()
+                .await
+                .get_tags(&controller.model().await)
+                .await,
+        ),
+        Err(err) => sing_failed_responce(err),
+    }
+}
+
+#[post("/subscriptions/{tag}")]
+async fn subscribe(
+    req: HttpRequest,
+    path: Path<String>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    let controller = match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller,
+        Err(err) => return sing_failed_responce(err),
+    };
+    match controller.subscribe(&path).await {
+        Ok(_) => HttpResponse::Accepted().finish(),
+        Err(err) => follow_error_responce(err),
+    }
+}
+
+#[delete("/subscriptions/{tag}")]
+async fn unsubscribe(
+    req: HttpRequest,
+    path: Path<String>,
+    sing_dto: Json<SingDTO>,
+) -> impl Responder {
+    let controller = match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller,
+        Err(err) => return sing_failed_responce(err),
+    };
+    match controller.unsubscribe(&path).await {
+        Ok(_) => HttpResponse::Accepted().finish(),
+        Err(err) => follow_error_responce(err),
+    }
+}
+
+#[get("/suspension")]
+async fn own_suspension(req: HttpRequest, sing_dto: Json<SingDTO>) -> impl Responder {
+    match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => match SuspensionsRepo::get_instance()
+            .await
+            .get_active(&controller.model().await.username())
+            .await
+        {
+            Some(suspension) => HttpResponse::Ok().json(suspension),
+            None => HttpResponse::NotFound().finish(),
+        },
+        Err(err) => sing_failed_responce(err),
+    }
+}
+
+#[post("/suspension/appeal")]
+async fn appeal(req: HttpRequest, json: Json<(AppealJSON, SingDTO)>) -> impl Responder {
+    let (appeal, sing_data) = json.clone();
+    let user = match UserController::sing(&sing_data, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller.model().await,
+        Err(err) => return sing_failed_responce(err),
+    };
+    match suspensions::appeal(&user, &appeal.text).await {
+        Ok(suspension) => HttpResponse::Accepted().json(suspension),
+        Err(err) => suspension_error_responce(err),
+    }
+}
+
+/// Sign-ins to the account and failed attempts on it.
+#[get("/security_log")]
+async fn security_log(req: HttpRequest, sing_dto: Json<SingDTO>) -> impl Responder {
+    match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => HttpResponse::Ok().json(
+            sign_in::security_log(
+                &controller.model().await.username(),
+                Limit {
+                    limit: 100,
+                    offset: None,
+                },
+            )
+            .await,
+        ),
+        Err(err) => sing_error_responce(err),
+    }
+}
+
+/// The user's own posts and comments waiting for approval.
+#[get("/pending")]
+async fn own_pending(req: HttpRequest, sing_dto: Json<SingDTO>) -> impl Responder {
+    match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => {
+            let user = controller.model().await;
+            HttpResponse::Ok().json(
+                premoderation::pending(
+                    &user,
+                    Some(user.username()),
+                    Limit {
+                        limit: 100,
+                        offset: None,
+                    },
+                )
+                .await,
+            )
+        }
+        Err(err) => sing_failed_responce(err),
+    }
+}
+
+#[get("/mentions")]
+async fn mentioned_in(req: HttpRequest, sing_dto: Json<SingDTO>) -> impl Responder {
+    let viewer = match UserController::sing(&sing_dto, client_ip(&req).as_deref()).await {
+        Ok(controller) => controller.model().await,
+        Err(err) => return sing_failed_responce(err),
+    };
+    let repo = PostsRepo::get_instance().await;
+    let mut posts = Vec::new();
+    for post in MentionsRepo::get_instance()
+        .await
+        .get_mentioning_posts(&viewer.username())
+        .await
+    {
+        if let Some(post) = repo.get_visible(post, Some(viewer.clone())).await {
+            posts.push(post);
+        }
+    }
+    HttpResponse::Ok().json(posts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{
+        http::{header::ContentType, StatusCode},
+        test, App,
+    };
+
+    #[actix_web::test]
+    async fn parameter_changes_are_routed() {
+        let app = test::init_service(App::new().service(user_scope())).await;
+        let request = test::TestRequest::patch()
+            .uri("/users/change")
+            .insert_header(ContentType::json())
+            .set_payload("[]")
+            .to_request();
+        assert_eq!(
+            test::call_service(&app, request).await.status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
@@ -0,0 +1,15 @@
+use crate::{
+    repositories::posts::PostsRepo,
+    utils::logger::{console_logger::ConsoleLogger, Logger},
+};
+
+pub const INTERVAL_SECS: u64 = 60;
+
+pub async fn run() {
+    let logger = ConsoleLogger::new();
+    match PostsRepo::get_instance().await.archive_expired().await {
+        Ok(0) => {}
+        Ok(archived) => logger.info(&format!("archived {} expired posts", archived)),
+        Err(err) => logger.error(&format!("can't archive expired posts. {}", err)),
+    }
+}
@@ -0,0 +1,29 @@
+use crate::{
+    dto::NotificationDTO,
+    models::notification::NotificationKind,
+    repositories::suspensions::SuspensionsRepo,
+    services::notifications::notify,
+    utils::logger::{console_logger::ConsoleLogger, Logger},
+};
+
+pub const INTERVAL_SECS: u64 = 60;
+
+pub async fn run() {
+    let logger = ConsoleLogger::new();
+    match SuspensionsRepo::get_instance().await.lift_expired().await {
+        Ok(lifted) => {
+            for suspension in lifted {
+                logger.info(&format!("suspension of {} expired", suspension.username()));
+                notify(NotificationDTO {
+                    recipient: suspension.username(),
+                    kind: NotificationKind::Moderation,
+                    actor: None,
+                    resource: None,
+                    details: Some("suspension expired".to_string()),
+                })
+                .await;
+            }
+        }
+        Err(err) => logger.error(&format!("can't lift expired suspensions. {}", err)),
+    }
+}
@@ -0,0 +1,26 @@
+use crate::{
+    repositories::attachments::AttachmentsRepo,
+    services::images,
+    utils::logger::{console_logger::ConsoleLogger, Logger},
+};
+
+pub const INTERVAL_SECS: u64 = 15;
+
+const BATCH_SIZE: i64 = 16;
+
+pub async fn run() {
+    let logger = ConsoleLogger::new();
+    let pending = AttachmentsRepo::get_instance()
+        .await
+        .get_pending_images(BATCH_SIZE)
+        .await;
+    for attachment in pending {
+        if let Err(err) = images::process(&attachment).await {
+            logger.error(&format!(
+                "can't generate variants of attachment {}. {:?}",
+                attachment.uuid(),
+                err
+            ));
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use actix_web::rt::{self, time};
+use std::{future::Future, time::Duration};
+
+pub mod expired_posts;
+pub mod expired_suspensions;
+pub mod image_variants;
+pub mod parent_digests;
+pub mod scheduled_posts;
+
+pub fn start() {
+    every(
+        Duration::from_secs(scheduled_posts::INTERVAL_SECS),
+        scheduled_posts::run,
+    );
+    every(
+        Duration::from_secs(expired_posts::INTERVAL_SECS),
+        expired_posts::run,
+    );
+    every(
+        Duration::from_secs(expired_suspensions::INTERVAL_SECS),
+        expired_suspensions::run,
+    );
+    every(
+        Duration::from_secs(image_variants::INTERVAL_SECS),
+        image_variants::run,
+    );
+    every(
+        Duration::from_secs(parent_digests::INTERVAL_SECS),
+        parent_digests::run,
+    );
+}
+
+fn every<F, Fut>(period: Duration, job: F)
+where
+    F: Fn() -> Fut + 'static,
+    Fut: Future<Output = ()>,
+{
+    rt::spawn(async move {
+        let mut interval = time::interval(period);
+        loop {
+            interval.tick().await;
+            job().await;
+        }
+    });
+}
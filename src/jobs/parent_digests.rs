@@ -0,0 +1,58 @@
+use crate::{
+    repositories::{parent_links::ParentLinksRepo, users::UserRepo},
+    services::parents,
+    utils::{
+        config,
+        logger::{console_logger::ConsoleLogger, Logger},
+    },
+};
+use lazy_static::lazy_static;
+
+pub const INTERVAL_SECS: u64 = 60 * 60;
+
+lazy_static! {
+    /// How often parents get a digest, set with `PARENT_DIGEST_PERIOD_HOURS`.
+    static ref PERIOD: chrono::Duration =
+        chrono::Duration::hours(config::setting("PARENT_DIGEST_PERIOD_HOURS", 24));
+}
+
+/// The first digest covers one period back. A digest that couldn't be
+/// mailed is tried again on the next run.
+pub async fn run() {
+    let logger = ConsoleLogger::new();
+    let links = ParentLinksRepo::get_instance().await;
+    let users = UserRepo::get_instance().await;
+    let now = chrono::Utc::now().naive_utc();
+    for parent in links.get_linked_parents().await {
+        let since = links.last_digest(&parent).await.unwrap_or(now - *PERIOD);
+        if now - since < *PERIOD {
+            continue;
+        }
+        let Some(parent) = users.get_by_username(&parent).await else {
+            continue;
+        };
+        match parents::send_digest(&parent, since).await {
+            Ok(posts) => {
+                if posts > 0 {
+                    logger.info(&format!(
+                        "sent a digest of {} posts to {}",
+                        posts,
+                        parent.username()
+                    ));
+                }
+                if let Err(err) = links.digest_sent(&parent.username(), now).await {
+                    logger.error(&format!(
+                        "can't save the digest of {}. {}",
+                        parent.username(),
+                        err
+                    ));
+                }
+            }
+            Err(err) => logger.error(&format!(
+                "can't send a digest to {}. {:?}",
+                parent.username(),
+                err
+            )),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use crate::{
+    repositories::posts::PostsRepo,
+    services::{mentions, realtime::post_published},
+    utils::logger::{console_logger::ConsoleLogger, Logger},
+};
+
+pub const INTERVAL_SECS: u64 = 30;
+
+pub async fn run() {
+    let logger = ConsoleLogger::new();
+    let repo = PostsRepo::get_instance().await;
+    match repo.release_scheduled().await {
+        Ok(released) => {
+            if !released.is_empty() {
+                logger.info(&format!("released {} scheduled posts", released.len()));
+            }
+            for uuid in released {
+                if let Some(post) = repo.get_by_uuid(uuid).await {
+                    mentions::sync_post(&post).await;
+                    post_published(&post).await;
+                }
+            }
+        }
+        Err(err) => logger.error(&format!("can't release scheduled posts. {}", err)),
+    }
+}
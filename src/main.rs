@@ -5,14 +5,21 @@ use lazy_static::lazy_static;
 use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod};
 use sqlx::{postgres::PgPoolOptions, PgPool};
 
-use crate::handler::posts::posts_scope;
+use crate::handler::{
+    comments::comments_scope, feed::feed_scope, media::media_scope, moderation::moderation_scope,
+    notifications::notifications_scope, posts::posts_scope, realtime::realtime_scope,
+    revisions::revisions_scope,
+};
 
+mod cli;
 pub mod controllers;
 pub mod dto;
 mod handler;
+pub mod jobs;
 pub mod models;
 pub mod prelude;
 pub mod repositories;
+pub mod services;
 pub mod types;
 pub mod utils;
 pub mod validators;
@@ -24,6 +31,8 @@ lazy_static! {
         .parse()
         .expect("PORT must be a number");
     static ref DB_ADDRES: String = dotenv::var("DB_ADDRES").expect("DB_ADDRES must be set");
+    static ref REDIS_ADDRES: String =
+        dotenv::var("REDIS_ADDRES").expect("REDIS_ADDRES must be set");
 }
 
 pub async fn get_db_pool() -> PgPool {
@@ -32,14 +41,33 @@ pub async fn get_db_pool() -> PgPool {
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(result) = cli::run(&args).await {
+        return result;
+    }
     let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
     builder
         .set_private_key_file("key.pem", SslFiletype::PEM)
         .unwrap();
     builder.set_certificate_chain_file("cert.pem").unwrap();
+    println!(
+        "registration mode: {:?}",
+        services::invitations::registration_mode()
+    );
+    jobs::start();
+    services::realtime::start();
     HttpServer::new(|| {
         println!("started");
-        App::new().service(user_scope()).service(posts_scope())
+        App::new()
+            .service(user_scope())
+            .service(posts_scope())
+            .service(comments_scope())
+            .service(feed_scope())
+            .service(notifications_scope())
+            .service(realtime_scope())
+            .service(media_scope())
+            .service(revisions_scope())
+            .service(moderation_scope())
     })
     .bind_openssl("127.0.0.1:8080", builder)?
     .run()
@@ -0,0 +1,156 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, types::Uuid, FromRow, Row};
+use std::str::FromStr;
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "attachment_kind")]
+pub enum AttachmentKind {
+    Image,
+    Document,
+}
+
+/// Images are `Pending` until their variants are generated, documents are
+/// `Ready` right away.
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "processing_state")]
+pub enum ProcessingState {
+    Pending,
+    Ready,
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageVariant {
+    Thumbnail,
+    Medium,
+    Original,
+}
+
+impl ImageVariant {
+    pub const ALL: [ImageVariant; 3] = [
+        ImageVariant::Thumbnail,
+        ImageVariant::Medium,
+        ImageVariant::Original,
+    ];
+
+    /// Longest side in pixels, the original is only re-encoded.
+    pub fn max_side(&self) -> Option<u32> {
+        match self {
+            ImageVariant::Thumbnail => Some(320),
+            ImageVariant::Medium => Some(1280),
+            ImageVariant::Original => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImageVariant::Thumbnail => "thumbnail",
+            ImageVariant::Medium => "medium",
+            ImageVariant::Original => "original",
+        }
+    }
+}
+
+impl FromStr for ImageVariant {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ImageVariant::ALL
+            .into_iter()
+            .find(|variant| variant.name() == s)
+            .ok_or(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageVariants {
+    thumbnail: String,
+    medium: String,
+    original: String,
+}
+
+impl ImageVariants {
+    fn of(uuid: &Uuid) -> Self {
+        let url = |variant: ImageVariant| format!("/media/{uuid}/{}", variant.name());
+        ImageVariants {
+            thumbnail: url(ImageVariant::Thumbnail),
+            medium: url(ImageVariant::Medium),
+            original: url(ImageVariant::Original),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AttachmentModel {
+    uuid: String,
+    post: String,
+    filename: String,
+    content_type: String,
+    kind: AttachmentKind,
+    size: i64,
+    uploaded_at: NaiveDateTime,
+    processing: ProcessingState,
+    url: String,
+    variants: Option<ImageVariants>,
+    #[serde(skip)]
+    storage_key: String,
+}
+
+impl AttachmentModel {
+    pub fn uuid(&self) -> Uuid {
+        Uuid::parse_str(&self.uuid).unwrap()
+    }
+
+    pub fn post(&self) -> Uuid {
+        Uuid::parse_str(&self.post).unwrap()
+    }
+
+    pub fn filename(&self) -> String {
+        self.filename.clone()
+    }
+
+    pub fn content_type(&self) -> String {
+        self.content_type.clone()
+    }
+
+    pub fn kind(&self) -> AttachmentKind {
+        self.kind
+    }
+
+    pub fn processing(&self) -> ProcessingState {
+        self.processing
+    }
+
+    /// The original variant replaces the uploaded file, so it shares its key.
+    pub fn storage_key(&self, variant: ImageVariant) -> String {
+        match variant {
+            ImageVariant::Original => self.storage_key.clone(),
+            variant => format!("variants/{}/{}", self.uuid, variant.name()),
+        }
+    }
+}
+
+impl FromRow<'_, PgRow> for AttachmentModel {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        let uuid: Uuid = row.get("uuid");
+        let kind: AttachmentKind = row.get("kind");
+        let processing: ProcessingState = row.get("processing");
+        Ok(AttachmentModel {
+            uuid: uuid.to_string(),
+            post: row.get::<Uuid, &str>("post").to_string(),
+            filename: row.get("filename"),
+            content_type: row.get("content_type"),
+            kind,
+            size: row.get("size"),
+            uploaded_at: row.get("uploaded_at"),
+            processing,
+            url: format!("/posts/attachments/{uuid}"),
+            variants: match (kind, processing) {
+                (AttachmentKind::Image, ProcessingState::Ready) => Some(ImageVariants::of(&uuid)),
+                _ => None,
+            },
+            storage_key: row.get("storage_key"),
+        })
+    }
+}
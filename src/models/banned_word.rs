@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, FromRow, Row};
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "word_language")]
+pub enum Language {
+    Uz,
+    Ru,
+    En,
+}
+
+/// Ordered from the mildest to the strictest.
+#[derive(
+    sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[sqlx(type_name = "word_severity")]
+pub enum WordSeverity {
+    Mask,
+    Moderate,
+    Reject,
+}
+
+/// A word ending with `*` matches every word starting with it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BannedWord {
+    pub word: String,
+    pub language: Language,
+    pub severity: WordSeverity,
+}
+
+impl FromRow<'_, PgRow> for BannedWord {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(BannedWord {
+            word: row.get("word"),
+            language: row.get("language"),
+            severity: row.get("severity"),
+        })
+    }
+}
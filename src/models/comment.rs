@@ -1,11 +1,14 @@
-use super::{post::PostModel, user::UserModel};
+use super::{mention::MentionSpan, post::PostModel, user::UserModel};
 use crate::{
     controllers::users::UserController,
     dto::PublishCommentDTO,
-    prelude::{Commentable, Editable, Markable, PublishDTOBuilder, Resource},
+    prelude::{
+        Commentable, EditError, Editable, Markable, PublishDTOBuilder, Resource, ResourceKind,
+    },
     repositories::{
         comments::CommentsRepo,
         marks_repo::{comments::CommentsMarkRepo, MarkAbleRepo},
+        mentions::MentionsRepo,
         posts::PostsRepo,
         users::UserRepo,
     },
@@ -22,10 +25,15 @@ pub struct CommentModel {
     author: UserModel,
     comments: Vec<CommentModel>,
     content: String,
+    content_html: String,
+    mentions: Vec<MentionSpan>,
     dislikes: i64,
     edited: EditedState,
     likes: i64,
     published_at: NaiveDateTime,
+    version: i32,
+    hidden: bool,
+    pending: bool,
     replys_for: Option<String>,
     post: PostModel,
     uuid: String,
@@ -49,7 +57,12 @@ impl CommentModel {
         CommentModel {
             uuid: uuid.to_string(),
             content: row.get("content"),
+            content_html: row.get("content_html"),
+            mentions: MentionsRepo::get_instance().await.get_spans(&uuid).await,
             published_at: row.get("published_at"),
+            version: row.get("version"),
+            hidden: row.get("hidden"),
+            pending: row.get("pending"),
             edited: EditedState::from_row(&row).unwrap(),
             author: UserRepo::get_instance()
                 .await
@@ -72,9 +85,33 @@ impl CommentModel {
         self.content.clone()
     }
 
+    pub fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+
+    /// Waits for a teacher or an administrator to approve it.
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    pub fn author_username(&self) -> String {
+        self.author.username()
+    }
+
+    pub fn last_modified(&self) -> NaiveDateTime {
+        self.edited.edited_at().unwrap_or(self.published_at)
+    }
+
     pub fn under_post(&self) -> PostModel {
         self.post.clone()
     }
+
+    /// The comment this one replies to, `None` for a comment on the post.
+    pub fn replys_for(&self) -> Option<Uuid> {
+        self.replys_for
+            .as_deref()
+            .and_then(|uuid| Uuid::from_str(uuid).ok())
+    }
 }
 
 impl PublishDTOBuilder for CommentModel {
@@ -95,15 +132,24 @@ impl PublishDTOBuilder for CommentModel {
 impl Commentable for CommentModel {}
 
 impl Editable for CommentModel {
-    fn edit(&self, content: &str, user: &UserController) {
+    fn edit(
+        &self,
+        content: &str,
+        user: &UserController,
+        expected_version: i32,
+    ) -> Result<(), EditError> {
         futures::executor::block_on(async {
             CommentsRepo::get_instance()
                 .await
-                .edit(self.clone(), content.to_string(), user)
+                .edit(self.clone(), content.to_string(), user, expected_version)
                 .await
-                .unwrap();
+                .map(|_| ())
         })
     }
+
+    fn version(&self) -> i32 {
+        self.version
+    }
 }
 
 impl Markable for CommentModel {
@@ -128,4 +174,8 @@ impl Resource for CommentModel {
     fn author(&self) -> UserModel {
         self.author.clone()
     }
+
+    fn kind(&self) -> ResourceKind {
+        ResourceKind::Comment
+    }
 }
@@ -0,0 +1,35 @@
+use sqlx::{postgres::PgRow, FromRow, Row};
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "token_purpose")]
+pub enum TokenPurpose {
+    EmailVerification,
+    PasswordReset,
+}
+
+/// Only a hash of the token is stored, the token itself is in the mail.
+#[derive(Debug, Clone)]
+pub struct EmailTokenModel {
+    username: String,
+    email: String,
+}
+
+impl EmailTokenModel {
+    pub fn username(&self) -> String {
+        self.username.clone()
+    }
+
+    /// The address the token was sent to.
+    pub fn email(&self) -> String {
+        self.email.clone()
+    }
+}
+
+impl FromRow<'_, PgRow> for EmailTokenModel {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(EmailTokenModel {
+            username: row.get("username"),
+            email: row.get("email"),
+        })
+    }
+}
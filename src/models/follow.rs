@@ -0,0 +1,20 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::{postgres::PgRow, FromRow, Row};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FollowModel {
+    follower: String,
+    followed: String,
+    followed_at: NaiveDateTime,
+}
+
+impl FromRow<'_, PgRow> for FollowModel {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(FollowModel {
+            follower: row.get("follower"),
+            followed: row.get("followed"),
+            followed_at: row.get("followed_at"),
+        })
+    }
+}
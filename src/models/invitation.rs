@@ -0,0 +1,72 @@
+use super::user::UserType;
+use crate::types::{Class, Subject};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::{postgres::PgRow, types::Uuid, FromRow, Row};
+use std::str::FromStr;
+
+/// Only a hash of the code is stored, the code itself is shown once to the
+/// administrator who created the invitation.
+#[derive(Debug, Clone, Serialize)]
+pub struct InvitationModel {
+    uuid: Uuid,
+    created_by: String,
+    /// What the invited users register as, any type but administrator if
+    /// it's `None`.
+    user_specs: Option<UserType>,
+    max_uses: i32,
+    uses: i32,
+    expires_at: NaiveDateTime,
+    created_at: NaiveDateTime,
+    revoked: bool,
+}
+
+impl InvitationModel {
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn user_specs(&self) -> Option<UserType> {
+        self.user_specs.clone()
+    }
+
+    pub fn expires_at(&self) -> NaiveDateTime {
+        self.expires_at
+    }
+}
+
+fn user_specs(row: &PgRow) -> Option<UserType> {
+    let user_specs: Option<String> = row.get("user_specs");
+    Some(match user_specs?.as_str() {
+        "Teacher" => UserType::Teacher {
+            subject: Subject::from_str(&row.get::<String, &str>("subject")).ok()?,
+        },
+        "Student" => {
+            let class_num: i16 = row.get("class_num");
+            let class_char: String = row.get("class_char");
+            UserType::Student {
+                class: Class::from(*class_char.as_bytes().first()?, class_num as u8).ok()?,
+            }
+        }
+        "Administrator" => UserType::Administrator {
+            job_title: row.get("job_title"),
+        },
+        "Parent" => UserType::Parent,
+        _ => UserType::Other,
+    })
+}
+
+impl FromRow<'_, PgRow> for InvitationModel {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(InvitationModel {
+            uuid: row.get("uuid"),
+            created_by: row.get("created_by"),
+            user_specs: user_specs(row),
+            max_uses: row.get("max_uses"),
+            uses: row.get("uses"),
+            expires_at: row.get("expires_at"),
+            created_at: row.get("created_at"),
+            revoked: row.get("revoked"),
+        })
+    }
+}
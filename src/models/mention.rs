@@ -0,0 +1,21 @@
+use serde::Serialize;
+use sqlx::{postgres::PgRow, FromRow, Row};
+
+/// `@username` inside a content. `start` and `end` are character offsets of
+/// the span including the `@`, `end` is exclusive.
+#[derive(Debug, Clone, Serialize)]
+pub struct MentionSpan {
+    pub username: String,
+    pub start: i32,
+    pub end: i32,
+}
+
+impl FromRow<'_, PgRow> for MentionSpan {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(MentionSpan {
+            username: row.get("mentioned"),
+            start: row.get("span_start"),
+            end: row.get("span_end"),
+        })
+    }
+}
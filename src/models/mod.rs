@@ -1,3 +1,17 @@
+pub mod attachment;
+pub mod banned_word;
 pub mod comment;
+pub mod email_token;
+pub mod follow;
+pub mod invitation;
+pub mod mention;
+pub mod notification;
+pub mod parent_link;
 pub mod post;
+pub mod profile;
+pub mod report;
+pub mod revision;
+pub mod sign_in_attempt;
+pub mod suspension;
+pub mod two_factor;
 pub mod user;
@@ -0,0 +1,70 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, types::Uuid, FromRow, Row};
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "notification_kind")]
+pub enum NotificationKind {
+    Reply,
+    CommentOnPost,
+    Like,
+    Mention,
+    Follow,
+    Moderation,
+    ParentLink,
+    Digest,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NotificationModel {
+    uuid: String,
+    recipient: String,
+    kind: NotificationKind,
+    actor: Option<String>,
+    resource: Option<String>,
+    details: Option<String>,
+    created_at: NaiveDateTime,
+    read: bool,
+}
+
+impl NotificationModel {
+    pub fn uuid(&self) -> Uuid {
+        Uuid::parse_str(&self.uuid).unwrap()
+    }
+
+    pub fn recipient(&self) -> String {
+        self.recipient.clone()
+    }
+}
+
+impl FromRow<'_, PgRow> for NotificationModel {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(NotificationModel {
+            uuid: row.get::<Uuid, &str>("uuid").to_string(),
+            recipient: row.get("recipient"),
+            kind: row.get("kind"),
+            actor: row.get("actor"),
+            resource: row
+                .get::<Option<Uuid>, &str>("resource")
+                .map(|resource| resource.to_string()),
+            details: row.get("details"),
+            created_at: row.get("created_at"),
+            read: row.get::<Option<NaiveDateTime>, &str>("read_at").is_some(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationPreference {
+    pub kind: NotificationKind,
+    pub enabled: bool,
+}
+
+impl FromRow<'_, PgRow> for NotificationPreference {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(NotificationPreference {
+            kind: row.get("kind"),
+            enabled: row.get("enabled"),
+        })
+    }
+}
@@ -0,0 +1,40 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::{postgres::PgRow, FromRow, Row};
+
+/// A parent is connected to a student once the student or an administrator
+/// confirmed the link.
+#[derive(Debug, Clone, Serialize)]
+pub struct ParentLinkModel {
+    parent: String,
+    student: String,
+    requested_at: NaiveDateTime,
+    confirmed_by: Option<String>,
+    confirmed_at: Option<NaiveDateTime>,
+}
+
+impl ParentLinkModel {
+    pub fn parent(&self) -> String {
+        self.parent.clone()
+    }
+
+    pub fn student(&self) -> String {
+        self.student.clone()
+    }
+
+    pub fn is_confirmed(&self) -> bool {
+        self.confirmed_at.is_some()
+    }
+}
+
+impl FromRow<'_, PgRow> for ParentLinkModel {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(ParentLinkModel {
+            parent: row.get("parent"),
+            student: row.get("student"),
+            requested_at: row.get("requested_at"),
+            confirmed_by: row.get("confirmed_by"),
+            confirmed_at: row.get("confirmed_at"),
+        })
+    }
+}
@@ -1,15 +1,21 @@
-use super::{comment::CommentModel, user::UserModel};
+use super::{
+    attachment::AttachmentModel, comment::CommentModel, mention::MentionSpan, user::UserModel,
+};
 use crate::{
     controllers::users::UserController,
     dto::PublishCommentDTO,
-    prelude::{Commentable, Editable, Markable, PublishDTOBuilder, Resource},
+    prelude::{
+        Commentable, EditError, Editable, Markable, PublishDTOBuilder, Resource, ResourceKind,
+    },
     repositories::{
+        attachments::AttachmentsRepo,
         comments::CommentsRepo,
         marks_repo::{posts::PostsMarkRepo, MarkAbleRepo},
+        mentions::MentionsRepo,
         posts::PostsRepo,
         users::UserRepo,
     },
-    types::EditedState,
+    types::{Audience, EditedState, PostStatus},
 };
 use chrono::NaiveDateTime;
 use serde::Serialize;
@@ -21,13 +27,23 @@ pub struct PostModel {
     uuid: String,
     title: String,
     content: String,
+    content_html: String,
+    mentions: Vec<MentionSpan>,
     published_at: NaiveDateTime,
     edited: EditedState,
+    status: PostStatus,
+    expires_at: Option<NaiveDateTime>,
+    pinned: bool,
+    version: i32,
+    hidden: bool,
+    pending: bool,
     author: String,
     likes: i64,
     dislikes: i64,
     comments: Vec<CommentModel>,
     tags: Vec<String>,
+    audience: Vec<Audience>,
+    attachments: Vec<AttachmentModel>,
     raiting: f32,
 }
 
@@ -43,14 +59,27 @@ impl PostModel {
             uuid: uuid.to_string(),
             title: row.get("title"),
             content: row.get("content"),
+            content_html: row.get("content_html"),
             published_at: row.get("published_at"),
             edited: EditedState::from_row(&row).unwrap(),
+            status: PostStatus::from_row(row).unwrap(),
+            expires_at: row.get("expires_at"),
+            pinned: row.get("pinned"),
+            version: row.get("version"),
+            hidden: row.get("hidden"),
+            pending: row.get("pending"),
             author: row.get("author"),
             tags: row.get("tags"),
             likes: row.get("likes"),
             raiting: row.get("raiting"),
             dislikes: row.get("dislikes"),
             comments: CommentsRepo::get_instance().await.get_by_post(&uuid).await,
+            audience: PostsRepo::get_instance().await.get_audience(&uuid).await,
+            mentions: MentionsRepo::get_instance().await.get_spans(&uuid).await,
+            attachments: AttachmentsRepo::get_instance()
+                .await
+                .get_by_post(&uuid)
+                .await,
         }
     }
 
@@ -69,6 +98,35 @@ impl PostModel {
     pub fn title(&self) -> String {
         self.title.clone()
     }
+
+    pub fn tags(&self) -> Vec<String> {
+        self.tags.clone()
+    }
+
+    pub fn author_username(&self) -> String {
+        self.author.clone()
+    }
+
+    pub fn attachments(&self) -> Vec<AttachmentModel> {
+        self.attachments.clone()
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+
+    /// Waits for a teacher or an administrator to approve it.
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    pub fn last_modified(&self) -> NaiveDateTime {
+        self.edited.edited_at().unwrap_or(self.published_at)
+    }
+
+    pub fn status(&self) -> PostStatus {
+        self.status.clone()
+    }
 }
 
 impl Markable for PostModel {
@@ -88,14 +146,23 @@ impl Markable for PostModel {
 }
 
 impl Editable for PostModel {
-    fn edit(&self, content: &str, user: &UserController) {
+    fn edit(
+        &self,
+        content: &str,
+        user: &UserController,
+        expected_version: i32,
+    ) -> Result<(), EditError> {
         futures::executor::block_on(async {
             PostsRepo::get_instance()
                 .await
-                .edit_content(self.clone(), content, user)
-                .await;
+                .edit_content(self.clone(), content, user, expected_version)
+                .await
         })
     }
+
+    fn version(&self) -> i32 {
+        self.version
+    }
 }
 
 impl PublishDTOBuilder for PostModel {
@@ -115,4 +182,8 @@ impl Resource for PostModel {
     fn author(&self) -> UserModel {
         futures::executor::block_on(PostModel::author(self))
     }
+
+    fn kind(&self) -> ResourceKind {
+        ResourceKind::Post
+    }
 }
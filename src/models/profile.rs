@@ -0,0 +1,32 @@
+use super::user::{UserModel, UserType};
+use crate::repositories::{follows::FollowsRepo, subscriptions::SubscriptionsRepo};
+use serde::Serialize;
+
+/// What other users see about someone: no contacts, but social counters.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileModel {
+    username: String,
+    first_name: String,
+    last_name: String,
+    about: String,
+    user_specs: UserType,
+    followers: i64,
+    following: i64,
+    subscriptions: i64,
+}
+
+impl ProfileModel {
+    pub async fn from_user(user: &UserModel) -> Self {
+        let follows = FollowsRepo::get_instance().await;
+        ProfileModel {
+            username: user.username(),
+            first_name: user.first_name(),
+            last_name: user.last_name(),
+            about: user.about(),
+            user_specs: user.user_specs(),
+            followers: follows.count_followers(user).await,
+            following: follows.count_following(user).await,
+            subscriptions: SubscriptionsRepo::get_instance().await.count(user).await,
+        }
+    }
+}
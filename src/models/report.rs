@@ -0,0 +1,114 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, types::Uuid, FromRow, Row};
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "report_reason")]
+pub enum ReportReason {
+    Spam,
+    Harassment,
+    Inappropriate,
+    Misinformation,
+    Other,
+}
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "report_target")]
+pub enum ReportTargetKind {
+    Post,
+    Comment,
+    Profile,
+}
+
+/// `Hide`, `Delete` and `Restore` apply to posts and comments only. A deleted
+/// item keeps its row but loses its text for good, a restored one is visible
+/// again. `Suspend` applies to the author.
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "moderation_action")]
+pub enum ModerationAction {
+    Dismiss,
+    Hide,
+    Delete,
+    Warn,
+    Suspend,
+    Restore,
+}
+
+/// Reports without a reporter are filed by the content policy.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportModel {
+    uuid: String,
+    reporter: Option<String>,
+    target_kind: ReportTargetKind,
+    target: String,
+    reason: ReportReason,
+    details: Option<String>,
+    created_at: NaiveDateTime,
+    resolved_at: Option<NaiveDateTime>,
+    resolution: Option<ModerationAction>,
+}
+
+impl FromRow<'_, PgRow> for ReportModel {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(ReportModel {
+            uuid: row.get::<Uuid, &str>("uuid").to_string(),
+            reporter: row.get("reporter"),
+            target_kind: row.get("target_kind"),
+            target: row.get("target"),
+            reason: row.get("reason"),
+            details: row.get("details"),
+            created_at: row.get("created_at"),
+            resolved_at: row.get("resolved_at"),
+            resolution: row.get("resolution"),
+        })
+    }
+}
+
+/// Open reports of one post, comment or profile.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueItemModel {
+    target_kind: ReportTargetKind,
+    target: String,
+    reports: i64,
+    reasons: Vec<String>,
+    first_reported_at: NaiveDateTime,
+    last_reported_at: NaiveDateTime,
+}
+
+impl FromRow<'_, PgRow> for QueueItemModel {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(QueueItemModel {
+            target_kind: row.get("target_kind"),
+            target: row.get("target"),
+            reports: row.get("reports"),
+            reasons: row.get("reasons"),
+            first_reported_at: row.get("first_reported_at"),
+            last_reported_at: row.get("last_reported_at"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModerationActionModel {
+    uuid: String,
+    moderator: String,
+    target_kind: ReportTargetKind,
+    target: String,
+    action: ModerationAction,
+    note: Option<String>,
+    created_at: NaiveDateTime,
+}
+
+impl FromRow<'_, PgRow> for ModerationActionModel {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(ModerationActionModel {
+            uuid: row.get::<Uuid, &str>("uuid").to_string(),
+            moderator: row.get("moderator"),
+            target_kind: row.get("target_kind"),
+            target: row.get("target"),
+            action: row.get("action"),
+            note: row.get("note"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
@@ -0,0 +1,42 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::{postgres::PgRow, types::Uuid, FromRow, Row};
+
+/// A version of a post or a comment. Revision 1 is the text as it was
+/// published, `title` is `None` for comments.
+#[derive(Debug, Clone, Serialize)]
+pub struct RevisionModel {
+    resource: String,
+    number: i32,
+    title: Option<String>,
+    content: String,
+    edited_by: String,
+    created_at: NaiveDateTime,
+}
+
+impl RevisionModel {
+    pub fn number(&self) -> i32 {
+        self.number
+    }
+
+    pub fn title(&self) -> Option<String> {
+        self.title.clone()
+    }
+
+    pub fn content(&self) -> String {
+        self.content.clone()
+    }
+}
+
+impl FromRow<'_, PgRow> for RevisionModel {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(RevisionModel {
+            resource: row.get::<Uuid, &str>("resource").to_string(),
+            number: row.get("number"),
+            title: row.get("title"),
+            content: row.get("content"),
+            edited_by: row.get("edited_by"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
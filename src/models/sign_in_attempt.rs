@@ -0,0 +1,20 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::{postgres::PgRow, FromRow, Row};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SignInAttemptModel {
+    ip: Option<String>,
+    succeeded: bool,
+    attempted_at: NaiveDateTime,
+}
+
+impl FromRow<'_, PgRow> for SignInAttemptModel {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(SignInAttemptModel {
+            ip: row.get("ip"),
+            succeeded: row.get("succeeded"),
+            attempted_at: row.get("attempted_at"),
+        })
+    }
+}
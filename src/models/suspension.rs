@@ -0,0 +1,64 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, types::Uuid, FromRow, Row};
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "appeal_status")]
+pub enum AppealStatus {
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+/// A suspension without `until` is a ban. It stops applying once `until`
+/// passes or it's lifted.
+#[derive(Debug, Clone, Serialize)]
+pub struct SuspensionModel {
+    uuid: String,
+    username: String,
+    reason: String,
+    issued_by: String,
+    created_at: NaiveDateTime,
+    until: Option<NaiveDateTime>,
+    lifted_at: Option<NaiveDateTime>,
+    appeal: Option<String>,
+    appeal_status: Option<AppealStatus>,
+}
+
+impl SuspensionModel {
+    pub fn uuid(&self) -> Uuid {
+        Uuid::parse_str(&self.uuid).unwrap()
+    }
+
+    pub fn username(&self) -> String {
+        self.username.clone()
+    }
+
+    pub fn reason(&self) -> String {
+        self.reason.clone()
+    }
+
+    pub fn until(&self) -> Option<NaiveDateTime> {
+        self.until
+    }
+
+    pub fn appeal_status(&self) -> Option<AppealStatus> {
+        self.appeal_status
+    }
+}
+
+impl FromRow<'_, PgRow> for SuspensionModel {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(SuspensionModel {
+            uuid: row.get::<Uuid, &str>("uuid").to_string(),
+            username: row.get("username"),
+            reason: row.get("reason"),
+            issued_by: row.get("issued_by"),
+            created_at: row.get("created_at"),
+            until: row.get("until"),
+            lifted_at: row.get("lifted_at"),
+            appeal: row.get("appeal"),
+            appeal_status: row.get("appeal_status"),
+        })
+    }
+}
@@ -0,0 +1,29 @@
+use sqlx::{postgres::PgRow, FromRow, Row};
+
+/// The user's TOTP secret. It only guards sign-ins once the user proved
+/// their authenticator has it.
+#[derive(Debug, Clone)]
+pub struct TwoFactorModel {
+    secret: String,
+    enabled: bool,
+}
+
+impl TwoFactorModel {
+    /// Base32, the way authenticator apps take it.
+    pub fn secret(&self) -> String {
+        self.secret.clone()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+impl FromRow<'_, PgRow> for TwoFactorModel {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(TwoFactorModel {
+            secret: row.get("secret"),
+            enabled: row.get("enabled"),
+        })
+    }
+}
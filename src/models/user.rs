@@ -16,6 +16,7 @@ pub struct UserModel {
     #[serde(skip)]
     password: String,
     email: String,
+    email_verified: bool,
     phone_number: Option<String>,
     birth_date: NaiveDate,
     user_specs: UserType,
@@ -53,17 +54,29 @@ impl UserModel {
         self.email.clone()
     }
 
+    pub fn is_email_verified(&self) -> bool {
+        self.email_verified
+    }
+
     pub fn uuid(&self) -> Uuid {
         Uuid::from_str(&self.uuid).unwrap()
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum UserType {
-    Teacher { subject: Subject },
-    Student { class: Class },
-    Administrator { job_title: String },
+    Teacher {
+        subject: Subject,
+    },
+    Student {
+        class: Class,
+    },
+    Administrator {
+        job_title: String,
+    },
     Other,
+    /// Linked to their children's student accounts through parent links.
+    Parent,
 }
 
 impl UserType {
@@ -82,18 +95,36 @@ impl UserType {
     }
 
     pub fn is_school_member(&self) -> bool {
-        if let Self::Other = self {
+        if let Self::Other | Self::Parent = self {
             return false;
         }
         true
     }
 
+    pub fn is_parent(&self) -> bool {
+        if let Self::Parent = self {
+            return true;
+        }
+        false
+    }
+
     pub fn is_teacher(&self) -> bool {
         if let Self::Teacher { subject: _ } = self {
             return true;
         }
         false
     }
+
+    /// Lowercase name of the type, the way settings in .env refer to it.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Teacher { subject: _ } => "teacher",
+            Self::Student { class: _ } => "student",
+            Self::Administrator { job_title: _ } => "administrator",
+            Self::Other => "other",
+            Self::Parent => "parent",
+        }
+    }
 }
 
 impl FromRow<'_, PgRow> for UserModel {
@@ -105,6 +136,7 @@ impl FromRow<'_, PgRow> for UserModel {
             last_name: row.get("last_name"),
             password: row.get("password"),
             email: row.get("email"),
+            email_verified: row.get("email_verified"),
             phone_number: row.get("phone_number"),
             birth_date: row.get("birth_date"),
             about: row.get("about"),
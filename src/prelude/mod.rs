@@ -1,9 +1,10 @@
 use crate::{
     controllers::{users::UserController, Controller},
     dto::PublishCommentDTO,
-    models::user::UserModel,
-    repositories::comments::CommentsRepo,
+    models::{comment::CommentModel, user::UserModel},
+    repositories::comments::{CommentsRepo, PublishError},
 };
+use serde::Serialize;
 use uuid::Uuid;
 
 pub trait QueryInterpreter {
@@ -23,23 +24,49 @@ pub trait PublishDTOBuilder {
 }
 
 pub trait Commentable: PublishDTOBuilder {
-    fn comment(&self, content: String, author: &UserController) {
+    fn comment(
+        &self,
+        content: String,
+        author: &UserController,
+    ) -> Result<CommentModel, PublishError> {
         futures::executor::block_on(async {
             CommentsRepo::get_instance()
                 .await
                 .publish_comment(self.build_dto(content, author.model().await))
                 .await
-                .unwrap();
         })
     }
 }
 
+#[derive(Debug, Serialize)]
 pub enum EditError {
     EditsNotAuthor,
+    Stale { current_version: i32 },
+    Rejected { words: Vec<String> },
+    ProblemsWithDB,
 }
 
+impl From<sqlx::Error> for EditError {
+    fn from(_: sqlx::Error) -> Self {
+        EditError::ProblemsWithDB
+    }
+}
+
+/// Edits only apply to the version the editor has seen.
 pub trait Editable {
-    fn edit(&self, content: &str, user: &UserController);
+    fn edit(
+        &self,
+        content: &str,
+        user: &UserController,
+        expected_version: i32,
+    ) -> Result<(), EditError>;
+    fn version(&self) -> i32;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResourceKind {
+    Post,
+    Comment,
 }
 
 pub trait Resource
@@ -47,6 +74,7 @@ where
     Self: Markable + Commentable + Editable + Sync,
 {
     fn author(&self) -> UserModel;
+    fn kind(&self) -> ResourceKind;
 }
 
 pub enum SortingDirection<T>
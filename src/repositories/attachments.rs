@@ -0,0 +1,111 @@
+use crate::{
+    dto::AttachmentDTO,
+    get_db_pool,
+    models::attachment::{AttachmentKind, AttachmentModel, ProcessingState},
+};
+use sqlx::{types::Uuid, PgPool};
+
+pub struct AttachmentsRepo(PgPool);
+
+impl AttachmentsRepo {
+    pub async fn get_instance() -> AttachmentsRepo {
+        AttachmentsRepo(get_db_pool().await)
+    }
+
+    pub async fn create(&self, attachment: AttachmentDTO) -> Result<AttachmentModel, sqlx::Error> {
+        sqlx::query_as::<_, AttachmentModel>(
+            r#"
+            insert into attachments (
+                uuid,
+                post,
+                filename,
+                content_type,
+                kind,
+                size,
+                storage_key,
+                processing,
+                uploaded_at
+            )
+            values ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            returning *;
+            "#,
+        )
+        .bind(attachment.uuid)
+        .bind(attachment.post)
+        .bind(attachment.filename)
+        .bind(attachment.content_type)
+        .bind(attachment.kind)
+        .bind(attachment.size)
+        .bind(attachment.storage_key)
+        .bind(attachment.processing)
+        .bind(chrono::Utc::now().naive_utc())
+        .fetch_one(&self.0)
+        .await
+    }
+
+    pub async fn get_by_uuid(&self, uuid: &Uuid) -> Option<AttachmentModel> {
+        sqlx::query_as::<_, AttachmentModel>("select * from attachments where uuid = $1;")
+            .bind(uuid)
+            .fetch_optional(&self.0)
+            .await
+            .ok()
+            .flatten()
+    }
+
+    pub async fn get_by_post(&self, post: &Uuid) -> Vec<AttachmentModel> {
+        sqlx::query_as::<_, AttachmentModel>(
+            "select * from attachments where post = $1 order by uploaded_at;",
+        )
+        .bind(post)
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+
+    pub async fn delete(&self, attachment: &AttachmentModel) -> Result<(), sqlx::Error> {
+        sqlx::query("delete from attachments where uuid = $1;")
+            .bind(attachment.uuid())
+            .execute(&self.0)
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn get_pending_images(&self, limit: i64) -> Vec<AttachmentModel> {
+        sqlx::query_as::<_, AttachmentModel>(
+            "select * from attachments where kind = $1 and processing = $2 order by uploaded_at limit $3;",
+        )
+        .bind(AttachmentKind::Image)
+        .bind(ProcessingState::Pending)
+        .bind(limit)
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+
+    /// Records the outcome of image processing along with the type and size of
+    /// the re-encoded original, which replaced the upload.
+    pub async fn set_processed(
+        &self,
+        attachment: &AttachmentModel,
+        processing: ProcessingState,
+        original: Option<(&str, i64)>,
+    ) -> Result<(), sqlx::Error> {
+        let (content_type, size) = original.unzip();
+        sqlx::query(
+            r#"
+            update attachments set
+                processing = $2,
+                content_type = coalesce($3, content_type),
+                size = coalesce($4, size)
+            where uuid = $1;
+            "#,
+        )
+        .bind(attachment.uuid())
+        .bind(processing)
+        .bind(content_type)
+        .bind(size)
+        .execute(&self.0)
+        .await
+        .map(|_| ())
+    }
+}
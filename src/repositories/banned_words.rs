@@ -0,0 +1,51 @@
+use crate::{
+    get_db_pool,
+    models::banned_word::{BannedWord, Language},
+};
+use sqlx::PgPool;
+
+pub struct BannedWordsRepo(PgPool);
+
+impl BannedWordsRepo {
+    pub async fn get_instance() -> BannedWordsRepo {
+        BannedWordsRepo(get_db_pool().await)
+    }
+
+    pub async fn get_all(&self) -> Vec<BannedWord> {
+        sqlx::query_as::<_, BannedWord>("select * from banned_words;")
+            .fetch_all(&self.0)
+            .await
+            .unwrap_or_default()
+    }
+
+    pub async fn get_by_language(&self, language: Language) -> Vec<BannedWord> {
+        sqlx::query_as::<_, BannedWord>(
+            "select * from banned_words where language = $1 order by word;",
+        )
+        .bind(language)
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+
+    pub async fn set(&self, word: &BannedWord) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "insert into banned_words (word, language, severity) values ($1, $2, $3) on conflict (word, language) do update set severity = excluded.severity;",
+        )
+        .bind(word.word.to_lowercase())
+        .bind(word.language)
+        .bind(word.severity)
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete(&self, word: &str, language: Language) -> Result<u64, sqlx::Error> {
+        sqlx::query("delete from banned_words where word = $1 and language = $2;")
+            .bind(word.to_lowercase())
+            .bind(language)
+            .execute(&self.0)
+            .await
+            .map(|result| result.rows_affected())
+    }
+}
@@ -1,21 +1,38 @@
 use crate::{
     controllers::{users::UserController, Controller},
-    dto::PublishCommentDTO,
+    dto::{PublishCommentDTO, ReportTarget, RevisionDTO},
     get_db_pool,
     models::{comment::CommentModel, user::UserModel},
-    prelude::ToSQL,
-    utils::sql::SelectRequestBuilder,
+    prelude::{EditError, ToSQL},
+    repositories::{moderation::ModerationRepo, revisions::RevisionsRepo},
+    types::EditedState,
+    utils::{markdown, sql::SelectRequestBuilder},
+    validators::{
+        content_policy::{self, Verdict},
+        duplicates, premoderation,
+    },
 };
-use sqlx::{types::Uuid, PgPool};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::{types::Uuid, FromRow, PgConnection, PgPool, Row};
 
 #[derive(Clone)]
 pub struct CommentsRepo(PgPool);
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub enum PublishError {
     WrittenByNoone,
     WrittenUnderUnexistedPost,
     WrongLanguageCode,
+    Rejected { words: Vec<String> },
+    Duplicate,
+    ProblemsWithDB,
+}
+
+impl From<sqlx::Error> for PublishError {
+    fn from(_: sqlx::Error) -> Self {
+        PublishError::ProblemsWithDB
+    }
 }
 
 impl CommentsRepo {
@@ -29,51 +46,130 @@ impl CommentsRepo {
     ) -> Result<CommentModel, PublishError> {
         let uuid = Uuid::new_v4();
         let published_at = chrono::Utc::now();
-
+        if duplicates::is_repeated(&comment.author.username(), &comment.content).await {
+            return Err(PublishError::Duplicate);
+        }
+        let (screened, verdict) = content_policy::review(&[&comment.content]).await;
+        if let Verdict::Reject { words } = verdict {
+            return Err(PublishError::Rejected { words });
+        }
+        let content = &screened[0];
+        let mut transaction = self.0.begin().await?;
         sqlx::query(
             r#"
         insert into comments (
             uuid,
             written_under,
             content,
+            content_html,
             published_at,
-            written_by,
+            author,
+            hidden,
+            pending
         )
-        values ($1, $2, $3, $4, $5);
+        values ($1, $2, $3, $4, $5, $6, $7, $8);
         "#,
         )
         .bind(uuid)
         .bind(comment.for_post.uuid())
-        .bind(comment.content)
+        .bind(content)
+        .bind(markdown::render(content))
         .bind(published_at)
         .bind(comment.author.username())
-        .execute(&self.0)
-        .await
-        .unwrap();
+        .bind(matches!(verdict, Verdict::Moderate { .. }))
+        .bind(premoderation::required(&comment.author).await)
+        .execute(&mut *transaction)
+        .await?;
+        if let Verdict::Moderate { words } = verdict {
+            ModerationRepo::flag(
+                &mut transaction,
+                ReportTarget::Comment(uuid.to_string()),
+                &words,
+            )
+            .await?;
+        }
+        transaction.commit().await?;
         Ok(self.get_by_uuid(&uuid).await.unwrap())
     }
 
+    /// Same as `PostsRepo::edit_content`, the comment is only updated if
+    /// it's still at `expected_version` and passes the content policy. It
+    /// waits for approval again if its author is premoderated.
     pub async fn edit(
         &self,
         target: CommentModel,
         updated_content: String,
         author: &UserController,
-    ) -> Result<CommentModel, sqlx::Error> {
-        let edited_at = chrono::Utc::now();
+        expected_version: i32,
+    ) -> Result<CommentModel, EditError> {
+        let author = author.model().await;
+        let username = author.username();
+        let pending = premoderation::required(&author).await;
+        let edited_at = chrono::Utc::now().naive_utc();
+        let mut transaction = self.0.begin().await?;
+
+        let current = sqlx::query(
+            "select content, version, published_at, edited, edited_at from comments where uuid = $1 and author = $2 for update;",
+        )
+        .bind(target.uuid())
+        .bind(username.clone())
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(EditError::EditsNotAuthor)?;
+        let current_version: i32 = current.get("version");
+        if current_version != expected_version {
+            return Err(EditError::Stale { current_version });
+        }
+        let (screened, verdict) = content_policy::review(&[&updated_content]).await;
+        if let Verdict::Reject { words } = verdict {
+            return Err(EditError::Rejected { words });
+        }
+        let updated_content = screened[0].clone();
 
         sqlx::query(
             r#"
         update comments
-        set content = $1, edited = true, edited_at = $2
-        wher uuid = $3 and author = $4;
+        set content = $1, content_html = $2, edited = true, edited_at = $3, version = version + 1, hidden = hidden or $4, pending = pending or $5
+        where uuid = $6;
         "#,
         )
-        .bind(updated_content)
+        .bind(updated_content.clone())
+        .bind(markdown::render(&updated_content))
         .bind(edited_at)
+        .bind(matches!(verdict, Verdict::Moderate { .. }))
+        .bind(pending)
         .bind(target.uuid())
-        .bind(author.model().await.username())
-        .execute(&self.0)
+        .execute(&mut *transaction)
+        .await?;
+        RevisionsRepo::record(
+            &mut transaction,
+            RevisionDTO {
+                resource: target.uuid(),
+                title: None,
+                content: current.get("content"),
+                edited_by: username.clone(),
+                created_at: EditedState::from_row(&current)?
+                    .edited_at()
+                    .unwrap_or(current.get("published_at")),
+            },
+            RevisionDTO {
+                resource: target.uuid(),
+                title: None,
+                content: updated_content,
+                edited_by: username,
+                created_at: edited_at,
+            },
+        )
         .await?;
+        if let Verdict::Moderate { words } = verdict {
+            ModerationRepo::flag(
+                &mut transaction,
+                ReportTarget::Comment(target.uuid().to_string()),
+                &words,
+            )
+            .await?;
+        }
+        transaction.commit().await?;
 
         Ok(self.get_by_uuid(&target.uuid()).await.unwrap())
     }
@@ -95,9 +191,83 @@ impl CommentsRepo {
             .map_or(None, |comment| Some((*comment).clone()))
     }
 
+    /// Hidden comments and the ones waiting for approval are left out.
     pub async fn get_by_post(&self, post: &Uuid) -> Vec<CommentModel> {
-        self.get_many(vec![GetCommentQueryParam::Post(post.clone())])
-            .await
+        self.get_many(vec![
+            GetCommentQueryParam::Post(post.clone()),
+            GetCommentQueryParam::Hidden(false),
+            GetCommentQueryParam::Pending(false),
+        ])
+        .await
+    }
+
+    /// Comments waiting for approval, only the ones of `author` if it's given.
+    pub async fn get_pending(&self, author: Option<String>) -> Vec<CommentModel> {
+        let mut query = vec![GetCommentQueryParam::Pending(true)];
+        if let Some(author) = author {
+            query.push(GetCommentQueryParam::User(author));
+        }
+        self.get_many(query).await
+    }
+
+    /// Runs on the given connection, so premoderation can record the
+    /// decision in the same transaction.
+    pub async fn set_pending(
+        connection: &mut PgConnection,
+        comment: &Uuid,
+        pending: bool,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("update comments set pending = $1 where uuid = $2;")
+            .bind(pending)
+            .bind(comment)
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    /// Runs on the given connection, so moderation can record the action in
+    /// the same transaction.
+    pub async fn set_hidden(
+        connection: &mut PgConnection,
+        comment: &Uuid,
+        hidden: bool,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("update comments set hidden = $1 where uuid = $2;")
+            .bind(hidden)
+            .bind(comment)
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove(&self, comment: &CommentModel) -> Result<(), sqlx::Error> {
+        Self::erase(&mut *self.0.acquire().await?, &comment.uuid()).await
+    }
+
+    /// Hides the comment and erases its text, replies stay where they are.
+    pub async fn erase(connection: &mut PgConnection, comment: &Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "update comments set content = '', content_html = '', hidden = true where uuid = $1;",
+        )
+        .bind(comment)
+        .execute(connection)
+        .await?;
+        Ok(())
+    }
+
+    /// Contents of the author's comments published after `since`, the latest
+    /// first.
+    pub async fn get_recent_contents(&self, author: &str, since: NaiveDateTime) -> Vec<String> {
+        sqlx::query(
+            "select content from comments where author = $1 and published_at > $2 order by published_at desc limit 50;",
+        )
+        .bind(author)
+        .bind(since)
+        .fetch_all(&self.0)
+        .await
+        .map_or(Vec::new(), |rows| {
+            rows.iter().map(|row| row.get("content")).collect()
+        })
     }
 
     pub async fn get_by_author(&self, author: &UserModel) -> Vec<CommentModel> {
@@ -112,9 +282,13 @@ fn generte_select_sql(query: Vec<GetCommentQueryParam>) -> String {
                             comments.uuid,
                             comments.written_under,
                             comments.content,
+                            comments.content_html,
                             comments.published_at,
                             comments.edited,
                             comments.edited_at,
+                            comments.version,
+                            comments.hidden,
+                            comments.pending,
                             comments.author,
                             comments.replys_for,
                                         
@@ -128,9 +302,11 @@ fn generte_select_sql(query: Vec<GetCommentQueryParam>) -> String {
         "comments.uuid,
              comments.written_under,
              comments.content,
+             comments.content_html,
              comments.published_at,
              comments.edited,
              comments.edited_at,
+             comments.version,
              comments.author,
              comments.replys_for
                                         
@@ -146,6 +322,8 @@ pub enum GetCommentQueryParam {
     Post(Uuid),
     Replies(Uuid),
     User(String),
+    Hidden(bool),
+    Pending(bool),
 }
 
 impl ToSQL for GetCommentQueryParam {
@@ -163,6 +341,12 @@ impl ToSQL for GetCommentQueryParam {
             GetCommentQueryParam::User(username) => {
                 format!("author = '{}'", username)
             }
+            GetCommentQueryParam::Hidden(hidden) => {
+                format!("comments.hidden = {}", hidden)
+            }
+            GetCommentQueryParam::Pending(pending) => {
+                format!("comments.pending = {}", pending)
+            }
         }
     }
 }
@@ -0,0 +1,80 @@
+use crate::{
+    get_db_pool,
+    models::email_token::{EmailTokenModel, TokenPurpose},
+};
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+
+pub struct EmailTokensRepo(PgPool);
+
+impl EmailTokensRepo {
+    pub async fn get_instance() -> EmailTokensRepo {
+        EmailTokensRepo(get_db_pool().await)
+    }
+
+    /// Stores a new token, the user's earlier unused tokens of the same
+    /// purpose stop working.
+    pub async fn create(
+        &self,
+        token_hash: &str,
+        username: &str,
+        purpose: TokenPurpose,
+        email: &str,
+        expires_at: NaiveDateTime,
+    ) -> Result<(), sqlx::Error> {
+        let now = chrono::Utc::now().naive_utc();
+        let mut transaction = self.0.begin().await?;
+        sqlx::query(
+            "update email_tokens set used_at = $1 where username = $2 and purpose = $3 and used_at is null;",
+        )
+        .bind(now)
+        .bind(username)
+        .bind(purpose)
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query(
+            "insert into email_tokens (token_hash, username, purpose, email, created_at, expires_at) values ($1, $2, $3, $4, $5, $6);",
+        )
+        .bind(token_hash)
+        .bind(username)
+        .bind(purpose)
+        .bind(email)
+        .bind(now)
+        .bind(expires_at)
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await
+    }
+
+    /// The token if it can still be used.
+    pub async fn get(&self, token_hash: &str, purpose: TokenPurpose) -> Option<EmailTokenModel> {
+        sqlx::query_as::<_, EmailTokenModel>(
+            "select * from email_tokens where token_hash = $1 and purpose = $2 and used_at is null and expires_at > $3;",
+        )
+        .bind(token_hash)
+        .bind(purpose)
+        .bind(chrono::Utc::now().naive_utc())
+        .fetch_optional(&self.0)
+        .await
+        .ok()
+        .flatten()
+    }
+
+    /// Marks the token used and returns it, `None` if it's unknown, expired
+    /// or already used.
+    pub async fn consume(
+        &self,
+        token_hash: &str,
+        purpose: TokenPurpose,
+    ) -> Result<Option<EmailTokenModel>, sqlx::Error> {
+        let now = chrono::Utc::now().naive_utc();
+        sqlx::query_as::<_, EmailTokenModel>(
+            "update email_tokens set used_at = $1 where token_hash = $2 and purpose = $3 and used_at is null and expires_at > $1 returning *;",
+        )
+        .bind(now)
+        .bind(token_hash)
+        .bind(purpose)
+        .fetch_optional(&self.0)
+        .await
+    }
+}
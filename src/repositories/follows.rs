@@ -0,0 +1,78 @@
+use crate::{get_db_pool, models::follow::FollowModel, models::user::UserModel};
+use sqlx::{PgPool, Row};
+
+pub struct FollowsRepo(PgPool);
+
+impl FollowsRepo {
+    pub async fn get_instance() -> FollowsRepo {
+        FollowsRepo(get_db_pool().await)
+    }
+
+    pub async fn follow(
+        &self,
+        follower: &UserModel,
+        followed: &UserModel,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "insert into follows (follower, followed, followed_at) values ($1, $2, $3) on conflict do nothing;",
+        )
+        .bind(follower.username())
+        .bind(followed.username())
+        .bind(chrono::Utc::now().naive_utc())
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn unfollow(
+        &self,
+        follower: &UserModel,
+        followed: &UserModel,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("delete from follows where follower = $1 and followed = $2;")
+            .bind(follower.username())
+            .bind(followed.username())
+            .execute(&self.0)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_followers(&self, user: &UserModel) -> Vec<FollowModel> {
+        sqlx::query_as::<_, FollowModel>(
+            "select follower, followed, followed_at from follows where followed = $1 order by followed_at desc;",
+        )
+        .bind(user.username())
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+
+    pub async fn get_following(&self, user: &UserModel) -> Vec<FollowModel> {
+        sqlx::query_as::<_, FollowModel>(
+            "select follower, followed, followed_at from follows where follower = $1 order by followed_at desc;",
+        )
+        .bind(user.username())
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+
+    pub async fn count_followers(&self, user: &UserModel) -> i64 {
+        self.count("followed", user).await
+    }
+
+    pub async fn count_following(&self, user: &UserModel) -> i64 {
+        self.count("follower", user).await
+    }
+
+    async fn count(&self, column: &str, user: &UserModel) -> i64 {
+        sqlx::query(&format!(
+            "select count(*) as count from follows where {} = $1;",
+            column
+        ))
+        .bind(user.username())
+        .fetch_one(&self.0)
+        .await
+        .map_or(0, |row| row.get("count"))
+    }
+}
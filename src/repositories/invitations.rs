@@ -0,0 +1,121 @@
+use crate::{
+    get_db_pool,
+    models::{invitation::InvitationModel, user::UserType},
+    types::Limit,
+};
+use chrono::NaiveDateTime;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+const INVITATION_COLUMNS: &str = "uuid, created_by, user_specs::text as user_specs, class_num, class_char, subject::text as subject, job_title, max_uses, uses, expires_at, created_at, revoked";
+
+pub struct InvitationsRepo(PgPool);
+
+impl InvitationsRepo {
+    pub async fn get_instance() -> InvitationsRepo {
+        InvitationsRepo(get_db_pool().await)
+    }
+
+    pub async fn create(
+        &self,
+        code_hash: &str,
+        created_by: &str,
+        user_specs: Option<&UserType>,
+        max_uses: i32,
+        expires_at: NaiveDateTime,
+    ) -> Result<InvitationModel, sqlx::Error> {
+        let (kind, class_num, class_char, subject, job_title) = match user_specs {
+            Some(UserType::Teacher { subject }) => {
+                (Some("Teacher"), None, None, Some(subject.to_string()), None)
+            }
+            Some(UserType::Student { class }) => (
+                Some("Student"),
+                Some(i16::from(class.class_num())),
+                Some(class.class_char()),
+                None,
+                None,
+            ),
+            Some(UserType::Administrator { job_title }) => (
+                Some("Administrator"),
+                None,
+                None,
+                None,
+                Some(job_title.clone()),
+            ),
+            Some(UserType::Other) => (Some("Other"), None, None, None, None),
+            Some(UserType::Parent) => (Some("Parent"), None, None, None, None),
+            None => (None, None, None, None, None),
+        };
+        sqlx::query_as::<_, InvitationModel>(&format!(
+            "insert into invitations (uuid, code_hash, created_by, user_specs, class_num, class_char, subject, job_title, max_uses, expires_at, created_at) values ($1, $2, $3, cast($4 as user_specs), $5, $6, cast($7 as subject), $8, $9, $10, $11) returning {};",
+            INVITATION_COLUMNS
+        ))
+        .bind(Uuid::new_v4())
+        .bind(code_hash)
+        .bind(created_by)
+        .bind(kind)
+        .bind(class_num)
+        .bind(class_char)
+        .bind(subject)
+        .bind(job_title)
+        .bind(max_uses)
+        .bind(expires_at)
+        .bind(chrono::Utc::now().naive_utc())
+        .fetch_one(&self.0)
+        .await
+    }
+
+    /// The invitation if it can still be used.
+    pub async fn get_usable(&self, code_hash: &str) -> Option<InvitationModel> {
+        sqlx::query_as::<_, InvitationModel>(&format!(
+            "select {} from invitations where code_hash = $1 and not revoked and uses < max_uses and expires_at > $2;",
+            INVITATION_COLUMNS
+        ))
+        .bind(code_hash)
+        .bind(chrono::Utc::now().naive_utc())
+        .fetch_optional(&self.0)
+        .await
+        .ok()
+        .flatten()
+    }
+
+    /// Counts a use of the invitation and returns it, `None` if it can't be
+    /// used anymore. Runs on the given connection, so the use is only counted
+    /// along with the registration.
+    pub async fn claim(
+        connection: &mut PgConnection,
+        code_hash: &str,
+    ) -> Result<Option<InvitationModel>, sqlx::Error> {
+        sqlx::query_as::<_, InvitationModel>(&format!(
+            "update invitations set uses = uses + 1 where code_hash = $1 and not revoked and uses < max_uses and expires_at > $2 returning {};",
+            INVITATION_COLUMNS
+        ))
+        .bind(code_hash)
+        .bind(chrono::Utc::now().naive_utc())
+        .fetch_optional(connection)
+        .await
+    }
+
+    /// The latest first.
+    pub async fn get_all(&self, limit: Limit) -> Vec<InvitationModel> {
+        sqlx::query_as::<_, InvitationModel>(&format!(
+            "select {} from invitations order by created_at desc limit $1 offset $2;",
+            INVITATION_COLUMNS
+        ))
+        .bind(i64::from(limit.limit))
+        .bind(i64::from(limit.offset.unwrap_or(0)))
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+
+    pub async fn revoke(&self, uuid: &Uuid) -> Result<u64, sqlx::Error> {
+        Ok(
+            sqlx::query("update invitations set revoked = true where uuid = $1;")
+                .bind(uuid)
+                .execute(&self.0)
+                .await?
+                .rows_affected(),
+        )
+    }
+}
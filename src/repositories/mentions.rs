@@ -0,0 +1,58 @@
+use crate::{get_db_pool, models::mention::MentionSpan};
+use sqlx::{types::Uuid, PgPool, Row};
+
+pub struct MentionsRepo(PgPool);
+
+impl MentionsRepo {
+    pub async fn get_instance() -> MentionsRepo {
+        MentionsRepo(get_db_pool().await)
+    }
+
+    /// Replaces the mentions of `resource`, a post or a comment under `post`.
+    pub async fn replace(
+        &self,
+        resource: Uuid,
+        post: Uuid,
+        mentions: &[MentionSpan],
+    ) -> Result<(), sqlx::Error> {
+        let mut transaction = self.0.begin().await?;
+        sqlx::query("delete from mentions where resource = $1;")
+            .bind(resource)
+            .execute(&mut *transaction)
+            .await?;
+        for mention in mentions {
+            sqlx::query(
+                "insert into mentions (resource, post, mentioned, span_start, span_end) values ($1, $2, $3, $4, $5);",
+            )
+            .bind(resource)
+            .bind(post)
+            .bind(mention.username.clone())
+            .bind(mention.start)
+            .bind(mention.end)
+            .execute(&mut *transaction)
+            .await?;
+        }
+        transaction.commit().await
+    }
+
+    pub async fn get_spans(&self, resource: &Uuid) -> Vec<MentionSpan> {
+        sqlx::query_as::<_, MentionSpan>(
+            "select mentioned, span_start, span_end from mentions where resource = $1 order by span_start;",
+        )
+        .bind(resource)
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+
+    /// Posts whose own content, not their comments, mention the user.
+    pub async fn get_mentioning_posts(&self, username: &str) -> Vec<Uuid> {
+        sqlx::query("select distinct post from mentions where mentioned = $1 and resource = post;")
+            .bind(username)
+            .fetch_all(&self.0)
+            .await
+            .map_or(Vec::new(), |rows| {
+                rows.iter().map(|row| row.get("post")).collect()
+            })
+    }
+}
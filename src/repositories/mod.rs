@@ -1,11 +1,25 @@
 use self::{comments::CommentsRepo, posts::PostsRepo};
-use crate::{prelude::Resource, types::EditedState};
+use crate::{models::user::UserModel, prelude::Resource, types::EditedState};
 use sqlx::{postgres::PgRow, FromRow, Row};
 use uuid::Uuid;
 
+pub mod attachments;
+pub mod banned_words;
 pub mod comments;
+pub mod email_tokens;
+pub mod follows;
+pub mod invitations;
 pub(super) mod marks_repo;
+pub mod mentions;
+pub mod moderation;
+pub mod notifications;
+pub mod parent_links;
 pub mod posts;
+pub mod revisions;
+pub mod sign_in_attempts;
+pub mod subscriptions;
+pub mod suspensions;
+pub mod two_factor;
 pub mod users;
 
 impl FromRow<'_, PgRow> for EditedState {
@@ -20,11 +34,23 @@ impl FromRow<'_, PgRow> for EditedState {
     }
 }
 
-pub async fn find_resources(uuid: Uuid) -> Option<Box<dyn Resource>> {
+/// Finds a post or a comment `viewer` may interact with, so only the ones
+/// under a post in their audience.
+pub async fn find_resources(uuid: Uuid, viewer: &UserModel) -> Option<Box<dyn Resource>> {
+    let posts = PostsRepo::get_instance().await;
     if let Some(comment) = CommentsRepo::get_instance().await.get_by_uuid(&uuid).await {
+        if comment.is_hidden() || comment.is_pending() {
+            return None;
+        }
+        posts
+            .get_visible(comment.under_post().uuid(), Some(viewer.clone()))
+            .await?;
         return Some(Box::new(comment));
     }
-    if let Some(post) = PostsRepo::get_instance().await.get_by_uuid(uuid).await {
+    if let Some(post) = posts.get_visible(uuid, Some(viewer.clone())).await {
+        if !post.status().is_published() || post.is_hidden() || post.is_pending() {
+            return None;
+        }
         return Some(Box::new(post));
     }
     None
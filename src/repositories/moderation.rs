@@ -0,0 +1,305 @@
+use crate::{
+    dto::{ReportDTO, ReportTarget, SuspendJSON},
+    get_db_pool,
+    models::report::{
+        ModerationAction, ModerationActionModel, QueueItemModel, ReportModel, ReportReason,
+        ReportTargetKind,
+    },
+    repositories::{comments::CommentsRepo, posts::PostsRepo, suspensions::SuspensionsRepo},
+    types::Limit,
+};
+use sqlx::{types::Uuid, PgConnection, PgPool, Row};
+use std::str::FromStr;
+
+pub struct ModerationRepo(PgPool);
+
+impl ModerationRepo {
+    pub async fn get_instance() -> ModerationRepo {
+        ModerationRepo(get_db_pool().await)
+    }
+
+    /// `None` when the reporter already has an open report on the target.
+    pub async fn report(&self, report: ReportDTO) -> Result<Option<ReportModel>, sqlx::Error> {
+        insert_report(&mut *self.0.acquire().await?, report).await
+    }
+
+    /// Files a report on behalf of the content policy for an item it held
+    /// back, so that a moderator restores or removes it. Runs on the
+    /// connection that stores the item, so both are committed together.
+    pub async fn flag(
+        connection: &mut PgConnection,
+        target: ReportTarget,
+        words: &[String],
+    ) -> Result<(), sqlx::Error> {
+        insert_report(
+            connection,
+            ReportDTO {
+                reporter: None,
+                target,
+                reason: ReportReason::Inappropriate,
+                details: Some(format!("content policy: {}", words.join(", "))),
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Targets with open reports, the most reported first.
+    pub async fn get_queue(&self, limit: Limit) -> Vec<QueueItemModel> {
+        sqlx::query_as::<_, QueueItemModel>(
+            r#"
+            select
+                target_kind,
+                target,
+                count(*) as reports,
+                array_agg(distinct reason::text) as reasons,
+                min(created_at) as first_reported_at,
+                max(created_at) as last_reported_at
+            from reports
+            where resolved_at is null
+            group by target_kind, target
+            order by reports desc, first_reported_at
+            limit $1 offset $2;
+            "#,
+        )
+        .bind(i64::from(limit.limit))
+        .bind(i64::from(limit.offset.unwrap_or(0)))
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+
+    pub async fn get_open_reports(&self, target: &ReportTarget) -> Vec<ReportModel> {
+        sqlx::query_as::<_, ReportModel>(
+            "select * from reports where target_kind = $1 and target = $2 and resolved_at is null order by created_at;",
+        )
+        .bind(target.kind())
+        .bind(target.id())
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+
+    /// Records the action and closes the open reports of its target, along
+    /// with the `suspension` if one is given. Returns the action along with
+    /// the reporters to notify.
+    pub async fn resolve(
+        &self,
+        target: &ReportTarget,
+        action: ModerationAction,
+        moderator: &str,
+        note: Option<String>,
+        suspension: Option<&SuspendJSON>,
+    ) -> Result<(ModerationActionModel, Vec<String>), sqlx::Error> {
+        let mut transaction = self.0.begin().await?;
+        let resolved = record(
+            &mut transaction,
+            target,
+            action,
+            moderator,
+            note,
+            suspension,
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok(resolved)
+    }
+
+    /// Takes the target out of the approval queue. A rejected one is deleted
+    /// the way a moderator deletes reported content, so it counts as a
+    /// strike.
+    pub async fn settle_pending(
+        &self,
+        target: &ReportTarget,
+        approve: bool,
+        moderator: &str,
+        note: Option<String>,
+    ) -> Result<(), sqlx::Error> {
+        let Ok(uuid) = Uuid::from_str(&target.id()) else {
+            return Ok(());
+        };
+        let mut transaction = self.0.begin().await?;
+        match target {
+            ReportTarget::Post(_) => PostsRepo::set_pending(&mut transaction, &uuid, false).await?,
+            ReportTarget::Comment(_) => {
+                CommentsRepo::set_pending(&mut transaction, &uuid, false).await?
+            }
+            ReportTarget::Profile(_) => (),
+        }
+        if !approve {
+            record(
+                &mut transaction,
+                target,
+                ModerationAction::Delete,
+                moderator,
+                note,
+                None,
+            )
+            .await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_actions(
+        &self,
+        target_kind: Option<ReportTargetKind>,
+        limit: Limit,
+    ) -> Vec<ModerationActionModel> {
+        sqlx::query_as::<_, ModerationActionModel>(
+            "select * from moderation_actions where $1::report_target is null or target_kind = $1 order by created_at desc limit $2 offset $3;",
+        )
+        .bind(target_kind)
+        .bind(i64::from(limit.limit))
+        .bind(i64::from(limit.offset.unwrap_or(0)))
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+
+    /// Hidden, deleted or warned posts and comments of the user plus every
+    /// suspension they got.
+    pub async fn strikes(&self, username: &str) -> i64 {
+        sqlx::query(
+            r#"
+            select
+                (select count(*) from moderation_actions
+                    where action in ('Hide', 'Delete', 'Warn')
+                    and (
+                        (target_kind = 'Post' and target in (select uuid::text from posts where author = $1))
+                        or (target_kind = 'Comment' and target in (select uuid::text from comments where author = $1))
+                    ))
+                + (select count(*) from suspensions where username = $1) as strikes;
+            "#,
+        )
+        .bind(username)
+        .fetch_one(&self.0)
+        .await
+        .map_or(0, |row| row.get("strikes"))
+    }
+}
+
+/// Open reports are unique per reporter and target, and so are the ones the
+/// content policy files without a reporter.
+async fn insert_report(
+    connection: &mut PgConnection,
+    report: ReportDTO,
+) -> Result<Option<ReportModel>, sqlx::Error> {
+    sqlx::query_as::<_, ReportModel>(
+        r#"
+        insert into reports (
+            uuid,
+            reporter,
+            target_kind,
+            target,
+            reason,
+            details,
+            created_at
+        )
+        values ($1, $2, $3, $4, $5, $6, $7)
+        on conflict do nothing
+        returning *;
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(report.reporter)
+    .bind(report.target.kind())
+    .bind(report.target.id())
+    .bind(report.reason)
+    .bind(report.details)
+    .bind(chrono::Utc::now().naive_utc())
+    .fetch_optional(connection)
+    .await
+}
+
+async fn record(
+    connection: &mut PgConnection,
+    target: &ReportTarget,
+    action: ModerationAction,
+    moderator: &str,
+    note: Option<String>,
+    suspension: Option<&SuspendJSON>,
+) -> Result<(ModerationActionModel, Vec<String>), sqlx::Error> {
+    let resolved_at = chrono::Utc::now().naive_utc();
+    apply(&mut *connection, target, action).await?;
+    if let Some(suspension) = suspension {
+        SuspensionsRepo::insert(
+            &mut *connection,
+            &suspension.username,
+            &suspension.reason,
+            moderator,
+            suspension.until,
+        )
+        .await?;
+    }
+    let reporters = sqlx::query(
+        "update reports set resolved_at = $1, resolution = $2 where target_kind = $3 and target = $4 and resolved_at is null returning reporter;",
+    )
+    .bind(resolved_at)
+    .bind(action)
+    .bind(target.kind())
+    .bind(target.id())
+    .fetch_all(&mut *connection)
+    .await?
+    .iter()
+    .filter_map(|row| row.get("reporter"))
+    .collect();
+    let action = sqlx::query_as::<_, ModerationActionModel>(
+        r#"
+        insert into moderation_actions (
+            uuid,
+            moderator,
+            target_kind,
+            target,
+            action,
+            note,
+            created_at
+        )
+        values ($1, $2, $3, $4, $5, $6, $7)
+        returning *;
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(moderator)
+    .bind(target.kind())
+    .bind(target.id())
+    .bind(action)
+    .bind(note)
+    .bind(resolved_at)
+    .fetch_one(connection)
+    .await?;
+    Ok((action, reporters))
+}
+
+/// Hides, restores or erases the reported post or comment, the other actions
+/// and profiles leave the content as it is.
+async fn apply(
+    connection: &mut PgConnection,
+    target: &ReportTarget,
+    action: ModerationAction,
+) -> Result<(), sqlx::Error> {
+    let Ok(uuid) = Uuid::from_str(&target.id()) else {
+        return Ok(());
+    };
+    match (target, action) {
+        (ReportTarget::Post(_), ModerationAction::Hide) => {
+            PostsRepo::set_hidden(connection, &uuid, true).await
+        }
+        (ReportTarget::Post(_), ModerationAction::Restore) => {
+            PostsRepo::set_hidden(connection, &uuid, false).await
+        }
+        (ReportTarget::Post(_), ModerationAction::Delete) => {
+            PostsRepo::erase(connection, &uuid).await
+        }
+        (ReportTarget::Comment(_), ModerationAction::Hide) => {
+            CommentsRepo::set_hidden(connection, &uuid, true).await
+        }
+        (ReportTarget::Comment(_), ModerationAction::Restore) => {
+            CommentsRepo::set_hidden(connection, &uuid, false).await
+        }
+        (ReportTarget::Comment(_), ModerationAction::Delete) => {
+            CommentsRepo::erase(connection, &uuid).await
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{
+    dto::NotificationDTO,
+    get_db_pool,
+    models::notification::{NotificationKind, NotificationModel, NotificationPreference},
+    types::Limit,
+};
+use sqlx::{types::Uuid, PgPool, Row};
+
+pub struct NotificationsRepo(PgPool);
+
+impl NotificationsRepo {
+    pub async fn get_instance() -> NotificationsRepo {
+        NotificationsRepo(get_db_pool().await)
+    }
+
+    pub async fn create(
+        &self,
+        notification: NotificationDTO,
+    ) -> Result<NotificationModel, sqlx::Error> {
+        sqlx::query_as::<_, NotificationModel>(
+            r#"
+            insert into notifications (
+                uuid,
+                recipient,
+                kind,
+                actor,
+                resource,
+                details,
+                created_at
+            )
+            values ($1, $2, $3, $4, $5, $6, $7)
+            returning *;
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(notification.recipient)
+        .bind(notification.kind)
+        .bind(notification.actor)
+        .bind(notification.resource)
+        .bind(notification.details)
+        .bind(chrono::Utc::now().naive_utc())
+        .fetch_one(&self.0)
+        .await
+    }
+
+    pub async fn get_for(
+        &self,
+        recipient: &str,
+        unread_only: bool,
+        limit: Limit,
+    ) -> Vec<NotificationModel> {
+        sqlx::query_as::<_, NotificationModel>(&format!(
+            "select * from notifications where recipient = $1{} order by created_at desc limit $2 offset $3;",
+            if unread_only { " and read_at is null" } else { "" }
+        ))
+        .bind(recipient)
+        .bind(i64::from(limit.limit))
+        .bind(i64::from(limit.offset.unwrap_or(0)))
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+
+    pub async fn count_unread(&self, recipient: &str) -> i64 {
+        sqlx::query(
+            "select count(*) as count from notifications where recipient = $1 and read_at is null;",
+        )
+        .bind(recipient)
+        .fetch_one(&self.0)
+        .await
+        .map_or(0, |row| row.get("count"))
+    }
+
+    /// Marks one notification, or every notification of the recipient when
+    /// `uuid` is `None`, as read. Returns how many were updated.
+    pub async fn mark_read(&self, recipient: &str, uuid: Option<Uuid>) -> Result<u64, sqlx::Error> {
+        Ok(sqlx::query(
+            "update notifications set read_at = $1 where recipient = $2 and read_at is null and ($3::uuid is null or uuid = $3);",
+        )
+        .bind(chrono::Utc::now().naive_utc())
+        .bind(recipient)
+        .bind(uuid)
+        .execute(&self.0)
+        .await?
+        .rows_affected())
+    }
+
+    pub async fn get_preferences(&self, username: &str) -> Vec<NotificationPreference> {
+        sqlx::query_as::<_, NotificationPreference>(
+            "select kind, enabled from notification_preferences where username = $1;",
+        )
+        .bind(username)
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+
+    pub async fn set_preference(
+        &self,
+        username: &str,
+        preference: &NotificationPreference,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "insert into notification_preferences (username, kind, enabled) values ($1, $2, $3) on conflict (username, kind) do update set enabled = $3;",
+        )
+        .bind(username)
+        .bind(preference.kind)
+        .bind(preference.enabled)
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    /// Every kind is enabled until the user turns it off.
+    pub async fn is_enabled(&self, username: &str, kind: NotificationKind) -> bool {
+        sqlx::query(
+            "select enabled from notification_preferences where username = $1 and kind = $2;",
+        )
+        .bind(username)
+        .bind(kind)
+        .fetch_optional(&self.0)
+        .await
+        .ok()
+        .flatten()
+        .is_none_or(|row| row.get("enabled"))
+    }
+}
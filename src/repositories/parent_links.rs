@@ -0,0 +1,122 @@
+use crate::{get_db_pool, models::parent_link::ParentLinkModel, types::Limit};
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+
+pub struct ParentLinksRepo(PgPool);
+
+impl ParentLinksRepo {
+    pub async fn get_instance() -> ParentLinksRepo {
+        ParentLinksRepo(get_db_pool().await)
+    }
+
+    /// `false` if the parent already asked to be linked to the student.
+    pub async fn request(&self, parent: &str, student: &str) -> Result<bool, sqlx::Error> {
+        Ok(sqlx::query(
+            "insert into parent_links (parent, student, requested_at) values ($1, $2, $3) on conflict do nothing;",
+        )
+        .bind(parent)
+        .bind(student)
+        .bind(chrono::Utc::now().naive_utc())
+        .execute(&self.0)
+        .await?
+        .rows_affected()
+            > 0)
+    }
+
+    /// `false` if there is no unconfirmed link between the two.
+    pub async fn confirm(
+        &self,
+        parent: &str,
+        student: &str,
+        confirmed_by: &str,
+    ) -> Result<bool, sqlx::Error> {
+        Ok(sqlx::query(
+            "update parent_links set confirmed_by = $1, confirmed_at = $2 where parent = $3 and student = $4 and confirmed_at is null;",
+        )
+        .bind(confirmed_by)
+        .bind(chrono::Utc::now().naive_utc())
+        .bind(parent)
+        .bind(student)
+        .execute(&self.0)
+        .await?
+        .rows_affected()
+            > 0)
+    }
+
+    pub async fn remove(&self, parent: &str, student: &str) -> Result<u64, sqlx::Error> {
+        Ok(
+            sqlx::query("delete from parent_links where parent = $1 and student = $2;")
+                .bind(parent)
+                .bind(student)
+                .execute(&self.0)
+                .await?
+                .rows_affected(),
+        )
+    }
+
+    pub async fn get_children(&self, parent: &str) -> Vec<ParentLinkModel> {
+        sqlx::query_as::<_, ParentLinkModel>(
+            "select * from parent_links where parent = $1 order by requested_at;",
+        )
+        .bind(parent)
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+
+    pub async fn get_parents(&self, student: &str) -> Vec<ParentLinkModel> {
+        sqlx::query_as::<_, ParentLinkModel>(
+            "select * from parent_links where student = $1 order by requested_at;",
+        )
+        .bind(student)
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+
+    /// Links waiting for confirmation, the oldest first.
+    pub async fn get_unconfirmed(&self, limit: Limit) -> Vec<ParentLinkModel> {
+        sqlx::query_as::<_, ParentLinkModel>(
+            "select * from parent_links where confirmed_at is null order by requested_at limit $1 offset $2;",
+        )
+        .bind(i64::from(limit.limit))
+        .bind(i64::from(limit.offset.unwrap_or(0)))
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+
+    /// Parents with at least one confirmed link.
+    pub async fn get_linked_parents(&self) -> Vec<String> {
+        sqlx::query_scalar(
+            "select distinct parent from parent_links where confirmed_at is not null;",
+        )
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+
+    pub async fn last_digest(&self, parent: &str) -> Option<NaiveDateTime> {
+        sqlx::query_scalar("select sent_at from parent_digests where parent = $1;")
+            .bind(parent)
+            .fetch_optional(&self.0)
+            .await
+            .ok()
+            .flatten()
+    }
+
+    pub async fn digest_sent(
+        &self,
+        parent: &str,
+        sent_at: NaiveDateTime,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "insert into parent_digests (parent, sent_at) values ($1, $2) on conflict (parent) do update set sent_at = $2;",
+        )
+        .bind(parent)
+        .bind(sent_at)
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+}
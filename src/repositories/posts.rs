@@ -1,14 +1,29 @@
 use crate::{
     controllers::{users::UserController, Controller},
-    dto::PublishPostDTO,
+    dto::{PublishPostDTO, ReportTarget, RevisionDTO},
     get_db_pool,
-    models::post::PostModel,
-    prelude::{SortingDirection, ToSQL},
-    types::Limit,
-    utils::sql::SelectRequestBuilder,
+    models::{
+        post::PostModel,
+        user::{UserModel, UserType},
+    },
+    prelude::{EditError, SortingDirection, ToSQL},
+    repositories::{moderation::ModerationRepo, revisions::RevisionsRepo},
+    services::two_factor,
+    types::{Audience, Class, EditedState, FeedCursor, FeedRanking, Limit, PostStatus, Subject},
+    utils::{markdown, sql::SelectRequestBuilder},
+    validators::{
+        content_policy::{self, Verdict},
+        premoderation,
+    },
 };
-use serde::Deserialize;
-use sqlx::{postgres::PgPool, types::Uuid};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    postgres::{PgConnection, PgPool, PgRow},
+    types::Uuid,
+    FromRow, Row,
+};
+use std::str::FromStr;
 
 #[derive(Deserialize)]
 enum SortBy {
@@ -19,58 +34,669 @@ enum SortBy {
 
 pub struct PostsRepo(PgPool);
 
+const POST_COLUMNS: &str = "posts.uuid,
+    posts.title,
+    posts.content,
+    posts.content_html,
+    posts.published_at,
+    posts.author,
+    posts.edited,
+    posts.edited_at,
+    posts.tags,
+    posts.status,
+    posts.publish_at,
+    posts.expires_at,
+    posts.pinned,
+    posts.version,
+    posts.hidden,
+    posts.pending,
+    div(count(post_mark.liked = true), count(post_mark.liked = true) + count(post_mark.liked = false) + 1) as raiting,
+    count(post_mark.liked = true) as likes,
+    count(post_mark.liked = false) as dislikes";
+
+const POST_GROUP_BY: &str = "posts.uuid,
+    posts.content,
+    posts.content_html,
+    posts.title,
+    posts.published_at,
+    posts.author,
+    posts.edited,
+    posts.edited_at,
+    posts.tags,
+    posts.status,
+    posts.publish_at,
+    posts.expires_at,
+    posts.pinned,
+    posts.version,
+    posts.hidden,
+    posts.pending";
+
+#[derive(Debug, Serialize)]
+pub enum DraftError {
+    DraftDoesntExist,
+    EditsNotAuthor,
+    /// Published or archived.
+    NotADraft,
+    Rejected {
+        words: Vec<String>,
+    },
+    ProblemsWithDB,
+}
+
+impl From<sqlx::Error> for DraftError {
+    fn from(_: sqlx::Error) -> Self {
+        DraftError::ProblemsWithDB
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub enum PublishError {
+    Rejected { words: Vec<String> },
+    InvalidAudience,
+    ProblemsWithDB,
+}
+
+impl From<sqlx::Error> for PublishError {
+    fn from(_: sqlx::Error) -> Self {
+        PublishError::ProblemsWithDB
+    }
+}
+
 impl PostsRepo {
     pub async fn get_instance() -> PostsRepo {
         PostsRepo(get_db_pool().await)
     }
 
-    pub async fn publish(&self, post: PublishPostDTO) -> Result<PostModel, sqlx::Error> {
+    /// Drafts aren't screened by the content policy nor held for
+    /// premoderation until they're published.
+    pub async fn publish(&self, post: PublishPostDTO) -> Result<PostModel, PublishError> {
+        if !post.audience.iter().all(Audience::is_valid) {
+            return Err(PublishError::InvalidAudience);
+        }
         let published_at = chrono::Utc::now().naive_utc();
         let uuid = Uuid::new_v4();
+        let publish_at = match post.status {
+            PostStatus::Scheduled { publish_at } => Some(publish_at),
+            _ => None,
+        };
+        let (screened, verdict, pending) = match post.status {
+            PostStatus::Draft => (
+                vec![post.title.clone(), post.content.clone()],
+                Verdict::Allow,
+                false,
+            ),
+            _ => {
+                let (screened, verdict) =
+                    content_policy::review(&[&post.title, &post.content]).await;
+                (
+                    screened,
+                    verdict,
+                    premoderation::required(&post.author).await,
+                )
+            }
+        };
+        if let Verdict::Reject { words } = verdict {
+            return Err(PublishError::Rejected { words });
+        }
+        let (title, content) = (&screened[0], &screened[1]);
+        let mut transaction = self.0.begin().await?;
         sqlx::query(
             r#"
             INSERT INTO posts (
-                id,
+                uuid,
                 title,
                 content,
+                content_html,
                 published_at,
-                author
+                author,
+                status,
+                publish_at,
+                expires_at,
+                hidden,
+                pending
             )
-            VALUES ($1, $2, $3, $4, $5)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
             "#,
         )
         .bind(uuid)
-        .bind(post.title.clone())
-        .bind(post.content.clone())
+        .bind(title)
+        .bind(content)
+        .bind(markdown::render(content))
         .bind(published_at)
         .bind(post.author.username().clone())
-        .execute(&self.0)
+        .bind(PostStatusKind::from(&post.status))
+        .bind(publish_at)
+        .bind(post.expires_at)
+        .bind(matches!(verdict, Verdict::Moderate { .. }))
+        .bind(pending)
+        .execute(&mut *transaction)
         .await?;
+        for audience in post.audience.iter() {
+            add_audience(&mut transaction, uuid, audience).await?;
+        }
+        if let Verdict::Moderate { words } = verdict {
+            ModerationRepo::flag(
+                &mut transaction,
+                ReportTarget::Post(uuid.to_string()),
+                &words,
+            )
+            .await?;
+        }
+        transaction.commit().await?;
 
         Ok(self.get_by_uuid(uuid).await.unwrap())
     }
 
-    pub async fn edit_content(&self, post: PostModel, content: &str, author: &UserController) {
+    pub async fn get_audience(&self, post: &Uuid) -> Vec<Audience> {
+        sqlx::query_as::<_, Audience>(
+            "select kind, class_num, class_char, subject::text as subject from post_audience where post = $1;",
+        )
+        .bind(post)
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+
+    /// Returns the post only if `viewer` belongs to its audience. Drafts are
+    /// never returned here, they are reachable through the drafts endpoints.
+    pub async fn get_visible(&self, uuid: Uuid, viewer: Option<UserModel>) -> Option<PostModel> {
+        self.get_many(
+            vec![
+                GetQueryParam::Uuid(uuid),
+                GetQueryParam::Status(vec![PostStatusKind::Published, PostStatusKind::Archived]),
+                GetQueryParam::VisibleFor(viewer),
+            ],
+            Limit {
+                limit: 1,
+                offset: None,
+            },
+            SortingDirection::Up(SortingParam::ReleaseTime),
+        )
+        .await
+        .first()
+        .cloned()
+    }
+
+    pub async fn edit_content(
+        &self,
+        post: PostModel,
+        content: &str,
+        author: &UserController,
+        expected_version: i32,
+    ) -> Result<(), EditError> {
+        self.edit(
+            post,
+            None,
+            Some(content.to_string()),
+            author,
+            expected_version,
+        )
+        .await
+    }
+
+    pub async fn edit_title(
+        &self,
+        post: PostModel,
+        title: &str,
+        author: &UserController,
+        expected_version: i32,
+    ) -> Result<(), EditError> {
+        self.edit(
+            post,
+            Some(title.to_string()),
+            None,
+            author,
+            expected_version,
+        )
+        .await
+    }
+
+    /// Updates the post if it's still at `expected_version` and stores the
+    /// result as its next revision. The row is locked until then, so of two
+    /// concurrent edits of the same version only the first one applies. The
+    /// edited post goes through the content policy and premoderation like a
+    /// new one.
+    async fn edit(
+        &self,
+        post: PostModel,
+        title: Option<String>,
+        content: Option<String>,
+        author: &UserController,
+        expected_version: i32,
+    ) -> Result<(), EditError> {
+        let author = author.model().await;
+        let username = author.username();
+        let pending = premoderation::required(&author).await;
+        let edited_at = chrono::Utc::now().naive_utc();
+        let mut transaction = self.0.begin().await?;
+        let current = sqlx::query(
+            "select title, content, version, published_at, edited, edited_at from posts where uuid = $1 and author = $2 for update;",
+        )
+        .bind(post.uuid())
+        .bind(username.clone())
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(EditError::EditsNotAuthor)?;
+        let current_version: i32 = current.get("version");
+        if current_version != expected_version {
+            return Err(EditError::Stale { current_version });
+        }
+        let previous_title: String = current.get("title");
+        let previous_content: String = current.get("content");
+        let title = title.unwrap_or(previous_title.clone());
+        let content = content.unwrap_or(previous_content.clone());
+        let (screened, verdict) = content_policy::review(&[&title, &content]).await;
+        if let Verdict::Reject { words } = verdict {
+            return Err(EditError::Rejected { words });
+        }
+        let (title, content) = (screened[0].clone(), screened[1].clone());
+        sqlx::query(
+            "update posts set title = $1, content = $2, content_html = $3, edited = true, edited_at = $4, version = version + 1, hidden = hidden or $5, pending = pending or $6 where uuid = $7;",
+        )
+        .bind(title.clone())
+        .bind(content.clone())
+        .bind(markdown::render(&content))
+        .bind(edited_at)
+        .bind(matches!(verdict, Verdict::Moderate { .. }))
+        .bind(pending)
+        .bind(post.uuid())
+        .execute(&mut *transaction)
+        .await?;
+        RevisionsRepo::record(
+            &mut transaction,
+            RevisionDTO {
+                resource: post.uuid(),
+                title: Some(previous_title),
+                content: previous_content,
+                edited_by: username.clone(),
+                created_at: EditedState::from_row(&current)?
+                    .edited_at()
+                    .unwrap_or(current.get("published_at")),
+            },
+            RevisionDTO {
+                resource: post.uuid(),
+                title: Some(title),
+                content,
+                edited_by: username,
+                created_at: edited_at,
+            },
+        )
+        .await?;
+        if let Verdict::Moderate { words } = verdict {
+            ModerationRepo::flag(
+                &mut transaction,
+                ReportTarget::Post(post.uuid().to_string()),
+                &words,
+            )
+            .await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// A scheduled post was screened when it got scheduled, so its edits are
+    /// screened the same way before they can go out.
+    pub async fn edit_draft(
+        &self,
+        draft: PostModel,
+        title: Option<String>,
+        content: Option<String>,
+        author: &UserController,
+    ) -> Result<PostModel, DraftError> {
+        if !draft.status().is_draft() {
+            return Err(DraftError::NotADraft);
+        }
+        let author = author.model().await;
+        let title = title.unwrap_or(draft.title());
+        let content = content.unwrap_or(draft.content());
+        let (parts, verdict, pending) = match draft.status() {
+            PostStatus::Scheduled { .. } => {
+                let (screened, verdict) = content_policy::review(&[&title, &content]).await;
+                if let Verdict::Reject { words } = verdict {
+                    return Err(DraftError::Rejected { words });
+                }
+                (screened, verdict, premoderation::required(&author).await)
+            }
+            _ => (vec![title, content], Verdict::Allow, false),
+        };
+        let (title, content) = (&parts[0], &parts[1]);
+        let mut transaction = self.0.begin().await?;
         sqlx::query(
-            "update posts set content = $1, edited = true, edited_at = now()  where uuid = $2 and author = $3;",
+            "update posts set title = $1, content = $2, content_html = $3, hidden = hidden or $4, pending = pending or $5 where uuid = $6 and author = $7;",
         )
+        .bind(title)
         .bind(content)
-        .bind(post.uuid())
-            .bind(author.model().await.username())
+        .bind(markdown::render(content))
+        .bind(matches!(verdict, Verdict::Moderate { .. }))
+        .bind(pending)
+        .bind(draft.uuid())
+        .bind(author.username())
+        .execute(&mut *transaction)
+        .await?;
+        if let Verdict::Moderate { words } = verdict {
+            ModerationRepo::flag(
+                &mut transaction,
+                ReportTarget::Post(draft.uuid().to_string()),
+                &words,
+            )
+            .await?;
+        }
+        transaction.commit().await?;
+        self.get_by_uuid(draft.uuid())
+            .await
+            .ok_or(DraftError::DraftDoesntExist)
+    }
+
+    pub async fn publish_draft(
+        &self,
+        draft: PostModel,
+        publish_at: Option<NaiveDateTime>,
+        author: &UserController,
+    ) -> Result<PostModel, DraftError> {
+        if !draft.status().is_draft() {
+            return Err(DraftError::NotADraft);
+        }
+        let (screened, verdict) = content_policy::review(&[&draft.title(), &draft.content()]).await;
+        if let Verdict::Reject { words } = verdict {
+            return Err(DraftError::Rejected { words });
+        }
+        let (title, content) = (&screened[0], &screened[1]);
+        let author = author.model().await;
+        let status = match publish_at {
+            Some(publish_at) => PostStatus::Scheduled { publish_at },
+            None => PostStatus::Published,
+        };
+        let mut transaction = self.0.begin().await?;
+        sqlx::query(
+            "update posts set status = $1, publish_at = $2, published_at = $3, title = $4, content = $5, content_html = $6, hidden = hidden or $7, pending = $8 where uuid = $9 and author = $10;",
+        )
+        .bind(PostStatusKind::from(&status))
+        .bind(publish_at)
+        .bind(chrono::Utc::now().naive_utc())
+        .bind(title)
+        .bind(content)
+        .bind(markdown::render(content))
+        .bind(matches!(verdict, Verdict::Moderate { .. }))
+        .bind(premoderation::required(&author).await)
+        .bind(draft.uuid())
+        .bind(author.username())
+        .execute(&mut *transaction)
+        .await?;
+        if let Verdict::Moderate { words } = verdict {
+            ModerationRepo::flag(
+                &mut transaction,
+                ReportTarget::Post(draft.uuid().to_string()),
+                &words,
+            )
+            .await?;
+        }
+        transaction.commit().await?;
+        self.get_by_uuid(draft.uuid())
+            .await
+            .ok_or(DraftError::DraftDoesntExist)
+    }
+
+    pub async fn get_drafts(&self, author: &UserController) -> Vec<PostModel> {
+        self.get_many(
+            vec![
+                GetQueryParam::Author(author.model().await.username()),
+                GetQueryParam::Status(vec![PostStatusKind::Draft, PostStatusKind::Scheduled]),
+            ],
+            Limit {
+                limit: 100,
+                offset: None,
+            },
+            SortingDirection::Up(SortingParam::ReleaseTime),
+        )
+        .await
+    }
+
+    /// Flips every scheduled post whose `publish_at` has passed to published
+    /// and returns the released posts, except the ones held for moderation or
+    /// waiting for approval.
+    pub async fn release_scheduled(&self) -> Result<Vec<Uuid>, sqlx::Error> {
+        let now = chrono::Utc::now().naive_utc();
+        Ok(sqlx::query(
+            "update posts set status = 'Published', published_at = publish_at, publish_at = NULL where status = 'Scheduled' and publish_at <= $1 returning uuid, hidden or pending as withheld;",
+        )
+        .bind(now)
+        .fetch_all(&self.0)
+        .await?
+        .iter()
+        .filter(|row| !row.get::<bool, &str>("withheld"))
+        .map(|row| row.get("uuid"))
+        .collect())
+    }
+
+    /// Moves every published post whose `expires_at` has passed to the archive
+    /// and returns how many posts were archived.
+    pub async fn archive_expired(&self) -> Result<u64, sqlx::Error> {
+        let now = chrono::Utc::now().naive_utc();
+        Ok(sqlx::query(
+            "update posts set status = 'Archived' where status = 'Published' and expires_at <= $1;",
+        )
+        .bind(now)
         .execute(&self.0)
+        .await?
+        .rows_affected())
+    }
+
+    pub async fn set_pinned(&self, post: PostModel, pinned: bool) -> Result<(), sqlx::Error> {
+        sqlx::query("update posts set pinned = $1 where uuid = $2;")
+            .bind(pinned)
+            .bind(post.uuid())
+            .execute(&self.0)
+            .await?;
+        Ok(())
+    }
+
+    /// Runs on the given connection, so premoderation can record the
+    /// decision in the same transaction.
+    pub async fn set_pending(
+        connection: &mut PgConnection,
+        post: &Uuid,
+        pending: bool,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("update posts set pending = $1 where uuid = $2;")
+            .bind(pending)
+            .bind(post)
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    /// Whether the post targets `viewer`, hidden and pending posts included.
+    pub async fn is_in_audience(&self, post: Uuid, viewer: &UserModel) -> bool {
+        !self
+            .get_many(
+                vec![
+                    GetQueryParam::Uuid(post),
+                    GetQueryParam::InAudienceOf(viewer.clone()),
+                ],
+                Limit {
+                    limit: 1,
+                    offset: None,
+                },
+                SortingDirection::Up(SortingParam::ReleaseTime),
+            )
+            .await
+            .is_empty()
+    }
+
+    /// Pending posts in the audience of `moderator`, only the ones of
+    /// `author` if it's given.
+    pub async fn get_pending(
+        &self,
+        moderator: &UserModel,
+        author: Option<String>,
+        limit: Limit,
+    ) -> Vec<PostModel> {
+        let mut query = vec![
+            GetQueryParam::Pending(true),
+            GetQueryParam::InAudienceOf(moderator.clone()),
+        ];
+        if let Some(author) = author {
+            query.push(GetQueryParam::Author(author));
+        }
+        self.get_many(
+            query,
+            limit,
+            SortingDirection::Up(SortingParam::ReleaseTime),
+        )
         .await
-        .unwrap();
     }
-    pub async fn edit_title(&self, post: PostModel, title: &str, author: &UserController) {
+
+    /// Runs on the given connection, so moderation can record the action in
+    /// the same transaction.
+    pub async fn set_hidden(
+        connection: &mut PgConnection,
+        post: &Uuid,
+        hidden: bool,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("update posts set hidden = $1 where uuid = $2;")
+            .bind(hidden)
+            .bind(post)
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove(&self, post: &PostModel) -> Result<(), sqlx::Error> {
+        Self::erase(&mut *self.0.acquire().await?, &post.uuid()).await
+    }
+
+    /// Hides the post and erases its text, the row stays for the comments
+    /// and marks referencing it.
+    pub async fn erase(connection: &mut PgConnection, post: &Uuid) -> Result<(), sqlx::Error> {
         sqlx::query(
-            "update posts set title = $1, edited = true, edited_at = now() where uuid = $2 and author = $3;",
+            "update posts set title = '', content = '', content_html = '', hidden = true where uuid = $1;",
         )
-        .bind(title)
+        .bind(post)
+        .execute(connection)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn mark_seen(&self, post: &PostModel, viewer: &UserModel) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "insert into post_views (username, post, seen_at) values ($1, $2, $3) on conflict do nothing;",
+        )
+        .bind(viewer.username())
         .bind(post.uuid())
-            .bind(author.model().await.username())
+        .bind(chrono::Utc::now().naive_utc())
         .execute(&self.0)
-        .await
-        .unwrap();
+        .await?;
+        Ok(())
+    }
+
+    /// Ranks the posts from followed authors, subscribed tags, the viewer's
+    /// audience and pinned posts. Every post is returned with the cursor that
+    /// continues the feed right after it.
+    pub async fn get_feed(
+        &self,
+        viewer: &UserModel,
+        ranking: &FeedRanking,
+        cursor: Option<FeedCursor>,
+        limit: u32,
+    ) -> Vec<(PostModel, FeedCursor)> {
+        let generated_at = cursor
+            .as_ref()
+            .map_or(chrono::Utc::now().naive_utc(), |cursor| cursor.generated_at);
+        let conditions = [
+            GetQueryParam::Status(vec![PostStatusKind::Published]),
+            GetQueryParam::VisibleFor(Some(viewer.clone())),
+            GetQueryParam::InFeedOf(viewer.clone()),
+        ]
+        .iter()
+        .map(|param| param.to_sql())
+        .collect::<Vec<String>>()
+        .join(" and ");
+        // `seen` is taken as of `generated_at` too, posts marked seen while
+        // paging don't move between pages.
+        let sql = format!(
+            "select * from (
+                select *, cast(
+                    $2 / (1 + extract(epoch from ($1 - published_at)) / 3600)
+                    + $3 * raiting
+                    + case when pinned then $4 else 0 end
+                    - case when seen then $5 else 0 end
+                as float8) as score
+                from (
+                    select {},
+                        exists (select 1 from post_views where post_views.post = posts.uuid and post_views.username = $6 and post_views.seen_at <= $1) as seen
+                    from posts left join post_mark on posts.uuid = post_mark.post
+                    where {} and posts.published_at <= $1
+                    group by {}
+                ) as feed
+            ) as ranked
+            {}
+            order by score desc, uuid desc
+            limit {};",
+            POST_COLUMNS,
+            conditions,
+            POST_GROUP_BY,
+            match cursor {
+                Some(_) => "where (score, uuid) < ($7, $8)",
+                None => "",
+            },
+            limit
+        );
+        let mut query = sqlx::query(&sql)
+            .bind(generated_at)
+            .bind(ranking.recency)
+            .bind(ranking.raiting)
+            .bind(ranking.pinned)
+            .bind(ranking.seen)
+            .bind(viewer.username());
+        if let Some(cursor) = cursor {
+            query = query.bind(cursor.score).bind(cursor.uuid);
+        }
+        let rows = query.fetch_all(&self.0).await.unwrap_or_default();
+        let mut page = Vec::new();
+        for row in rows {
+            let cursor = FeedCursor {
+                generated_at,
+                score: row.get("score"),
+                uuid: row.get("uuid"),
+            };
+            page.push((PostModel::from_row(&row).await, cursor));
+        }
+        page
+    }
+
+    /// Published posts targeted at the classes of the parent's children,
+    /// newest first.
+    pub async fn get_for_children(
+        &self,
+        parent: &UserModel,
+        since: NaiveDateTime,
+        limit: u32,
+    ) -> Vec<PostModel> {
+        let sql = format!(
+            "select {} from posts left join post_mark on posts.uuid = post_mark.post
+            where {} and {} and posts.published_at > $2
+            and exists (select 1 from post_audience where post_audience.post = posts.uuid and {})
+            group by {}
+            order by posts.published_at desc
+            limit $3;",
+            POST_COLUMNS,
+            GetQueryParam::Status(vec![PostStatusKind::Published]).to_sql(),
+            GetQueryParam::VisibleFor(Some(parent.clone())).to_sql(),
+            children_condition("$1"),
+            POST_GROUP_BY
+        );
+        let rows = sqlx::query(&sql)
+            .bind(parent.username())
+            .bind(since)
+            .bind(limit as i64)
+            .fetch_all(&self.0)
+            .await
+            .unwrap_or_default();
+        let mut models = Vec::new();
+        for row in rows {
+            models.push(PostModel::from_row(&row).await);
+        }
+        models
     }
 
     pub async fn get_by_uuid(&self, uuid: Uuid) -> Option<PostModel> {
@@ -91,37 +717,16 @@ impl PostsRepo {
         order_by: SortingDirection<SortingParam>,
     ) -> Vec<PostModel> {
         let sql = SelectRequestBuilder::new(
-            "select posts.uuid,
-                posts.title,
-                posts.content,
-                posts.published_at,
-                posts.author, 
-                posts.edited ,
-                posts.edited_at,
-                posts.tags,
-                div(count(post_mark.liked = true), count(post_mark.liked = true) + count(post_mark.liked = false) + 1) as raiting,
-                count(post_mark.liked = true) as likes,                  
-                count(post_mark.liked = false) as dislikes
-                from posts join post_mark on posts.uuid = post_mark.post"
-                .to_string(),
+            format!(
+                "select {} from posts left join post_mark on posts.uuid = post_mark.post",
+                POST_COLUMNS
+            ),
             query,
         )
         .limit(limit)
         .order_by(order_by)
-        .group_by(
-            "
-                posts.uuid,
-                posts.content,
-                posts.title,
-                posts.published_at,
-                posts.author,
-                posts.edited,
-                posts.edited_at,
-                posts.tags
-            
-            "
-            .to_string(),
-        ).build();
+        .group_by(POST_GROUP_BY.to_string())
+        .build();
         let rows = sqlx::query(&sql)
             .fetch_all(&self.0)
             .await
@@ -138,6 +743,211 @@ pub enum GetQueryParam {
     Uuid(Uuid),
     Author(String),
     Tags(Vec<String>),
+    Status(Vec<PostStatusKind>),
+    Archived(bool),
+    Pending(bool),
+    VisibleFor(Option<UserModel>),
+    /// Only the audience part of `VisibleFor`, hidden and pending posts
+    /// aren't filtered out.
+    InAudienceOf(UserModel),
+    InFeedOf(UserModel),
+    PublishedSince(NaiveDateTime),
+}
+
+#[derive(sqlx::Type)]
+#[sqlx(type_name = "audience_kind")]
+enum AudienceKind {
+    Public,
+    Class,
+    Grade,
+    Teachers,
+    Administrators,
+}
+
+impl From<&Audience> for AudienceKind {
+    fn from(audience: &Audience) -> Self {
+        match audience {
+            Audience::Public => AudienceKind::Public,
+            Audience::Class { class: _ } => AudienceKind::Class,
+            Audience::Grade { class_num: _ } => AudienceKind::Grade,
+            Audience::Teachers { subject: _ } => AudienceKind::Teachers,
+            Audience::Administrators => AudienceKind::Administrators,
+        }
+    }
+}
+
+async fn add_audience(
+    connection: &mut PgConnection,
+    post: Uuid,
+    audience: &Audience,
+) -> Result<(), sqlx::Error> {
+    let (class_num, class_char, subject) = match audience {
+        Audience::Class { class } => (
+            Some(i16::from(class.class_num())),
+            Some(class.class_char()),
+            None,
+        ),
+        Audience::Grade { class_num } => (Some(i16::from(*class_num)), None, None),
+        Audience::Teachers { subject } => (None, None, subject.map(|subject| subject.to_string())),
+        Audience::Public | Audience::Administrators => (None, None, None),
+    };
+    sqlx::query(
+        "insert into post_audience (post, kind, class_num, class_char, subject) values ($1, $2, $3, $4, cast($5 as subject));",
+    )
+    .bind(post)
+    .bind(AudienceKind::from(audience))
+    .bind(class_num)
+    .bind(class_char)
+    .bind(subject)
+    .execute(connection)
+    .await?;
+    Ok(())
+}
+
+impl FromRow<'_, PgRow> for Audience {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        let audience = match row.try_get("kind")? {
+            AudienceKind::Public => Audience::Public,
+            AudienceKind::Class => {
+                let class_num: i16 = row.try_get("class_num")?;
+                let class_char: String = row.try_get("class_char")?;
+                let class = u8::try_from(class_num)
+                    .ok()
+                    .zip(class_char.bytes().next())
+                    .and_then(|(class_num, class_char)| Class::from(class_char, class_num).ok())
+                    .ok_or_else(|| {
+                        sqlx::Error::Decode(
+                            format!("invalid audience class {}{}", class_num, class_char).into(),
+                        )
+                    })?;
+                Audience::Class { class }
+            }
+            AudienceKind::Grade => {
+                let class_num: i16 = row.try_get("class_num")?;
+                Audience::Grade {
+                    class_num: u8::try_from(class_num)
+                        .map_err(|err| sqlx::Error::Decode(err.into()))?,
+                }
+            }
+            AudienceKind::Teachers => Audience::Teachers {
+                subject: row
+                    .try_get::<Option<String>, &str>("subject")?
+                    .map(|subject| {
+                        Subject::from_str(&subject).map_err(|_| {
+                            sqlx::Error::Decode(
+                                format!("invalid audience subject {}", subject).into(),
+                            )
+                        })
+                    })
+                    .transpose()?,
+            },
+            AudienceKind::Administrators => Audience::Administrators,
+        };
+        Ok(audience)
+    }
+}
+
+/// The condition on `post_audience` rows that target the class or the grade
+/// of a child the parent has a confirmed link to. `parent` is the SQL for the
+/// username, a bind parameter where the query allows it.
+fn children_condition(parent: &str) -> String {
+    format!(
+        "exists (select 1 from parent_links join students on students.username = parent_links.student where parent_links.parent = {} and parent_links.confirmed_at is not null and ((post_audience.kind = 'Class' and post_audience.class_num = students.class_num and post_audience.class_char = students.class_char) or (post_audience.kind = 'Grade' and post_audience.class_num = students.class_num)))",
+        parent
+    )
+}
+
+/// Whether the viewer sees hidden and pending posts: administrators do, once
+/// they enabled 2FA if the policy makes them use it.
+fn moderates(viewer: &UserModel) -> String {
+    if !viewer.user_specs().is_administrator() {
+        "false".to_string()
+    } else if two_factor::required(viewer) {
+        format!(
+            "exists (select 1 from two_factor where two_factor.username = '{}' and two_factor.enabled)",
+            viewer.username()
+        )
+    } else {
+        "true".to_string()
+    }
+}
+
+/// The post is the viewer's own, has no audience rows or one the viewer
+/// belongs to.
+fn in_audience(username: &str, user_type: &UserType) -> String {
+    format!(
+        "(posts.author = '{}' or not exists (select 1 from post_audience where post_audience.post = posts.uuid) or exists (select 1 from post_audience where post_audience.post = posts.uuid and ({})))",
+        username,
+        audience_condition(username, user_type)
+    )
+}
+
+/// Builds the condition on `post_audience` rows that a viewer of the given
+/// type satisfies. Posts without any audience rows are public.
+fn audience_condition(username: &str, user_type: &UserType) -> String {
+    match user_type {
+        UserType::Student { class } => format!(
+            "post_audience.kind = 'Public' or (post_audience.kind = 'Class' and post_audience.class_num = {} and post_audience.class_char = '{}') or (post_audience.kind = 'Grade' and post_audience.class_num = {})",
+            class.class_num(),
+            class.class_char(),
+            class.class_num()
+        ),
+        UserType::Teacher { subject } => format!(
+            "post_audience.kind = 'Public' or (post_audience.kind = 'Teachers' and (post_audience.subject is null or post_audience.subject = '{}'))",
+            subject.to_string()
+        ),
+        UserType::Administrator { job_title: _ } => "true".to_string(),
+        UserType::Other => "post_audience.kind = 'Public'".to_string(),
+        UserType::Parent => format!(
+            "post_audience.kind = 'Public' or {}",
+            children_condition(&format!("'{}'", username))
+        ),
+    }
+}
+
+#[derive(sqlx::Type, Clone, Copy)]
+#[sqlx(type_name = "post_status")]
+pub enum PostStatusKind {
+    Draft,
+    Scheduled,
+    Published,
+    Archived,
+}
+
+impl From<&PostStatus> for PostStatusKind {
+    fn from(status: &PostStatus) -> Self {
+        match status {
+            PostStatus::Draft => PostStatusKind::Draft,
+            PostStatus::Scheduled { publish_at: _ } => PostStatusKind::Scheduled,
+            PostStatus::Published => PostStatusKind::Published,
+            PostStatus::Archived => PostStatusKind::Archived,
+        }
+    }
+}
+
+impl ToSQL for PostStatusKind {
+    fn to_sql(&self) -> String {
+        match self {
+            PostStatusKind::Draft => "'Draft'",
+            PostStatusKind::Scheduled => "'Scheduled'",
+            PostStatusKind::Published => "'Published'",
+            PostStatusKind::Archived => "'Archived'",
+        }
+        .to_string()
+    }
+}
+
+impl FromRow<'_, PgRow> for PostStatus {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        Ok(match row.get("status") {
+            PostStatusKind::Draft => PostStatus::Draft,
+            PostStatusKind::Scheduled => PostStatus::Scheduled {
+                publish_at: row.get("publish_at"),
+            },
+            PostStatusKind::Published => PostStatus::Published,
+            PostStatusKind::Archived => PostStatus::Archived,
+        })
+    }
 }
 
 #[derive(Deserialize, Clone)]
@@ -160,7 +970,7 @@ impl ToSQL for GetQueryParam {
     fn to_sql(&self) -> String {
         match self {
             GetQueryParam::Uuid(uuid) => {
-                format!("uuid = '{}'", uuid)
+                format!("posts.uuid = '{}'", uuid)
             }
             GetQueryParam::Author(username) => {
                 format!("author = '{}'", username)
@@ -183,6 +993,47 @@ impl ToSQL for GetQueryParam {
                 };
                 format!("tags @> \"{}{}{}\"", "{", tag_string, "}")
             }
+            GetQueryParam::Status(statuses) => format!(
+                "posts.status in ({})",
+                statuses
+                    .iter()
+                    .map(|status| status.to_sql())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            GetQueryParam::Archived(archived) => format!(
+                "posts.status {} 'Archived'",
+                if *archived { "=" } else { "<>" }
+            ),
+            GetQueryParam::Pending(pending) => format!("posts.pending = {}", pending),
+            GetQueryParam::VisibleFor(viewer) => {
+                let (author, audience, moderator) = match viewer {
+                    Some(viewer) => (
+                        viewer.username(),
+                        in_audience(&viewer.username(), &viewer.user_specs()),
+                        moderates(viewer),
+                    ),
+                    None => (
+                        String::new(),
+                        in_audience("", &UserType::Other),
+                        "false".to_string(),
+                    ),
+                };
+                format!(
+                    "(not (posts.hidden or posts.pending) or posts.author = '{}' or {}) and {}",
+                    author, moderator, audience
+                )
+            }
+            GetQueryParam::InAudienceOf(viewer) => {
+                in_audience(&viewer.username(), &viewer.user_specs())
+            }
+            GetQueryParam::InFeedOf(viewer) => format!(
+                "(posts.pinned or posts.author in (select followed from follows where follower = '{}') or posts.tags && array(select tag from tag_subscriptions where username = '{}') or exists (select 1 from post_audience where post_audience.post = posts.uuid and post_audience.kind <> 'Public' and ({})))",
+                viewer.username(),
+                viewer.username(),
+                audience_condition(&viewer.username(), &viewer.user_specs())
+            ),
+            GetQueryParam::PublishedSince(since) => format!("posts.published_at > '{}'", since),
         }
     }
 }
@@ -0,0 +1,83 @@
+use crate::{dto::RevisionDTO, get_db_pool, models::revision::RevisionModel};
+use sqlx::{types::Uuid, PgConnection, PgPool, Row};
+
+pub struct RevisionsRepo(PgPool);
+
+impl RevisionsRepo {
+    pub async fn get_instance() -> RevisionsRepo {
+        RevisionsRepo(get_db_pool().await)
+    }
+
+    /// Stores `edited` as the next revision of the resource. The first edit
+    /// also stores `previous`, so the published text isn't lost. Runs on the
+    /// connection of the edit so both are committed together.
+    pub async fn record(
+        connection: &mut PgConnection,
+        previous: RevisionDTO,
+        edited: RevisionDTO,
+    ) -> Result<(), sqlx::Error> {
+        let last: i32 = sqlx::query(
+            "select coalesce(max(number), 0) as last from revisions where resource = $1;",
+        )
+        .bind(edited.resource)
+        .fetch_one(&mut *connection)
+        .await?
+        .get("last");
+        if last == 0 {
+            insert(connection, 1, previous).await?;
+            return insert(connection, 2, edited).await;
+        }
+        insert(connection, last + 1, edited).await
+    }
+
+    pub async fn get_for(&self, resource: &Uuid) -> Vec<RevisionModel> {
+        sqlx::query_as::<_, RevisionModel>(
+            "select * from revisions where resource = $1 order by number;",
+        )
+        .bind(resource)
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+
+    pub async fn get(&self, resource: &Uuid, number: i32) -> Option<RevisionModel> {
+        sqlx::query_as::<_, RevisionModel>(
+            "select * from revisions where resource = $1 and number = $2;",
+        )
+        .bind(resource)
+        .bind(number)
+        .fetch_optional(&self.0)
+        .await
+        .ok()
+        .flatten()
+    }
+}
+
+async fn insert(
+    connection: &mut PgConnection,
+    number: i32,
+    revision: RevisionDTO,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        insert into revisions (
+            resource,
+            number,
+            title,
+            content,
+            edited_by,
+            created_at
+        )
+        values ($1, $2, $3, $4, $5, $6);
+        "#,
+    )
+    .bind(revision.resource)
+    .bind(number)
+    .bind(revision.title)
+    .bind(revision.content)
+    .bind(revision.edited_by)
+    .bind(revision.created_at)
+    .execute(connection)
+    .await
+    .map(|_| ())
+}
@@ -0,0 +1,85 @@
+use crate::{get_db_pool, models::sign_in_attempt::SignInAttemptModel, types::Limit};
+use chrono::NaiveDateTime;
+use sqlx::{PgPool, Row};
+
+pub struct SignInAttemptsRepo(PgPool);
+
+impl SignInAttemptsRepo {
+    pub async fn get_instance() -> SignInAttemptsRepo {
+        SignInAttemptsRepo(get_db_pool().await)
+    }
+
+    /// `username` is stored as given, whether or not such an account exists.
+    pub async fn record(
+        &self,
+        username: &str,
+        ip: Option<&str>,
+        succeeded: bool,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "insert into sign_in_attempts (username, ip, succeeded, attempted_at) values ($1, $2, $3, $4);",
+        )
+        .bind(username)
+        .bind(ip)
+        .bind(succeeded)
+        .bind(chrono::Utc::now().naive_utc())
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    /// Failed attempts on the account after `since` and its last successful
+    /// sign-in, along with the time of the latest one.
+    pub async fn account_failures(
+        &self,
+        username: &str,
+        since: NaiveDateTime,
+    ) -> (i64, Option<NaiveDateTime>) {
+        sqlx::query(
+            r#"
+            select count(*) as failures, max(attempted_at) as last_failure
+            from sign_in_attempts
+            where username = $1 and not succeeded and attempted_at > greatest($2, (
+                select max(attempted_at) from sign_in_attempts where username = $1 and succeeded
+            ));
+            "#,
+        )
+        .bind(username)
+        .bind(since)
+        .fetch_one(&self.0)
+        .await
+        .map_or((0, None), |row| {
+            (row.get("failures"), row.get("last_failure"))
+        })
+    }
+
+    /// Failed attempts from the address after `since` on any account.
+    pub async fn ip_failures(
+        &self,
+        ip: &str,
+        since: NaiveDateTime,
+    ) -> (i64, Option<NaiveDateTime>) {
+        sqlx::query(
+            "select count(*) as failures, max(attempted_at) as last_failure from sign_in_attempts where ip = $1 and not succeeded and attempted_at > $2;",
+        )
+        .bind(ip)
+        .bind(since)
+        .fetch_one(&self.0)
+        .await
+        .map_or((0, None), |row| {
+            (row.get("failures"), row.get("last_failure"))
+        })
+    }
+
+    pub async fn get_for(&self, username: &str, limit: Limit) -> Vec<SignInAttemptModel> {
+        sqlx::query_as::<_, SignInAttemptModel>(
+            "select * from sign_in_attempts where username = $1 order by attempted_at desc limit $2 offset $3;",
+        )
+        .bind(username)
+        .bind(i64::from(limit.limit))
+        .bind(i64::from(limit.offset.unwrap_or(0)))
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+}
@@ -0,0 +1,49 @@
+use crate::{get_db_pool, models::user::UserModel};
+use sqlx::{PgPool, Row};
+
+pub struct SubscriptionsRepo(PgPool);
+
+impl SubscriptionsRepo {
+    pub async fn get_instance() -> SubscriptionsRepo {
+        SubscriptionsRepo(get_db_pool().await)
+    }
+
+    pub async fn subscribe(&self, user: &UserModel, tag: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "insert into tag_subscriptions (username, tag, subscribed_at) values ($1, $2, $3) on conflict do nothing;",
+        )
+        .bind(user.username())
+        .bind(tag)
+        .bind(chrono::Utc::now().naive_utc())
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn unsubscribe(&self, user: &UserModel, tag: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("delete from tag_subscriptions where username = $1 and tag = $2;")
+            .bind(user.username())
+            .bind(tag)
+            .execute(&self.0)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_tags(&self, user: &UserModel) -> Vec<String> {
+        sqlx::query("select tag from tag_subscriptions where username = $1 order by tag;")
+            .bind(user.username())
+            .fetch_all(&self.0)
+            .await
+            .map_or(Vec::new(), |rows| {
+                rows.iter().map(|row| row.get("tag")).collect()
+            })
+    }
+
+    pub async fn count(&self, user: &UserModel) -> i64 {
+        sqlx::query("select count(*) as count from tag_subscriptions where username = $1;")
+            .bind(user.username())
+            .fetch_one(&self.0)
+            .await
+            .map_or(0, |row| row.get("count"))
+    }
+}
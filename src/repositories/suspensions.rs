@@ -0,0 +1,170 @@
+use crate::{
+    get_db_pool,
+    models::suspension::{AppealStatus, SuspensionModel},
+    types::Limit,
+};
+use chrono::NaiveDateTime;
+use sqlx::{types::Uuid, PgConnection, PgPool};
+
+pub struct SuspensionsRepo(PgPool);
+
+/// `until` is stored in UTC, so the current time is bound as the given
+/// parameter rather than taken from the session time zone.
+fn active(now: usize) -> String {
+    format!("lifted_at is null and (until is null or until > ${now})")
+}
+
+impl SuspensionsRepo {
+    pub async fn get_instance() -> SuspensionsRepo {
+        SuspensionsRepo(get_db_pool().await)
+    }
+
+    pub async fn suspend(
+        &self,
+        username: &str,
+        reason: &str,
+        issued_by: &str,
+        until: Option<NaiveDateTime>,
+    ) -> Result<SuspensionModel, sqlx::Error> {
+        Self::insert(
+            &mut *self.0.acquire().await?,
+            username,
+            reason,
+            issued_by,
+            until,
+        )
+        .await
+    }
+
+    /// Runs on the given connection, so moderation can suspend in the same
+    /// transaction it records the action in.
+    pub async fn insert(
+        connection: &mut PgConnection,
+        username: &str,
+        reason: &str,
+        issued_by: &str,
+        until: Option<NaiveDateTime>,
+    ) -> Result<SuspensionModel, sqlx::Error> {
+        sqlx::query_as::<_, SuspensionModel>(
+            r#"
+            insert into suspensions (
+                uuid,
+                username,
+                reason,
+                issued_by,
+                created_at,
+                until
+            )
+            values ($1, $2, $3, $4, $5, $6)
+            returning *;
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(username)
+        .bind(reason)
+        .bind(issued_by)
+        .bind(chrono::Utc::now().naive_utc())
+        .bind(until)
+        .fetch_one(connection)
+        .await
+    }
+
+    /// The active suspension ending last, a ban comes before any timed one.
+    pub async fn get_active(&self, username: &str) -> Option<SuspensionModel> {
+        sqlx::query_as::<_, SuspensionModel>(&format!(
+            "select * from suspensions where username = $1 and {} order by until desc nulls first limit 1;",
+            active(2)
+        ))
+        .bind(username)
+        .bind(chrono::Utc::now().naive_utc())
+        .fetch_optional(&self.0)
+        .await
+        .ok()
+        .flatten()
+    }
+
+    pub async fn get_by_uuid(&self, uuid: &Uuid) -> Option<SuspensionModel> {
+        sqlx::query_as::<_, SuspensionModel>("select * from suspensions where uuid = $1;")
+            .bind(uuid)
+            .fetch_optional(&self.0)
+            .await
+            .ok()
+            .flatten()
+    }
+
+    pub async fn get_all_active(&self, limit: Limit) -> Vec<SuspensionModel> {
+        sqlx::query_as::<_, SuspensionModel>(&format!(
+            "select * from suspensions where {} order by created_at desc limit $1 offset $2;",
+            active(3)
+        ))
+        .bind(i64::from(limit.limit))
+        .bind(i64::from(limit.offset.unwrap_or(0)))
+        .bind(chrono::Utc::now().naive_utc())
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+
+    pub async fn get_pending_appeals(&self, limit: Limit) -> Vec<SuspensionModel> {
+        sqlx::query_as::<_, SuspensionModel>(&format!(
+            "select * from suspensions where appeal_status = $1 and {} order by appealed_at limit $2 offset $3;",
+            active(4)
+        ))
+        .bind(AppealStatus::Pending)
+        .bind(i64::from(limit.limit))
+        .bind(i64::from(limit.offset.unwrap_or(0)))
+        .bind(chrono::Utc::now().naive_utc())
+        .fetch_all(&self.0)
+        .await
+        .unwrap_or_default()
+    }
+
+    pub async fn lift(&self, suspension: &SuspensionModel) -> Result<(), sqlx::Error> {
+        sqlx::query("update suspensions set lifted_at = $1 where uuid = $2 and lifted_at is null;")
+            .bind(chrono::Utc::now().naive_utc())
+            .bind(suspension.uuid())
+            .execute(&self.0)
+            .await?;
+        Ok(())
+    }
+
+    /// Marks suspensions whose time ran out as lifted and returns them.
+    pub async fn lift_expired(&self) -> Result<Vec<SuspensionModel>, sqlx::Error> {
+        sqlx::query_as::<_, SuspensionModel>(
+            "update suspensions set lifted_at = until where lifted_at is null and until <= $1 returning *;",
+        )
+        .bind(chrono::Utc::now().naive_utc())
+        .fetch_all(&self.0)
+        .await
+    }
+
+    pub async fn appeal(
+        &self,
+        suspension: &SuspensionModel,
+        appeal: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "update suspensions set appeal = $1, appeal_status = $2, appealed_at = $3 where uuid = $4;",
+        )
+        .bind(appeal)
+        .bind(AppealStatus::Pending)
+        .bind(chrono::Utc::now().naive_utc())
+        .bind(suspension.uuid())
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_appeal_status(
+        &self,
+        suspension: &SuspensionModel,
+        status: AppealStatus,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("update suspensions set appeal_status = $1 where uuid = $2;")
+            .bind(status)
+            .bind(suspension.uuid())
+            .execute(&self.0)
+            .await?;
+        Ok(())
+    }
+}
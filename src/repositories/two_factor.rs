@@ -0,0 +1,173 @@
+use crate::{get_db_pool, models::two_factor::TwoFactorModel};
+use chrono::NaiveDateTime;
+use sqlx::{PgPool, Postgres, Transaction};
+
+pub struct TwoFactorRepo(PgPool);
+
+async fn replace_codes(
+    transaction: &mut Transaction<'_, Postgres>,
+    username: &str,
+    code_hashes: &[String],
+) -> Result<(), sqlx::Error> {
+    sqlx::query("delete from recovery_codes where username = $1;")
+        .bind(username)
+        .execute(&mut **transaction)
+        .await?;
+    for code_hash in code_hashes {
+        sqlx::query("insert into recovery_codes (code_hash, username) values ($1, $2);")
+            .bind(code_hash)
+            .bind(username)
+            .execute(&mut **transaction)
+            .await?;
+    }
+    Ok(())
+}
+
+impl TwoFactorRepo {
+    pub async fn get_instance() -> TwoFactorRepo {
+        TwoFactorRepo(get_db_pool().await)
+    }
+
+    pub async fn get(&self, username: &str) -> Option<TwoFactorModel> {
+        sqlx::query_as::<_, TwoFactorModel>("select * from two_factor where username = $1;")
+            .bind(username)
+            .fetch_optional(&self.0)
+            .await
+            .ok()
+            .flatten()
+    }
+
+    /// Stores a secret that isn't confirmed yet, replacing an earlier
+    /// unconfirmed one. Returns `false` if the user already has 2FA enabled.
+    pub async fn start_enrollment(
+        &self,
+        username: &str,
+        secret: &str,
+    ) -> Result<bool, sqlx::Error> {
+        Ok(sqlx::query(
+            "insert into two_factor (username, secret) values ($1, $2) on conflict (username) do update set secret = $2, last_step = null where not two_factor.enabled;",
+        )
+        .bind(username)
+        .bind(secret)
+        .execute(&self.0)
+        .await?
+        .rows_affected()
+            > 0)
+    }
+
+    /// Turns the confirmed secret on together with a fresh set of recovery
+    /// codes.
+    pub async fn enable(&self, username: &str, code_hashes: &[String]) -> Result<(), sqlx::Error> {
+        let mut transaction = self.0.begin().await?;
+        sqlx::query("update two_factor set enabled = true, enabled_at = $1 where username = $2;")
+            .bind(chrono::Utc::now().naive_utc())
+            .bind(username)
+            .execute(&mut *transaction)
+            .await?;
+        replace_codes(&mut transaction, username, code_hashes).await?;
+        transaction.commit().await
+    }
+
+    pub async fn disable(&self, username: &str) -> Result<(), sqlx::Error> {
+        let mut transaction = self.0.begin().await?;
+        sqlx::query("delete from recovery_codes where username = $1;")
+            .bind(username)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query("delete from two_factor_sessions where username = $1;")
+            .bind(username)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query("delete from two_factor where username = $1;")
+            .bind(username)
+            .execute(&mut *transaction)
+            .await?;
+        transaction.commit().await
+    }
+
+    /// The earlier codes stop working, used or not.
+    pub async fn replace_recovery_codes(
+        &self,
+        username: &str,
+        code_hashes: &[String],
+    ) -> Result<(), sqlx::Error> {
+        let mut transaction = self.0.begin().await?;
+        replace_codes(&mut transaction, username, code_hashes).await?;
+        transaction.commit().await
+    }
+
+    /// Marks the code used, `false` if the user has no such unused code.
+    pub async fn use_recovery_code(
+        &self,
+        username: &str,
+        code_hash: &str,
+    ) -> Result<bool, sqlx::Error> {
+        Ok(sqlx::query(
+            "update recovery_codes set used_at = $1 where username = $2 and code_hash = $3 and used_at is null;",
+        )
+        .bind(chrono::Utc::now().naive_utc())
+        .bind(username)
+        .bind(code_hash)
+        .execute(&self.0)
+        .await?
+        .rows_affected()
+            > 0)
+    }
+
+    /// Remembers `step` as the last one a code was accepted for, `false` if
+    /// a code from it or a later one was accepted already.
+    pub async fn accept_step(&self, username: &str, step: u64) -> Result<bool, sqlx::Error> {
+        Ok(sqlx::query(
+            "update two_factor set last_step = $1 where username = $2 and (last_step is null or last_step < $1);",
+        )
+        .bind(step as i64)
+        .bind(username)
+        .execute(&self.0)
+        .await?
+        .rows_affected()
+            > 0)
+    }
+
+    pub async fn open_session(
+        &self,
+        token_hash: &str,
+        username: &str,
+        expires_at: NaiveDateTime,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("delete from two_factor_sessions where username = $1 and expires_at <= $2;")
+            .bind(username)
+            .bind(chrono::Utc::now().naive_utc())
+            .execute(&self.0)
+            .await?;
+        sqlx::query(
+            "insert into two_factor_sessions (token_hash, username, expires_at) values ($1, $2, $3);",
+        )
+        .bind(token_hash)
+        .bind(username)
+        .bind(expires_at)
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn has_session(&self, username: &str, token_hash: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar(
+            "select exists (select 1 from two_factor_sessions where username = $1 and token_hash = $2 and expires_at > $3);",
+        )
+        .bind(username)
+        .bind(token_hash)
+        .bind(chrono::Utc::now().naive_utc())
+        .fetch_one(&self.0)
+        .await
+    }
+
+    pub async fn recovery_codes_left(&self, username: &str) -> i64 {
+        sqlx::query_scalar(
+            "select count(*) from recovery_codes where username = $1 and used_at is null;",
+        )
+        .bind(username)
+        .fetch_one(&self.0)
+        .await
+        .unwrap_or(0)
+    }
+}
@@ -4,12 +4,14 @@ use crate::{
     get_db_pool,
     models::user::{UserModel, UserType},
     prelude::ToSQL,
+    repositories::invitations::InvitationsRepo,
     types::{Class, Subject},
     utils::sql::SelectRequestBuilder,
     validators::repository_query::users::ValidatedChangeQueryParam,
 };
+use chrono::NaiveDateTime;
 use serde::Serialize;
-use sqlx::{postgres::PgRow, FromRow, PgPool, Row};
+use sqlx::{postgres::PgRow, FromRow, PgConnection, PgPool, Row};
 use std::str::FromStr;
 use uuid::Uuid;
 
@@ -17,27 +19,50 @@ pub mod queries;
 
 pub struct UserRepo(PgPool);
 
-#[derive(Serialize)]
+const USERS_SELECT: &str = "select   
+                 users.uuid,
+                 users.username,
+                 users.password,
+                 users.email,
+                 users.first_name,
+                 users.last_name,
+                 users.phone_number,
+                 users.email_verified,
+                 users.user_specs,
+                 users.birth_date,
+                 users.about,
+                students.class_num,
+                students.class_char,
+                teachers.subject,
+                administrators.job_title from users
+            left outer join students on users.username = students.username 
+            left outer join teachers on users.username = teachers.username 
+            left outer join administrators on users.username = administrators.username";
+
+#[derive(Debug, Serialize)]
 pub enum RegistrationError {
     UsernameAlreadyExists,
     ProblemsWithDB,
     ErrorsOnRegisttrationUserType,
 }
 
+impl From<sqlx::Error> for RegistrationError {
+    fn from(_: sqlx::Error) -> Self {
+        RegistrationError::ProblemsWithDB
+    }
+}
+
 async fn create_user(
     uuid: Uuid,
     user_dto: &UserRegistrationDTO,
-    repo: &UserRepo,
+    connection: &mut PgConnection,
 ) -> Result<(), RegistrationError> {
-    if !repo.is_username_free(user_dto.username.clone()).await {
-        return Err(RegistrationError::UsernameAlreadyExists);
-    }
-
     let user_type = match user_dto.user_specs {
         UserType::Teacher { subject: _ } => UserTypeFromRow::Teacher,
         UserType::Student { class: _ } => UserTypeFromRow::Student,
         UserType::Administrator { job_title: _ } => UserTypeFromRow::Administrator,
         UserType::Other => UserTypeFromRow::Other,
+        UserType::Parent => UserTypeFromRow::Parent,
     };
 
     let sql = format!("insert into users 
@@ -53,41 +78,37 @@ async fn create_user(
         .bind(user_type)
         .bind(user_dto.birth_date)
         .bind(user_dto.about.clone())
-        .execute(&repo.pool())
+        .execute(connection)
         .await
-        .expect("error in sql");
+        .map_err(|_| RegistrationError::ProblemsWithDB)?;
     Ok(())
 }
 
 async fn create_user_specs(
     user_dto: &UserRegistrationDTO,
-    pool: &PgPool,
+    connection: &mut PgConnection,
 ) -> Result<(), RegistrationError> {
-    let username = user_dto.username.as_str();
-    let sql = format!(
-        "insert into {};",
-        match user_dto.user_specs.clone() {
-            UserType::Teacher { subject } => format!(
-                "teachers (username, subject) values ('{}', '{}')",
-                username,
-                subject.to_string()
-            ),
-            UserType::Student { class } => format!(
-                "students (username, class_num, class_char) values ('{}', {}, '{}')",
-                username,
-                class.class_num(),
-                class.class_char()
-            ),
-            UserType::Administrator { job_title } => format!(
-                "administrators (username, job_title) values('{}', '{}')",
-                username, job_title
-            ),
-            UserType::Other => return Ok(()),
+    let query = match user_dto.user_specs.clone() {
+        UserType::Teacher { subject } => {
+            sqlx::query("insert into teachers (username, subject) values ($1, $2);")
+                .bind(user_dto.username.clone())
+                .bind(subject.to_string())
+        }
+        UserType::Student { class } => sqlx::query(
+            "insert into students (username, class_num, class_char) values ($1, $2, $3);",
+        )
+        .bind(user_dto.username.clone())
+        .bind(class.class_num() as i16)
+        .bind(class.class_char()),
+        UserType::Administrator { job_title } => {
+            sqlx::query("insert into administrators (username, job_title) values ($1, $2);")
+                .bind(user_dto.username.clone())
+                .bind(job_title)
         }
-    );
-    let query = sqlx::query(&sql);
+        UserType::Other | UserType::Parent => return Ok(()),
+    };
 
-    if query.execute(pool).await.is_err() {
+    if query.execute(connection).await.is_err() {
         return Err(RegistrationError::ProblemsWithDB);
     }
     Ok(())
@@ -108,48 +129,115 @@ impl UserRepo {
     }
 
     pub async fn get_by_username(&self, username: &str) -> Option<UserModel> {
-        self.get_one(vec![GetByQueryParam::Username(username.to_string())])
+        sqlx::query_as::<_, UserModel>(&format!("{} where users.username = $1", USERS_SELECT))
+            .bind(username)
+            .fetch_optional(&self.pool())
             .await
+            .ok()
+            .flatten()
+    }
+
+    /// Emails are compared ignoring case.
+    pub async fn get_by_email(&self, email: &str) -> Option<UserModel> {
+        sqlx::query_as::<_, UserModel>(&format!(
+            "{} where lower(users.email) = lower($1) limit 1",
+            USERS_SELECT
+        ))
+        .bind(email)
+        .fetch_optional(&self.pool())
+        .await
+        .ok()
+        .flatten()
+    }
+
+    /// The user whose username or, failing that, email is `login`.
+    pub async fn get_by_login(&self, login: &str) -> Option<UserModel> {
+        sqlx::query_as::<_, UserModel>(&format!(
+            "{} where users.username = $1 or users.email = $1
+            order by users.username = $1 desc limit 1",
+            USERS_SELECT
+        ))
+        .bind(login)
+        .fetch_optional(&self.pool())
+        .await
+        .ok()
+        .flatten()
     }
 
     pub async fn register(
         &self,
         registration_dto: UserRegistrationDTO,
     ) -> Result<UserModel, RegistrationError> {
+        if !self
+            .is_username_free(registration_dto.username.clone())
+            .await
+        {
+            return Err(RegistrationError::UsernameAlreadyExists);
+        }
         let uuid = Uuid::new_v4();
-        create_user(uuid.clone(), &registration_dto, &self).await?;
-        create_user_specs(&registration_dto, &self.pool()).await?;
+        let mut transaction = self.0.begin().await?;
+        create_user(uuid, &registration_dto, &mut transaction).await?;
+        create_user_specs(&registration_dto, &mut transaction).await?;
+        transaction.commit().await?;
         Ok(self.get_by_uuid(&uuid).await.unwrap())
     }
 
+    /// Registers the user and counts a use of the invitation in one
+    /// transaction. `None` when the invitation can't be used anymore.
+    pub async fn register_invited(
+        &self,
+        registration_dto: UserRegistrationDTO,
+        code_hash: &str,
+    ) -> Result<Option<UserModel>, RegistrationError> {
+        if !self
+            .is_username_free(registration_dto.username.clone())
+            .await
+        {
+            return Err(RegistrationError::UsernameAlreadyExists);
+        }
+        let uuid = Uuid::new_v4();
+        let mut transaction = self.0.begin().await?;
+        if InvitationsRepo::claim(&mut transaction, code_hash)
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
+        create_user(uuid, &registration_dto, &mut transaction).await?;
+        create_user_specs(&registration_dto, &mut transaction).await?;
+        transaction.commit().await?;
+        Ok(self.get_by_uuid(&uuid).await)
+    }
+
+    /// Registers all of the users or, if one of them fails, none.
+    pub async fn register_many(
+        &self,
+        registration_dtos: &[UserRegistrationDTO],
+    ) -> Result<Vec<UserModel>, RegistrationError> {
+        let mut transaction = self.0.begin().await?;
+        let mut uuids = Vec::with_capacity(registration_dtos.len());
+        for registration_dto in registration_dtos {
+            let uuid = Uuid::new_v4();
+            create_user(uuid, registration_dto, &mut transaction).await?;
+            create_user_specs(registration_dto, &mut transaction).await?;
+            uuids.push(uuid);
+        }
+        transaction.commit().await?;
+        let mut users = Vec::with_capacity(uuids.len());
+        for uuid in uuids {
+            users.extend(self.get_by_uuid(&uuid).await);
+        }
+        Ok(users)
+    }
+
     async fn get_one(&self, params: Vec<GetByQueryParam>) -> Option<UserModel> {
         self.get_many(params).await.first().map(|user| user.clone())
     }
 
     pub async fn get_many(&self, params: Vec<GetByQueryParam>) -> Vec<UserModel> {
-        let sql = SelectRequestBuilder::<(), GetByQueryParam>::new(
-            "select   
-                 users.uuid,
-                 users.username,
-                 users.password,
-                 users.email,
-                 users.first_name,
-                 users.last_name,
-                 users.phone_number,
-                 users.user_specs,
-                 users.birth_date,
-                 users.about,
-                students.class_num,
-                students.class_char,
-                teachers.subject,
-                administrators.job_title from users
-            left outer join students on users.username = students.username 
-            left outer join teachers on users.username = teachers.username 
-            left outer join administrators on users.username = administrators.username"
-                .to_string(),
-            params,
-        )
-        .build();
+        let sql =
+            SelectRequestBuilder::<(), GetByQueryParam>::new(USERS_SELECT.to_string(), params)
+                .build();
 
         match sqlx::query_as::<_, UserModel>(&sql)
             .fetch_all(&self.pool())
@@ -161,9 +249,39 @@ impl UserRepo {
     }
 
     pub async fn is_username_free(&self, username: String) -> bool {
-        self.get_one(vec![GetByQueryParam::Username(username)])
+        self.get_by_username(&username).await.is_none()
+    }
+
+    pub async fn registered_at(&self, username: &str) -> Option<NaiveDateTime> {
+        sqlx::query("select registered_at from users where username = $1;")
+            .bind(username)
+            .fetch_optional(&self.pool())
             .await
-            .is_none()
+            .ok()
+            .flatten()
+            .map(|row| row.get("registered_at"))
+    }
+
+    pub async fn set_email_verified(
+        &self,
+        username: &str,
+        email_verified: bool,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("update users set email_verified = $1 where username = $2;")
+            .bind(email_verified)
+            .bind(username)
+            .execute(&self.pool())
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_password(&self, username: &str, password: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("update users set password = $1 where username = $2;")
+            .bind(password)
+            .bind(username)
+            .execute(&self.pool())
+            .await?;
+        Ok(())
     }
 
     pub async fn change(&self, params: Vec<ValidatedChangeQueryParam>, model: UserModel) {
@@ -205,6 +323,7 @@ impl FromRow<'_, PgRow> for UserType {
                 job_title: row.get("job_title"),
             },
             UserTypeFromRow::Other => UserType::Other,
+            UserTypeFromRow::Parent => UserType::Parent,
         })
     }
 }
@@ -216,4 +335,5 @@ enum UserTypeFromRow {
     Administrator,
     Teacher,
     Other,
+    Parent,
 }
@@ -73,6 +73,7 @@ impl ToSQL for GetByQueryParam {
                     UserType::Student { class: _ } => "Student",
                     UserType::Administrator { job_title: _ } => "Administrator",
                     UserType::Other => "Other",
+                    UserType::Parent => "Parent",
                 }
             ),
         }
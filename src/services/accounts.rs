@@ -0,0 +1,208 @@
+use crate::{
+    models::{email_token::TokenPurpose, user::UserModel},
+    prelude::Validateble,
+    repositories::{
+        email_tokens::EmailTokensRepo,
+        users::{queries::ChangeQueryParam, UserRepo},
+    },
+    utils::{
+        config::minutes,
+        mailer::{get_mailer, Mail, MailError, Mailer},
+    },
+    validators::repository_query::users::ValidationError,
+};
+use actix_web::rt;
+use lazy_static::lazy_static;
+use openssl::{rand::rand_bytes, sha::sha256};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub enum AccountError {
+    AlreadyVerified,
+    InvalidToken,
+    InvalidPassword(Vec<ValidationError>),
+    ProblemsWithMail(MailError),
+    ProblemsWithRandom,
+    ProblemsWithDB,
+}
+
+impl From<sqlx::Error> for AccountError {
+    fn from(_: sqlx::Error) -> Self {
+        AccountError::ProblemsWithDB
+    }
+}
+
+impl From<MailError> for AccountError {
+    fn from(err: MailError) -> Self {
+        AccountError::ProblemsWithMail(err)
+    }
+}
+
+lazy_static! {
+    pub(super) static ref PUBLIC_URL: String =
+        dotenv::var("PUBLIC_URL").unwrap_or("https://127.0.0.1:8080".to_string());
+    static ref VERIFICATION_TTL: chrono::Duration =
+        minutes("EMAIL_VERIFICATION_TTL_MINUTES", 48 * 60);
+    static ref RESET_TTL: chrono::Duration = minutes("PASSWORD_RESET_TTL_MINUTES", 30);
+    static ref INVITATION_TTL: chrono::Duration =
+        minutes("ACCOUNT_INVITATION_TTL_MINUTES", 7 * 24 * 60);
+}
+
+pub(super) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub(super) fn hash(token: &str) -> String {
+    hex(&sha256(token.as_bytes()))
+}
+
+/// Creates a token for `user` and returns it, only its hash is kept.
+async fn issue(
+    user: &UserModel,
+    purpose: TokenPurpose,
+    ttl: chrono::Duration,
+) -> Result<String, AccountError> {
+    let mut bytes = [0; 32];
+    rand_bytes(&mut bytes).map_err(|_| AccountError::ProblemsWithRandom)?;
+    let token = hex(&bytes);
+    EmailTokensRepo::get_instance()
+        .await
+        .create(
+            &hash(&token),
+            &user.username(),
+            purpose,
+            &user.email(),
+            chrono::Utc::now().naive_utc() + ttl,
+        )
+        .await?;
+    Ok(token)
+}
+
+/// Mails a link confirming that the user owns their current address.
+pub async fn send_verification(user: &UserModel) -> Result<(), AccountError> {
+    if user.is_email_verified() {
+        return Err(AccountError::AlreadyVerified);
+    }
+    let token = issue(user, TokenPurpose::EmailVerification, *VERIFICATION_TTL).await?;
+    get_mailer()
+        .send(&Mail {
+            to: user.email(),
+            subject: "Confirm your email".to_string(),
+            body: format!(
+                "Hello, {}!\n\nOpen this link to confirm your email:\n{}/users/email/verify?token={}\n\nThe link works for {} hours.",
+                user.first_name(),
+                *PUBLIC_URL,
+                token,
+                VERIFICATION_TTL.num_hours()
+            ),
+        })
+        .await?;
+    Ok(())
+}
+
+/// Mails a user created for them by someone else a link to choose their
+/// password. It's a password reset token that lives longer.
+pub async fn send_invitation(user: &UserModel) -> Result<(), AccountError> {
+    let token = issue(user, TokenPurpose::PasswordReset, *INVITATION_TTL).await?;
+    get_mailer()
+        .send(&Mail {
+            to: user.email(),
+            subject: "Your account is ready".to_string(),
+            body: format!(
+                "Hello, {}!\n\nAn account with the username {} was created for you. To start using it, send the password you want along with this token to {}/users/password/reset:\n{}\n\nThe token works for {} days.",
+                user.first_name(),
+                user.username(),
+                *PUBLIC_URL,
+                token,
+                INVITATION_TTL.num_days()
+            ),
+        })
+        .await?;
+    Ok(())
+}
+
+/// The token only counts if the user still has the address it was sent to.
+pub async fn verify_email(token: &str) -> Result<(), AccountError> {
+    let token = EmailTokensRepo::get_instance()
+        .await
+        .consume(&hash(token), TokenPurpose::EmailVerification)
+        .await?
+        .ok_or(AccountError::InvalidToken)?;
+    let users = UserRepo::get_instance().await;
+    match users.get_by_username(&token.username()).await {
+        Some(user) if user.email() == token.email() => {
+            Ok(users.set_email_verified(&user.username(), true).await?)
+        }
+        _ => Err(AccountError::InvalidToken),
+    }
+}
+
+/// Called once the user's address changed, the new one has to be verified.
+pub async fn email_changed(user: &UserModel) -> Result<(), AccountError> {
+    UserRepo::get_instance()
+        .await
+        .set_email_verified(&user.username(), false)
+        .await?;
+    let user = UserRepo::get_instance()
+        .await
+        .get_by_username(&user.username())
+        .await
+        .ok_or(AccountError::ProblemsWithDB)?;
+    send_verification(&user).await
+}
+
+/// Mails a reset link to the account with the given username or address.
+/// Nothing tells the caller whether such an account exists, the lookup and
+/// the mail happen after the request is answered.
+pub fn request_password_reset(login: String) {
+    rt::spawn(async move {
+        let Some(user) = UserRepo::get_instance().await.get_by_login(&login).await else {
+            return;
+        };
+        let token = match issue(&user, TokenPurpose::PasswordReset, *RESET_TTL).await {
+            Ok(token) => token,
+            Err(_) => return,
+        };
+        let _ = get_mailer()
+            .send(&Mail {
+                to: user.email(),
+                subject: "Reset your password".to_string(),
+                body: format!(
+                    "Hello, {}!\n\nSomeone asked to reset the password of {}. If it was you, send the new password along with this token to {}/users/password/reset:\n{}\n\nThe token works for {} minutes. If it wasn't you, ignore this mail.",
+                    user.first_name(),
+                    user.username(),
+                    *PUBLIC_URL,
+                    token,
+                    RESET_TTL.num_minutes()
+                ),
+            })
+            .await;
+    });
+}
+
+/// A password that doesn't pass validation leaves the token usable. Since
+/// the token came by mail, the address counts as verified afterwards.
+pub async fn reset_password(token: &str, password: String) -> Result<(), AccountError> {
+    let repo = EmailTokensRepo::get_instance().await;
+    let users = UserRepo::get_instance().await;
+    let token_hash = hash(token);
+    let token = repo
+        .get(&token_hash, TokenPurpose::PasswordReset)
+        .await
+        .ok_or(AccountError::InvalidToken)?;
+    let user = users
+        .get_by_username(&token.username())
+        .await
+        .ok_or(AccountError::InvalidToken)?;
+    ChangeQueryParam::Password(password.clone())
+        .validate(&user)
+        .map_err(AccountError::InvalidPassword)?;
+    repo.consume(&token_hash, TokenPurpose::PasswordReset)
+        .await?
+        .ok_or(AccountError::InvalidToken)?;
+    users.set_password(&user.username(), &password).await?;
+    if user.email() == token.email() {
+        users.set_email_verified(&user.username(), true).await?;
+    }
+    Ok(())
+}
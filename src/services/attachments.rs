@@ -0,0 +1,177 @@
+use crate::{
+    dto::AttachmentDTO,
+    models::{
+        attachment::{AttachmentKind, AttachmentModel, ImageVariant, ProcessingState},
+        post::PostModel,
+    },
+    repositories::attachments::AttachmentsRepo,
+    utils::{
+        config,
+        storage::{get_storage, Storage, StorageError},
+    },
+};
+use lazy_static::lazy_static;
+use serde::Serialize;
+use uuid::Uuid;
+
+lazy_static! {
+    static ref ATTACHMENT_MAX_SIZE: usize =
+        config::setting("ATTACHMENT_MAX_SIZE", 10 * 1024 * 1024);
+}
+
+/// Types are detected from the content itself, the one the client sent is
+/// not trusted.
+const ALLOWED_TYPES: [(&str, AttachmentKind); 10] = [
+    ("image/jpeg", AttachmentKind::Image),
+    ("image/png", AttachmentKind::Image),
+    ("image/gif", AttachmentKind::Image),
+    ("image/webp", AttachmentKind::Image),
+    ("application/pdf", AttachmentKind::Document),
+    (
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        AttachmentKind::Document,
+    ),
+    (
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        AttachmentKind::Document,
+    ),
+    (
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        AttachmentKind::Document,
+    ),
+    (
+        "application/vnd.oasis.opendocument.text",
+        AttachmentKind::Document,
+    ),
+    (
+        "application/vnd.oasis.opendocument.spreadsheet",
+        AttachmentKind::Document,
+    ),
+];
+
+#[derive(Debug, Serialize)]
+pub enum AttachmentError {
+    PostDoesntExist,
+    AttachmentDoesntExist,
+    AttachmentIsProcessing,
+    ProcessingFailed,
+    EditsNotAuthor,
+    MissingFile,
+    TooLarge,
+    UnsupportedType,
+    ProblemsWithStorage,
+    ProblemsWithDB,
+}
+
+impl From<StorageError> for AttachmentError {
+    fn from(err: StorageError) -> Self {
+        match err {
+            StorageError::NotFound => AttachmentError::AttachmentDoesntExist,
+            StorageError::Unavailable => AttachmentError::ProblemsWithStorage,
+        }
+    }
+}
+
+pub fn max_size() -> usize {
+    *ATTACHMENT_MAX_SIZE
+}
+
+fn detect_type(content: &[u8]) -> Result<(&'static str, AttachmentKind), AttachmentError> {
+    let detected = infer::get(content).ok_or(AttachmentError::UnsupportedType)?;
+    ALLOWED_TYPES
+        .into_iter()
+        .find(|(mime_type, _)| *mime_type == detected.mime_type())
+        .ok_or(AttachmentError::UnsupportedType)
+}
+
+/// Keeps only the last path component and drops characters that can't be put
+/// back into a `Content-Disposition` header.
+fn sanitize_filename(filename: &str) -> String {
+    let filename: String = filename
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|char| !char.is_control() && *char != '"')
+        .take(255)
+        .collect();
+    match filename.trim() {
+        "" => "attachment".to_string(),
+        filename => filename.to_string(),
+    }
+}
+
+pub async fn attach(
+    post: &PostModel,
+    filename: &str,
+    content: Vec<u8>,
+) -> Result<AttachmentModel, AttachmentError> {
+    if content.is_empty() {
+        return Err(AttachmentError::MissingFile);
+    }
+    if content.len() > max_size() {
+        return Err(AttachmentError::TooLarge);
+    }
+    let (content_type, kind) = detect_type(&content)?;
+    let uuid = Uuid::new_v4();
+    let storage_key = format!("attachments/{uuid}");
+    let size = content.len() as i64;
+    get_storage()
+        .put(&storage_key, content, content_type)
+        .await?;
+    let created = AttachmentsRepo::get_instance()
+        .await
+        .create(AttachmentDTO {
+            uuid,
+            post: post.uuid(),
+            filename: sanitize_filename(filename),
+            content_type: content_type.to_string(),
+            kind,
+            size,
+            storage_key: storage_key.clone(),
+            processing: match kind {
+                AttachmentKind::Image => ProcessingState::Pending,
+                AttachmentKind::Document => ProcessingState::Ready,
+            },
+        })
+        .await;
+    match created {
+        Ok(attachment) => Ok(attachment),
+        Err(_) => {
+            let _ = get_storage().delete(&storage_key).await;
+            Err(AttachmentError::ProblemsWithDB)
+        }
+    }
+}
+
+/// Images are held back until processing replaced the upload with an
+/// EXIF-stripped original.
+pub async fn content(
+    attachment: &AttachmentModel,
+    variant: ImageVariant,
+) -> Result<Vec<u8>, AttachmentError> {
+    match attachment.processing() {
+        ProcessingState::Pending => return Err(AttachmentError::AttachmentIsProcessing),
+        ProcessingState::Failed => return Err(AttachmentError::ProcessingFailed),
+        ProcessingState::Ready => {}
+    }
+    if attachment.kind() == AttachmentKind::Document && variant != ImageVariant::Original {
+        return Err(AttachmentError::AttachmentDoesntExist);
+    }
+    Ok(get_storage().get(&attachment.storage_key(variant)).await?)
+}
+
+pub async fn remove(attachment: &AttachmentModel) -> Result<(), AttachmentError> {
+    AttachmentsRepo::get_instance()
+        .await
+        .delete(attachment)
+        .await
+        .map_err(|_| AttachmentError::ProblemsWithDB)?;
+    for variant in ImageVariant::ALL {
+        match get_storage().delete(&attachment.storage_key(variant)).await {
+            Ok(_) | Err(StorageError::NotFound) => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(())
+}
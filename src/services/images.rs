@@ -0,0 +1,113 @@
+use crate::{
+    models::attachment::{AttachmentModel, ImageVariant, ProcessingState},
+    repositories::attachments::AttachmentsRepo,
+    utils::storage::{get_storage, Storage},
+};
+use actix_web::web;
+use image::{
+    codecs::{jpeg::JpegEncoder, png::PngEncoder},
+    imageops::FilterType,
+    DynamicImage, ImageDecoder, ImageError, ImageReader,
+};
+use std::io::Cursor;
+
+const JPEG_QUALITY: u8 = 85;
+
+pub struct EncodedVariant {
+    pub variant: ImageVariant,
+    pub content: Vec<u8>,
+    pub content_type: &'static str,
+}
+
+/// Decodes the upload and rotates it upright, metadata is not carried into
+/// the decoded image.
+fn decode(content: &[u8]) -> Result<DynamicImage, ImageError> {
+    let mut decoder = ImageReader::new(Cursor::new(content))
+        .with_guessed_format()?
+        .into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+/// Images with transparency stay PNG, everything else becomes JPEG.
+fn encode(image: &DynamicImage) -> Result<(Vec<u8>, &'static str), ImageError> {
+    let mut content = Vec::new();
+    if image.color().has_alpha() {
+        image
+            .to_rgba8()
+            .write_with_encoder(PngEncoder::new(&mut content))?;
+        return Ok((content, "image/png"));
+    }
+    image
+        .to_rgb8()
+        .write_with_encoder(JpegEncoder::new_with_quality(&mut content, JPEG_QUALITY))?;
+    Ok((content, "image/jpeg"))
+}
+
+pub fn generate_variants(content: &[u8]) -> Result<Vec<EncodedVariant>, ImageError> {
+    let image = decode(content)?;
+    let mut variants = Vec::new();
+    for variant in ImageVariant::ALL {
+        let (content, content_type) = match variant.max_side() {
+            Some(side) if image.width() > side || image.height() > side => {
+                encode(&image.resize(side, side, FilterType::Lanczos3))?
+            }
+            _ => encode(&image)?,
+        };
+        variants.push(EncodedVariant {
+            variant,
+            content,
+            content_type,
+        });
+    }
+    Ok(variants)
+}
+
+#[derive(Debug)]
+pub enum ProcessingError {
+    Storage,
+    Image(ImageError),
+    DB(sqlx::Error),
+}
+
+/// Stores the variants of a pending image. The EXIF-stripped original
+/// overwrites the upload. Images that can't be decoded are marked `Failed`
+/// and never served.
+pub async fn process(attachment: &AttachmentModel) -> Result<(), ProcessingError> {
+    let storage = get_storage();
+    let repo = AttachmentsRepo::get_instance().await;
+    let upload = storage
+        .get(&attachment.storage_key(ImageVariant::Original))
+        .await
+        .map_err(|_| ProcessingError::Storage)?;
+    let variants = match web::block(move || generate_variants(&upload)).await {
+        Ok(Ok(variants)) => variants,
+        Ok(Err(err)) => {
+            repo.set_processed(attachment, ProcessingState::Failed, None)
+                .await
+                .map_err(ProcessingError::DB)?;
+            return Err(ProcessingError::Image(err));
+        }
+        Err(_) => return Err(ProcessingError::Storage),
+    };
+    let mut original = None;
+    for variant in variants {
+        let size = variant.content.len() as i64;
+        storage
+            .put(
+                &attachment.storage_key(variant.variant),
+                variant.content,
+                variant.content_type,
+            )
+            .await
+            .map_err(|_| ProcessingError::Storage)?;
+        if variant.variant == ImageVariant::Original {
+            original = Some((variant.content_type, size));
+        }
+    }
+    repo.set_processed(attachment, ProcessingState::Ready, original)
+        .await
+        .map_err(ProcessingError::DB)
+}
@@ -0,0 +1,410 @@
+use super::accounts;
+use crate::{
+    dto::{ImportCredentials, UserRegistrationDTO},
+    models::user::UserType,
+    repositories::users::UserRepo,
+    types::{Class, Subject},
+    utils::mailer::MailError,
+    validators::repository_query::users::{validate_registration, ValidationError},
+};
+use chrono::NaiveDate;
+use futures::{stream, StreamExt};
+use openssl::rand::rand_bytes;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, str::FromStr};
+
+/// 64 characters, so every random byte picks one without bias.
+const PASSWORD_ALPHABET: &[u8] =
+    b"ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz23456789-_+=!@#$";
+const PASSWORD_LENGTH: usize = 14;
+/// Invitations are mailed this many at a time rather than one by one.
+const CONCURRENT_INVITATIONS: usize = 8;
+
+/// A row of the CSV. Students have a `class` such as `7B`, teachers a
+/// `subject`, `phone` and `username` may be left out. Usernames that are
+/// left out are made from the email.
+#[derive(Deserialize)]
+struct ImportRow {
+    first_name: String,
+    last_name: String,
+    birth_date: NaiveDate,
+    #[serde(default)]
+    class: Option<String>,
+    #[serde(default)]
+    subject: Option<String>,
+    email: String,
+    #[serde(default)]
+    phone: Option<String>,
+    #[serde(default)]
+    username: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub enum ImportError {
+    Malformed(String),
+    NeitherClassNorSubject,
+    BothClassAndSubject,
+    InvalidClass,
+    InvalidSubject,
+    UsernameTaken,
+    EmailTaken,
+    Invalid(ValidationError),
+}
+
+#[derive(Debug, Serialize)]
+pub enum ImportFailure {
+    Unreadable(String),
+    /// No random bytes for the passwords.
+    ProblemsWithRandom,
+    ProblemsWithDB,
+}
+
+#[derive(Serialize)]
+pub enum Credentials {
+    /// Shown only in this report.
+    Password(String),
+    InvitationSent,
+    InvitationFailed(MailError),
+}
+
+#[derive(Serialize)]
+pub struct ImportedUser {
+    line: u64,
+    username: String,
+    /// `None` in a dry run.
+    credentials: Option<Credentials>,
+}
+
+#[derive(Serialize)]
+pub struct RejectedRow {
+    line: u64,
+    errors: Vec<ImportError>,
+}
+
+#[derive(Serialize)]
+pub struct ImportReport {
+    dry_run: bool,
+    created: Vec<ImportedUser>,
+    rejected: Vec<RejectedRow>,
+}
+
+fn generate_password() -> Result<String, ImportFailure> {
+    let mut bytes = [0; PASSWORD_LENGTH];
+    rand_bytes(&mut bytes).map_err(|_| ImportFailure::ProblemsWithRandom)?;
+    Ok(bytes
+        .iter()
+        .map(|byte| char::from(PASSWORD_ALPHABET[usize::from(byte >> 2)]))
+        .collect())
+}
+
+/// `7B`, `7-B` and `7 b` all are class 7B.
+fn parse_class(class: &str) -> Option<Class> {
+    let class: String = class
+        .chars()
+        .filter(|char| char.is_ascii_alphanumeric())
+        .collect();
+    let class_char = class.chars().last()?.to_ascii_uppercase();
+    let class_num = class[..class.len() - 1].parse().ok()?;
+    Class::from(class_char as u8, class_num).ok()
+}
+
+fn user_specs(row: &ImportRow) -> Result<UserType, ImportError> {
+    match (row.class.as_deref(), row.subject.as_deref()) {
+        (Some(class), None) => parse_class(class)
+            .map(|class| UserType::Student { class })
+            .ok_or(ImportError::InvalidClass),
+        (None, Some(subject)) => Subject::from_str(subject)
+            .map(|subject| UserType::Teacher { subject })
+            .map_err(|_| ImportError::InvalidSubject),
+        (Some(_), Some(_)) => Err(ImportError::BothClassAndSubject),
+        (None, None) => Err(ImportError::NeitherClassNorSubject),
+    }
+}
+
+/// The part of the email before `@` with everything but ASCII letters,
+/// digits, dots, dashes and underscores dropped.
+fn username_base(email: &str) -> String {
+    let base: String = email
+        .split('@')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|char| char.is_ascii_alphanumeric() || ".-_".contains(*char))
+        .take(48)
+        .collect::<String>()
+        .to_lowercase();
+    if base.is_empty() {
+        "user".to_string()
+    } else {
+        base
+    }
+}
+
+/// `username_base` of the email, numbered if it's taken.
+async fn free_username(email: &str, taken: &HashSet<String>, users: &UserRepo) -> String {
+    let base = username_base(email);
+    let mut username = base.clone();
+    let mut number = 1;
+    while taken.contains(&username) || !users.is_username_free(username.clone()).await {
+        number += 1;
+        username = format!("{base}{number}");
+    }
+    username
+}
+
+/// Turns a row into a registration, `taken_usernames` and `taken_emails`
+/// hold the ones of the rows before it, the emails in lowercase. Nothing is
+/// looked up before the row passes validation.
+async fn registration(
+    row: ImportRow,
+    password: String,
+    taken_usernames: &HashSet<String>,
+    taken_emails: &HashSet<String>,
+    users: &UserRepo,
+) -> Result<UserRegistrationDTO, Vec<ImportError>> {
+    let mut errors = Vec::new();
+    let user_specs = user_specs(&row).map_err(|err| errors.push(err)).ok();
+    let email = row.email.trim().to_string();
+    let chosen = row.username.is_some();
+    let username = match row.username {
+        Some(username) => username,
+        None => free_username(&email, taken_usernames, users).await,
+    };
+    let dto = UserRegistrationDTO {
+        username,
+        last_name: row.last_name,
+        first_name: row.first_name,
+        birth_date: row.birth_date,
+        user_specs: user_specs.unwrap_or(UserType::Other),
+        about: String::new(),
+        password,
+        email,
+        phone_number: row.phone,
+        invitation: None,
+    };
+    if let Err(validation_errors) = validate_registration(&dto) {
+        errors.extend(validation_errors.into_iter().map(ImportError::Invalid));
+        return Err(errors);
+    }
+    if taken_emails.contains(&dto.email.to_lowercase())
+        || users.get_by_email(&dto.email).await.is_some()
+    {
+        errors.push(ImportError::EmailTaken);
+    }
+    if chosen
+        && (taken_usernames.contains(&dto.username)
+            || !users.is_username_free(dto.username.clone()).await)
+    {
+        errors.push(ImportError::UsernameTaken);
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(dto)
+}
+
+/// Registers the students and teachers of a CSV with the header
+/// `first_name,last_name,birth_date,class,subject,email,phone,username`.
+/// Rows with errors are reported and skipped, the rest are registered
+/// together or not at all. A dry run only checks the rows.
+pub async fn import(
+    csv: &[u8],
+    dry_run: bool,
+    credentials: ImportCredentials,
+) -> Result<ImportReport, ImportFailure> {
+    let users = UserRepo::get_instance().await;
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(csv);
+    let headers = reader
+        .headers()
+        .map_err(|err| ImportFailure::Unreadable(err.to_string()))?
+        .clone();
+    let mut taken_usernames = HashSet::new();
+    let mut taken_emails = HashSet::new();
+    let mut accepted = Vec::new();
+    let mut rejected = Vec::new();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                rejected.push(RejectedRow {
+                    line: err.position().map_or(0, |position| position.line()),
+                    errors: vec![ImportError::Malformed(err.to_string())],
+                });
+                continue;
+            }
+        };
+        let line = record.position().map_or(0, |position| position.line());
+        let row = match record.deserialize::<ImportRow>(Some(&headers)) {
+            Ok(row) => row,
+            Err(err) => {
+                rejected.push(RejectedRow {
+                    line,
+                    errors: vec![ImportError::Malformed(err.to_string())],
+                });
+                continue;
+            }
+        };
+        let password = generate_password()?;
+        match registration(row, password, &taken_usernames, &taken_emails, &users).await {
+            Ok(dto) => {
+                taken_usernames.insert(dto.username.clone());
+                taken_emails.insert(dto.email.to_lowercase());
+                accepted.push((line, dto));
+            }
+            Err(errors) => rejected.push(RejectedRow { line, errors }),
+        }
+    }
+    if dry_run || accepted.is_empty() {
+        return Ok(ImportReport {
+            dry_run,
+            created: accepted
+                .into_iter()
+                .map(|(line, dto)| ImportedUser {
+                    line,
+                    username: dto.username,
+                    credentials: None,
+                })
+                .collect(),
+            rejected,
+        });
+    }
+    let dtos: Vec<UserRegistrationDTO> = accepted.iter().map(|(_, dto)| dto.clone()).collect();
+    let registered = users
+        .register_many(&dtos)
+        .await
+        .map_err(|_| ImportFailure::ProblemsWithDB)?;
+    let credentials: Vec<Credentials> = match credentials {
+        ImportCredentials::Passwords => accepted
+            .iter()
+            .map(|(_, dto)| Credentials::Password(dto.password.clone()))
+            .collect(),
+        ImportCredentials::Invitations => {
+            stream::iter(&registered)
+                .map(|user| async move {
+                    match accounts::send_invitation(user).await {
+                        Ok(_) => Credentials::InvitationSent,
+                        Err(accounts::AccountError::ProblemsWithMail(err)) => {
+                            Credentials::InvitationFailed(err)
+                        }
+                        Err(_) => Credentials::InvitationFailed(MailError::Unavailable),
+                    }
+                })
+                .buffered(CONCURRENT_INVITATIONS)
+                .collect()
+                .await
+        }
+    };
+    let created = accepted
+        .into_iter()
+        .zip(registered)
+        .zip(credentials)
+        .map(|(((line, _), user), credentials)| ImportedUser {
+            line,
+            username: user.username(),
+            credentials: Some(credentials),
+        })
+        .collect();
+    Ok(ImportReport {
+        dry_run,
+        created,
+        rejected,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(csv: &str) -> Vec<ImportRow> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(csv.as_bytes());
+        reader
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .expect("the rows are well formed")
+    }
+
+    #[test]
+    fn classes_are_read_loosely() {
+        for class in ["7B", "7-B", "7 b", "11a"] {
+            assert!(parse_class(class).is_some(), "{class}");
+        }
+        let class = parse_class("7-b").unwrap();
+        assert_eq!(
+            (class.class_num(), class.class_char()),
+            (7, "B".to_string())
+        );
+        for class in ["", "B", "7", "0A", "12A", "7BB"] {
+            assert!(parse_class(class).is_none(), "{class}");
+        }
+    }
+
+    #[test]
+    fn usernames_come_from_the_email() {
+        assert_eq!(username_base("Anna.Smith@school.uz"), "anna.smith");
+        assert_eq!(username_base("o'neil+news@school.uz"), "oneilnews");
+        assert_eq!(username_base("@school.uz"), "user");
+        assert_eq!(
+            username_base(&format!("{}@school.uz", "a".repeat(60))).len(),
+            48
+        );
+    }
+
+    #[test]
+    fn passwords_pass_validation() {
+        let password = generate_password().unwrap();
+        assert_eq!(password.len(), PASSWORD_LENGTH);
+        assert!(password
+            .bytes()
+            .all(|byte| PASSWORD_ALPHABET.contains(&byte)));
+    }
+
+    #[test]
+    fn valid_row_makes_a_valid_registration() {
+        let row = rows(
+            "first_name,last_name,birth_date,class,subject,email,phone,username\n\
+             Anna,Smith,2012-03-04,7B,,anna.smith@school.uz,998901234567,anna_s\n",
+        )
+        .remove(0);
+        let user_specs = user_specs(&row).unwrap();
+        assert!(user_specs.is_student());
+        let dto = UserRegistrationDTO {
+            username: row.username.unwrap(),
+            last_name: row.last_name,
+            first_name: row.first_name,
+            birth_date: row.birth_date,
+            user_specs,
+            about: String::new(),
+            password: generate_password().unwrap(),
+            email: row.email,
+            phone_number: row.phone,
+            invitation: None,
+        };
+        assert!(validate_registration(&dto).is_ok());
+    }
+
+    #[test]
+    fn rows_need_exactly_one_of_class_and_subject() {
+        let rows = rows(
+            "first_name,last_name,birth_date,class,subject,email\n\
+             Anna,Smith,2012-03-04,,,anna@school.uz\n\
+             Anna,Smith,2012-03-04,7B,Math,anna@school.uz\n\
+             Anna,Smith,2012-03-04,12A,,anna@school.uz\n",
+        );
+        assert!(matches!(
+            user_specs(&rows[0]),
+            Err(ImportError::NeitherClassNorSubject)
+        ));
+        assert!(matches!(
+            user_specs(&rows[1]),
+            Err(ImportError::BothClassAndSubject)
+        ));
+        assert!(matches!(
+            user_specs(&rows[2]),
+            Err(ImportError::InvalidClass)
+        ));
+    }
+}
@@ -0,0 +1,187 @@
+use super::accounts::{hash, hex};
+use crate::{
+    dto::{InvitationJSON, UserRegistrationDTO},
+    models::{invitation::InvitationModel, user::UserModel},
+    repositories::{
+        invitations::InvitationsRepo,
+        users::{RegistrationError, UserRepo},
+    },
+    utils::config,
+    validators::repository_query::users::{validate_registration, ValidationError},
+};
+use lazy_static::lazy_static;
+use openssl::rand::rand_bytes;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy)]
+pub enum RegistrationMode {
+    Open,
+    InviteOnly,
+    Closed,
+}
+
+lazy_static! {
+    /// Set with `REGISTRATION_MODE`: `open`, `invite_only` or `closed`.
+    static ref MODE: RegistrationMode =
+        config::parsed("REGISTRATION_MODE", RegistrationMode::Open, |mode| match mode {
+            "open" => Some(RegistrationMode::Open),
+            "invite_only" => Some(RegistrationMode::InviteOnly),
+            "closed" => Some(RegistrationMode::Closed),
+            _ => None,
+        });
+}
+
+/// Read at boot, so a wrong `REGISTRATION_MODE` stops the service before
+/// anyone signs up.
+pub fn registration_mode() -> RegistrationMode {
+    *MODE
+}
+
+#[derive(Debug, Serialize)]
+pub enum InvitationError {
+    InvalidMaxUses,
+    AlreadyExpired,
+    InvitationDoesntExist,
+    ProblemsWithRandom,
+    ProblemsWithDB,
+}
+
+impl From<sqlx::Error> for InvitationError {
+    fn from(_: sqlx::Error) -> Self {
+        InvitationError::ProblemsWithDB
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub enum SignUpError {
+    RegistrationClosed,
+    InvitationRequired,
+    InvalidInvitation,
+    UserSpecsNotAllowed,
+    Invalid(Vec<ValidationError>),
+    Registration(RegistrationError),
+}
+
+impl From<RegistrationError> for SignUpError {
+    fn from(err: RegistrationError) -> Self {
+        SignUpError::Registration(err)
+    }
+}
+
+impl From<sqlx::Error> for SignUpError {
+    fn from(_: sqlx::Error) -> Self {
+        SignUpError::Registration(RegistrationError::ProblemsWithDB)
+    }
+}
+
+/// The code is only in the response, just its hash is kept.
+#[derive(Serialize)]
+pub struct CreatedInvitation {
+    code: String,
+    invitation: InvitationModel,
+}
+
+/// Case and surrounding spaces don't matter.
+fn code_hash(code: &str) -> String {
+    hash(&code.trim().to_lowercase())
+}
+
+pub async fn create(
+    administrator: &UserModel,
+    invitation: InvitationJSON,
+) -> Result<CreatedInvitation, InvitationError> {
+    let max_uses = invitation.max_uses.unwrap_or(1);
+    if max_uses < 1 {
+        return Err(InvitationError::InvalidMaxUses);
+    }
+    if invitation.expires_at <= chrono::Utc::now().naive_utc() {
+        return Err(InvitationError::AlreadyExpired);
+    }
+    let mut bytes = [0; 12];
+    rand_bytes(&mut bytes).map_err(|_| InvitationError::ProblemsWithRandom)?;
+    let code = hex(&bytes);
+    let invitation = InvitationsRepo::get_instance()
+        .await
+        .create(
+            &code_hash(&code),
+            &administrator.username(),
+            invitation.user_specs.as_ref(),
+            max_uses,
+            invitation.expires_at,
+        )
+        .await?;
+    Ok(CreatedInvitation { code, invitation })
+}
+
+pub async fn revoke(uuid: &uuid::Uuid) -> Result<(), InvitationError> {
+    match InvitationsRepo::get_instance().await.revoke(uuid).await? {
+        0 => Err(InvitationError::InvitationDoesntExist),
+        _ => Ok(()),
+    }
+}
+
+/// The invitation the code belongs to, if it can still be used.
+pub async fn lookup(code: &str) -> Option<InvitationModel> {
+    InvitationsRepo::get_instance()
+        .await
+        .get_usable(&code_hash(code))
+        .await
+}
+
+/// Registers a user the way the registration mode allows, once the
+/// registration passes validation. With an invitation the user registers as
+/// the type it was made for. Administrators can only
+/// register with an invitation made for administrators, whatever the mode.
+pub async fn register(registration: UserRegistrationDTO) -> Result<UserModel, SignUpError> {
+    let invited = registration.invitation.is_some();
+    match *MODE {
+        RegistrationMode::Closed => return Err(SignUpError::RegistrationClosed),
+        RegistrationMode::InviteOnly if !invited => return Err(SignUpError::InvitationRequired),
+        _ => {}
+    }
+    if !invited && registration.user_specs.is_administrator() {
+        return Err(SignUpError::InvitationRequired);
+    }
+    validate_registration(&registration).map_err(SignUpError::Invalid)?;
+    let users = UserRepo::get_instance().await;
+    let Some(code) = registration.invitation.clone() else {
+        return Ok(users.register(registration).await?);
+    };
+    let code_hash = code_hash(&code);
+    let invitation = InvitationsRepo::get_instance()
+        .await
+        .get_usable(&code_hash)
+        .await
+        .ok_or(SignUpError::InvalidInvitation)?;
+    let allowed = match invitation.user_specs() {
+        Some(user_specs) => user_specs == registration.user_specs,
+        None => !registration.user_specs.is_administrator(),
+    };
+    if !allowed {
+        return Err(SignUpError::UserSpecsNotAllowed);
+    }
+    users
+        .register_invited(registration, &code_hash)
+        .await?
+        .ok_or(SignUpError::InvalidInvitation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_are_hashed_ignoring_case_and_spaces() {
+        let code = "3f9a0c17be42d5e6a8c1f0b2";
+        assert_eq!(code_hash(code), code_hash(" 3F9A0C17BE42D5E6A8C1F0B2\n"));
+        assert_ne!(code_hash(code), code_hash("3f9a0c17be42d5e6a8c1f0b3"));
+    }
+
+    #[test]
+    fn hashes_dont_contain_the_code() {
+        let code = "3f9a0c17be42d5e6a8c1f0b2";
+        let code_hash = code_hash(code);
+        assert_eq!(code_hash.len(), 64);
+        assert!(!code_hash.contains(code));
+    }
+}
@@ -0,0 +1,110 @@
+use super::notifications::notify;
+use crate::{
+    dto::NotificationDTO,
+    models::{mention::MentionSpan, notification::NotificationKind, post::PostModel},
+    repositories::{
+        comments::CommentsRepo, mentions::MentionsRepo, posts::PostsRepo, users::UserRepo,
+    },
+};
+use lazy_static::lazy_static;
+use regex::Regex;
+use uuid::Uuid;
+
+lazy_static! {
+    static ref MENTION: Regex =
+        Regex::new(r"(?:^|[^A-Za-z0-9_.@])(@[A-Za-z0-9_]+(?:[.-][A-Za-z0-9_]+)*)").unwrap();
+}
+
+/// Finds every `@username` in `content`, whether or not the user exists.
+pub fn parse(content: &str) -> Vec<MentionSpan> {
+    MENTION
+        .captures_iter(content)
+        .filter_map(|captures| captures.get(1))
+        .map(|mention| {
+            let start = content[..mention.start()].chars().count();
+            let end = start + mention.as_str().chars().count();
+            MentionSpan {
+                username: mention.as_str()[1..].to_string(),
+                start: start as i32,
+                end: end as i32,
+            }
+        })
+        .collect()
+}
+
+/// `false` while the resource or its post is hidden, held for premoderation
+/// or not published yet.
+async fn announced(resource: Uuid, post: Uuid) -> bool {
+    if resource != post {
+        match CommentsRepo::get_instance()
+            .await
+            .get_by_uuid(&resource)
+            .await
+        {
+            Some(comment) if !comment.is_hidden() && !comment.is_pending() => {}
+            _ => return false,
+        }
+    }
+    PostsRepo::get_instance()
+        .await
+        .get_by_uuid(post)
+        .await
+        .is_some_and(|post| post.status().is_published() && !post.is_hidden() && !post.is_pending())
+}
+
+/// Stores the mentions of existing users found in `content` and notifies the
+/// users who weren't mentioned by the previous version of the resource.
+/// Nothing is stored while the resource isn't announced, and only the users
+/// who can see the post are notified.
+pub async fn sync(resource: Uuid, post: Uuid, content: &str, author: &str) {
+    if !announced(resource, post).await {
+        return;
+    }
+    let users = UserRepo::get_instance().await;
+    let posts = PostsRepo::get_instance().await;
+    let mut mentions = Vec::new();
+    let mut readers = Vec::new();
+    for mention in parse(content) {
+        if let Some(user) = users.get_by_username(&mention.username).await {
+            if posts.get_visible(post, Some(user)).await.is_some() {
+                readers.push(mention.username.clone());
+            }
+            mentions.push(mention);
+        }
+    }
+    let repo = MentionsRepo::get_instance().await;
+    let previous = repo.get_spans(&resource).await;
+    if repo.replace(resource, post, &mentions).await.is_err() {
+        return;
+    }
+    let mut notified = Vec::new();
+    for mention in mentions {
+        if notified.contains(&mention.username)
+            || !readers.contains(&mention.username)
+            || previous
+                .iter()
+                .any(|previous| previous.username == mention.username)
+        {
+            continue;
+        }
+        notify(NotificationDTO {
+            recipient: mention.username.clone(),
+            kind: NotificationKind::Mention,
+            actor: Some(author.to_string()),
+            resource: Some(resource),
+            details: None,
+        })
+        .await;
+        notified.push(mention.username);
+    }
+}
+
+pub async fn sync_post(post: &PostModel) {
+    sync(
+        post.uuid(),
+        post.uuid(),
+        &post.content(),
+        &post.author().await.username(),
+    )
+    .await
+}
@@ -0,0 +1,16 @@
+pub mod accounts;
+pub mod attachments;
+pub mod images;
+pub mod imports;
+pub mod invitations;
+pub mod mentions;
+pub mod moderation;
+pub mod notifications;
+pub mod parents;
+pub mod premoderation;
+pub mod rate_limits;
+pub mod realtime;
+pub mod revisions;
+pub mod sign_in;
+pub mod suspensions;
+pub mod two_factor;
@@ -0,0 +1,205 @@
+use super::{
+    attachments,
+    notifications::notify,
+    suspensions::{self, SuspensionError},
+    two_factor,
+};
+use crate::{
+    dto::{ModerationJSON, NotificationDTO, ReportDTO, ReportJSON, ReportTarget, SuspendJSON},
+    models::{
+        notification::NotificationKind,
+        report::{ModerationAction, ModerationActionModel, ReportModel},
+        user::UserModel,
+    },
+    repositories::{
+        comments::CommentsRepo, moderation::ModerationRepo, posts::PostsRepo, users::UserRepo,
+    },
+};
+use serde::Serialize;
+use std::str::FromStr;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize)]
+pub enum ModerationError {
+    TargetDoesntExist,
+    AlreadyReported,
+    ActionNotApplicable,
+    ProblemsWithDB,
+}
+
+impl From<sqlx::Error> for ModerationError {
+    fn from(_: sqlx::Error) -> Self {
+        ModerationError::ProblemsWithDB
+    }
+}
+
+fn target_uuid(target: &ReportTarget) -> Option<Uuid> {
+    Uuid::from_str(&target.id()).ok()
+}
+
+/// Username of whoever is responsible for the target. With a `viewer` only
+/// targets they can see are found, so reports don't reveal drafts or hidden
+/// content.
+async fn author_of(
+    target: &ReportTarget,
+    viewer: Option<&UserModel>,
+) -> Result<String, ModerationError> {
+    let posts = PostsRepo::get_instance().await;
+    let author = match target {
+        ReportTarget::Post(_) => match (target_uuid(target), viewer) {
+            (Some(uuid), Some(viewer)) => posts
+                .get_visible(uuid, Some(viewer.clone()))
+                .await
+                .map(|post| post.author_username()),
+            (Some(uuid), None) => posts
+                .get_by_uuid(uuid)
+                .await
+                .map(|post| post.author_username()),
+            (None, _) => None,
+        },
+        ReportTarget::Comment(_) => {
+            let comment = match target_uuid(target) {
+                Some(uuid) => CommentsRepo::get_instance().await.get_by_uuid(&uuid).await,
+                None => None,
+            };
+            match (comment, viewer) {
+                (Some(comment), Some(viewer)) => {
+                    let moderates = viewer.username() == comment.author_username()
+                        || two_factor::acts_as_administrator(viewer).await;
+                    let visible = (moderates || !(comment.is_hidden() || comment.is_pending()))
+                        && posts
+                            .get_visible(comment.under_post().uuid(), Some(viewer.clone()))
+                            .await
+                            .is_some();
+                    visible.then(|| comment.author_username())
+                }
+                (Some(comment), None) => Some(comment.author_username()),
+                (None, _) => None,
+            }
+        }
+        ReportTarget::Profile(username) => UserRepo::get_instance()
+            .await
+            .get_by_username(username)
+            .await
+            .map(|user| user.username()),
+    };
+    author.ok_or(ModerationError::TargetDoesntExist)
+}
+
+pub async fn report(
+    reporter: &UserModel,
+    report: ReportJSON,
+) -> Result<ReportModel, ModerationError> {
+    author_of(&report.target, Some(reporter)).await?;
+    ModerationRepo::get_instance()
+        .await
+        .report(ReportDTO {
+            reporter: Some(reporter.username()),
+            target: report.target,
+            reason: report.reason,
+            details: report.details,
+        })
+        .await?
+        .ok_or(ModerationError::AlreadyReported)
+}
+
+/// Content actions only apply to posts and comments.
+fn check_applicable(
+    target: &ReportTarget,
+    action: ModerationAction,
+) -> Result<(), ModerationError> {
+    match (target, action) {
+        (
+            ReportTarget::Profile(_),
+            ModerationAction::Hide | ModerationAction::Restore | ModerationAction::Delete,
+        ) => Err(ModerationError::ActionNotApplicable),
+        _ => Ok(()),
+    }
+}
+
+/// Files of a deleted post are removed once the deletion is committed.
+async fn remove_attachments(target: &ReportTarget) {
+    let Some(uuid) = target_uuid(target) else {
+        return;
+    };
+    if let ReportTarget::Post(_) = target {
+        if let Some(post) = PostsRepo::get_instance().await.get_by_uuid(uuid).await {
+            for attachment in post.attachments() {
+                let _ = attachments::remove(&attachment).await;
+            }
+        }
+    }
+}
+
+/// Applies the action and closes the open reports of the target, then lets
+/// the reporters and, unless dismissed, the author know.
+pub async fn resolve(
+    moderator: &UserModel,
+    target: ReportTarget,
+    decision: ModerationJSON,
+) -> Result<ModerationActionModel, ModerationError> {
+    let author = author_of(&target, None).await?;
+    check_applicable(&target, decision.action)?;
+    let suspension = if decision.action == ModerationAction::Suspend {
+        suspensions::ensure_suspendable(&author)
+            .await
+            .map_err(|err| match err {
+                SuspensionError::ProblemsWithDB => ModerationError::ProblemsWithDB,
+                _ => ModerationError::ActionNotApplicable,
+            })?;
+        Some(SuspendJSON {
+            username: author.clone(),
+            reason: decision
+                .note
+                .clone()
+                .unwrap_or("reported content".to_string()),
+            until: decision.until,
+        })
+    } else {
+        None
+    };
+    let (action, reporters) = ModerationRepo::get_instance()
+        .await
+        .resolve(
+            &target,
+            decision.action,
+            &moderator.username(),
+            decision.note.clone(),
+            suspension.as_ref(),
+        )
+        .await?;
+    if let Some(suspension) = suspension {
+        suspensions::announce(&suspension.username, &suspension.reason, suspension.until).await;
+    }
+    if decision.action == ModerationAction::Delete {
+        remove_attachments(&target).await;
+    }
+    let resource = target_uuid(&target);
+    for reporter in reporters {
+        notify(NotificationDTO {
+            recipient: reporter,
+            kind: NotificationKind::Moderation,
+            actor: None,
+            resource,
+            details: Some(format!("report resolved: {:?}", decision.action)),
+        })
+        .await;
+    }
+    if !matches!(
+        decision.action,
+        ModerationAction::Dismiss | ModerationAction::Suspend
+    ) {
+        notify(NotificationDTO {
+            recipient: author,
+            kind: NotificationKind::Moderation,
+            actor: None,
+            resource,
+            details: Some(match decision.note {
+                Some(note) => format!("{:?}: {}", decision.action, note),
+                None => format!("{:?}", decision.action),
+            }),
+        })
+        .await;
+    }
+    Ok(action)
+}
@@ -0,0 +1,46 @@
+use super::realtime::{self, Channel, RealtimeEventKind};
+use crate::{
+    dto::NotificationDTO,
+    models::{comment::CommentModel, notification::NotificationKind},
+    prelude::{Resource, ResourceKind},
+    repositories::notifications::NotificationsRepo,
+};
+
+/// Stores a notification for its recipient unless the recipient caused the
+/// event or turned notifications of this kind off.
+pub async fn notify(notification: NotificationDTO) {
+    if notification.actor.as_deref() == Some(notification.recipient.as_str()) {
+        return;
+    }
+    let repo = NotificationsRepo::get_instance().await;
+    if !repo
+        .is_enabled(&notification.recipient, notification.kind)
+        .await
+    {
+        return;
+    }
+    if let Ok(notification) = repo.create(notification).await {
+        realtime::publish(
+            Channel::Notifications(notification.recipient()),
+            RealtimeEventKind::Notification,
+            &notification,
+        )
+        .await;
+    }
+}
+
+/// Lets the author of `commented` know about the comment, a reply when it
+/// was a comment.
+pub async fn commented(comment: &CommentModel, commented: &dyn Resource) {
+    notify(NotificationDTO {
+        recipient: commented.author().username(),
+        kind: match commented.kind() {
+            ResourceKind::Post => NotificationKind::CommentOnPost,
+            ResourceKind::Comment => NotificationKind::Reply,
+        },
+        actor: Some(comment.author_username()),
+        resource: Some(commented.uuid()),
+        details: None,
+    })
+    .await;
+}
@@ -0,0 +1,166 @@
+use super::{accounts::PUBLIC_URL, notifications::notify, two_factor};
+use crate::{
+    dto::NotificationDTO,
+    models::{notification::NotificationKind, user::UserModel},
+    repositories::{
+        notifications::NotificationsRepo, parent_links::ParentLinksRepo, posts::PostsRepo,
+        users::UserRepo,
+    },
+    utils::mailer::{get_mailer, Mail, MailError, Mailer},
+};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+/// Most posts a single digest lists.
+const DIGEST_POSTS: u32 = 50;
+
+#[derive(Debug, Serialize)]
+pub enum ParentLinkError {
+    NotParent,
+    NotStudent,
+    UserDoesntExist,
+    AlreadyRequested,
+    LinkDoesntExist,
+    NotAllowed,
+    ProblemsWithDB,
+}
+
+impl From<sqlx::Error> for ParentLinkError {
+    fn from(_: sqlx::Error) -> Self {
+        ParentLinkError::ProblemsWithDB
+    }
+}
+
+/// The link stays unconfirmed until the student or an administrator
+/// confirms it, the student is asked to.
+pub async fn request_link(parent: &UserModel, student: &str) -> Result<(), ParentLinkError> {
+    if !parent.user_specs().is_parent() {
+        return Err(ParentLinkError::NotParent);
+    }
+    let student = UserRepo::get_instance()
+        .await
+        .get_by_username(student)
+        .await
+        .ok_or(ParentLinkError::UserDoesntExist)?;
+    if !student.user_specs().is_student() {
+        return Err(ParentLinkError::NotStudent);
+    }
+    if !ParentLinksRepo::get_instance()
+        .await
+        .request(&parent.username(), &student.username())
+        .await?
+    {
+        return Err(ParentLinkError::AlreadyRequested);
+    }
+    notify(NotificationDTO {
+        recipient: student.username(),
+        kind: NotificationKind::ParentLink,
+        actor: Some(parent.username()),
+        resource: None,
+        details: Some("asked to be linked to you as a parent".to_string()),
+    })
+    .await;
+    Ok(())
+}
+
+pub async fn confirm_link(
+    confirmer: &UserModel,
+    parent: &str,
+    student: &str,
+) -> Result<(), ParentLinkError> {
+    if confirmer.username() != student && !two_factor::acts_as_administrator(confirmer).await {
+        return Err(ParentLinkError::NotAllowed);
+    }
+    if !ParentLinksRepo::get_instance()
+        .await
+        .confirm(parent, student, &confirmer.username())
+        .await?
+    {
+        return Err(ParentLinkError::LinkDoesntExist);
+    }
+    notify(NotificationDTO {
+        recipient: parent.to_string(),
+        kind: NotificationKind::ParentLink,
+        actor: Some(confirmer.username()),
+        resource: None,
+        details: Some(format!("confirmed your link to {}", student)),
+    })
+    .await;
+    Ok(())
+}
+
+/// Either side of the link may remove it, and so may administrators.
+pub async fn remove_link(
+    user: &UserModel,
+    parent: &str,
+    student: &str,
+) -> Result<(), ParentLinkError> {
+    let username = user.username();
+    if username != parent && username != student && !two_factor::acts_as_administrator(user).await {
+        return Err(ParentLinkError::NotAllowed);
+    }
+    match ParentLinksRepo::get_instance()
+        .await
+        .remove(parent, student)
+        .await?
+    {
+        0 => Err(ParentLinkError::LinkDoesntExist),
+        _ => Ok(()),
+    }
+}
+
+/// Mails the parent the posts published since `since` that target the
+/// classes of their children. Returns how many posts were listed, nothing is
+/// sent when there are none or the parent turned digests off. A digest lists
+/// at most `DIGEST_POSTS` and says so when there were more.
+pub async fn send_digest(parent: &UserModel, since: NaiveDateTime) -> Result<usize, MailError> {
+    if !NotificationsRepo::get_instance()
+        .await
+        .is_enabled(&parent.username(), NotificationKind::Digest)
+        .await
+    {
+        return Ok(0);
+    }
+    // One more than listed, to tell whether some were left out.
+    let mut posts = PostsRepo::get_instance()
+        .await
+        .get_for_children(parent, since, DIGEST_POSTS + 1)
+        .await;
+    let capped = posts.len() > DIGEST_POSTS as usize;
+    posts.truncate(DIGEST_POSTS as usize);
+    if posts.is_empty() {
+        return Ok(0);
+    }
+    let list = posts
+        .iter()
+        .map(|post| {
+            format!(
+                "- {}\n  {}/posts/{}",
+                post.title(),
+                *PUBLIC_URL,
+                post.uuid()
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    get_mailer()
+        .send(&Mail {
+            to: parent.email(),
+            subject: "News for your children's classes".to_string(),
+            body: format!(
+                "Hello, {}!\n\nHere is what was posted for your children's classes lately:\n\n{}{}",
+                parent.first_name(),
+                list,
+                if capped {
+                    format!(
+                        "\n\nOnly the latest {} posts are listed, there were more.",
+                        DIGEST_POSTS
+                    )
+                } else {
+                    String::new()
+                }
+            ),
+        })
+        .await?;
+    Ok(posts.len())
+}
@@ -0,0 +1,162 @@
+use super::{
+    attachments, mentions,
+    notifications::{self, notify},
+    realtime::{self, post_published},
+};
+use crate::{
+    dto::{NotificationDTO, PremoderationJSON, ReportTarget},
+    models::{
+        comment::CommentModel, notification::NotificationKind, post::PostModel, user::UserModel,
+    },
+    prelude::Resource,
+    repositories::{comments::CommentsRepo, moderation::ModerationRepo, posts::PostsRepo},
+    types::Limit,
+};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize)]
+pub enum PremoderationError {
+    ItemDoesntExist,
+    NotPending,
+    OwnItem,
+    ProblemsWithDB,
+}
+
+impl From<sqlx::Error> for PremoderationError {
+    fn from(_: sqlx::Error) -> Self {
+        PremoderationError::ProblemsWithDB
+    }
+}
+
+#[derive(Serialize)]
+pub struct PendingItems {
+    posts: Vec<PostModel>,
+    comments: Vec<CommentModel>,
+}
+
+/// Posts and comments waiting for approval that `viewer` is in the audience
+/// of, only the ones of `author` if it's given.
+pub async fn pending(viewer: &UserModel, author: Option<String>, limit: Limit) -> PendingItems {
+    let posts = PostsRepo::get_instance().await;
+    let mut comments = Vec::new();
+    for comment in CommentsRepo::get_instance()
+        .await
+        .get_pending(author.clone())
+        .await
+    {
+        if posts
+            .is_in_audience(comment.under_post().uuid(), viewer)
+            .await
+        {
+            comments.push(comment);
+        }
+    }
+    PendingItems {
+        posts: posts.get_pending(viewer, author, limit).await,
+        comments,
+    }
+}
+
+/// Approved items are announced as if they were published just now.
+/// Rejected ones are deleted and the rejection is recorded as a moderation
+/// action, so it counts as a strike against the author.
+pub async fn decide(
+    moderator: &UserModel,
+    item: Uuid,
+    decision: PremoderationJSON,
+) -> Result<(), PremoderationError> {
+    let posts = PostsRepo::get_instance().await;
+    let comments = CommentsRepo::get_instance().await;
+    let moderation = ModerationRepo::get_instance().await;
+    let author = if let Some(post) = posts.get_by_uuid(item).await {
+        if !posts.is_in_audience(post.uuid(), moderator).await {
+            return Err(PremoderationError::ItemDoesntExist);
+        }
+        if post.author_username() == moderator.username() {
+            return Err(PremoderationError::OwnItem);
+        }
+        if !post.is_pending() {
+            return Err(PremoderationError::NotPending);
+        }
+        moderation
+            .settle_pending(
+                &ReportTarget::Post(item.to_string()),
+                decision.approve,
+                &moderator.username(),
+                decision.note.clone(),
+            )
+            .await?;
+        if !decision.approve {
+            for attachment in post.attachments() {
+                let _ = attachments::remove(&attachment).await;
+            }
+        } else if post.status().is_published() && !post.is_hidden() {
+            mentions::sync_post(&post).await;
+            post_published(&post).await;
+        }
+        post.author_username()
+    } else if let Some(comment) = comments.get_by_uuid(&item).await {
+        if !posts
+            .is_in_audience(comment.under_post().uuid(), moderator)
+            .await
+        {
+            return Err(PremoderationError::ItemDoesntExist);
+        }
+        if comment.author_username() == moderator.username() {
+            return Err(PremoderationError::OwnItem);
+        }
+        if !comment.is_pending() {
+            return Err(PremoderationError::NotPending);
+        }
+        moderation
+            .settle_pending(
+                &ReportTarget::Comment(item.to_string()),
+                decision.approve,
+                &moderator.username(),
+                decision.note.clone(),
+            )
+            .await?;
+        if decision.approve && !comment.is_hidden() {
+            announce_comment(&comment).await;
+        }
+        comment.author_username()
+    } else {
+        return Err(PremoderationError::ItemDoesntExist);
+    };
+    notify(NotificationDTO {
+        recipient: author,
+        kind: NotificationKind::Moderation,
+        actor: Some(moderator.username()),
+        resource: Some(item),
+        details: Some(match (decision.approve, decision.note) {
+            (true, _) => "approved".to_string(),
+            (false, Some(note)) => format!("rejected: {}", note),
+            (false, None) => "rejected".to_string(),
+        }),
+    })
+    .await;
+    Ok(())
+}
+
+/// Does what commenting does for a comment that didn't need approval.
+async fn announce_comment(comment: &CommentModel) {
+    let post = comment.under_post();
+    mentions::sync(
+        comment.uuid(),
+        post.uuid(),
+        &comment.contet(),
+        &comment.author_username(),
+    )
+    .await;
+    let replied = match comment.replys_for() {
+        Some(uuid) => CommentsRepo::get_instance().await.get_by_uuid(&uuid).await,
+        None => None,
+    };
+    let commented: &dyn Resource = match &replied {
+        Some(replied) => replied,
+        None => &post,
+    };
+    realtime::comment_published(comment, commented).await;
+    notifications::commented(comment, commented).await;
+}
@@ -0,0 +1,161 @@
+use crate::{
+    models::user::{UserModel, UserType},
+    utils::{
+        config,
+        logger::{console_logger::ConsoleLogger, Logger},
+        rate_limit::{get_rate_store, RateStore},
+    },
+};
+use serde::Serialize;
+
+/// Actions limited separately from each other.
+#[derive(Clone, Copy)]
+pub enum Bucket {
+    Publish,
+    Comment,
+    Mark,
+    Login,
+}
+
+impl Bucket {
+    fn name(&self) -> &'static str {
+        match self {
+            Bucket::Publish => "publish",
+            Bucket::Comment => "comment",
+            Bucket::Mark => "mark",
+            Bucket::Login => "login",
+        }
+    }
+
+    /// Hits per window in seconds for one user and for one IP address.
+    fn defaults(&self) -> ((u32, u64), (u32, u64)) {
+        match self {
+            Bucket::Publish => ((10, 3600), (30, 3600)),
+            Bucket::Comment => ((20, 600), (60, 600)),
+            Bucket::Mark => ((120, 60), (300, 60)),
+            Bucket::Login => ((10, 300), (30, 300)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RateLimited {
+    pub retry_after: u64,
+}
+
+/// Reads a `hits/seconds` quota such as `RATE_LIMIT_COMMENT_STUDENT=10/600`.
+fn quota(key: &str, default: (u32, u64)) -> (u32, u64) {
+    config::parsed(key, default, |value| {
+        let (hits, window) = value.split_once('/')?;
+        Some((hits.trim().parse().ok()?, window.trim().parse().ok()?))
+    })
+}
+
+async fn hit(key: String, (limit, window): (u32, u64)) -> Result<(), RateLimited> {
+    match get_rate_store().hit(&key, window).await {
+        Ok((hits, _)) if hits <= limit => Ok(()),
+        Ok((_, retry_after)) => Err(RateLimited { retry_after }),
+        // A broken store shouldn't take the whole service down with it.
+        Err(err) => {
+            ConsoleLogger::new().error(&format!("can't count {}. {:?}", key, err));
+            Ok(())
+        }
+    }
+}
+
+/// Keys and quotas the action is counted under, one for the account and one
+/// for the IP address. Accounts nobody registered get the default quota so
+/// the limits don't tell which usernames exist.
+fn limits(
+    bucket: Bucket,
+    username: Option<&str>,
+    user_type: Option<&UserType>,
+    ip: Option<&str>,
+) -> Vec<(String, (u32, u64))> {
+    let (user_default, ip_default) = bucket.defaults();
+    let prefix = format!("RATE_LIMIT_{}", bucket.name().to_uppercase());
+    let mut limits = Vec::new();
+    if let Some(username) = username {
+        let user_quota = match user_type {
+            Some(user_type) => quota(
+                &format!("{}_{}", prefix, user_type.type_name().to_uppercase()),
+                user_default,
+            ),
+            None => user_default,
+        };
+        limits.push((format!("{}:user:{}", bucket.name(), username), user_quota));
+    }
+    if let Some(ip) = ip {
+        limits.push((
+            format!("{}:ip:{}", bucket.name(), ip),
+            quota(&format!("{}_IP", prefix), ip_default),
+        ));
+    }
+    limits
+}
+
+/// Longest wait among the limits that are hit.
+fn longest(results: Vec<Result<(), RateLimited>>) -> Result<(), RateLimited> {
+    results
+        .into_iter()
+        .filter_map(Result::err)
+        .max_by_key(|limited| limited.retry_after)
+        .map_or(Ok(()), Err)
+}
+
+/// Counts the action against both the user and the IP address it comes
+/// from. Quotas are set in .env as `RATE_LIMIT_<BUCKET>_<USER TYPE>` and
+/// `RATE_LIMIT_<BUCKET>_IP`, e.g. `RATE_LIMIT_PUBLISH_STUDENT=5/3600`.
+pub async fn check(
+    bucket: Bucket,
+    user: Option<&UserModel>,
+    ip: Option<&str>,
+) -> Result<(), RateLimited> {
+    let user_type = user.map(|user| user.user_specs());
+    let mut results = Vec::new();
+    for (key, quota) in limits(
+        bucket,
+        user.map(|user| user.username()).as_deref(),
+        user_type.as_ref(),
+        ip,
+    ) {
+        results.push(hit(key, quota).await);
+    }
+    longest(results)
+}
+
+/// Counts a sign-in attempt with `username`, whether or not it's registered.
+pub async fn count_login(
+    username: &str,
+    user: Option<&UserModel>,
+    ip: Option<&str>,
+) -> Result<(), RateLimited> {
+    let user_type = user.map(|user| user.user_specs());
+    let mut results = Vec::new();
+    for (key, quota) in limits(Bucket::Login, Some(username), user_type.as_ref(), ip) {
+        results.push(hit(key, quota).await);
+    }
+    longest(results)
+}
+
+/// Whether sign-in attempts with `username` are over the quota already,
+/// without counting this one.
+pub async fn login_limited(
+    username: &str,
+    user: Option<&UserModel>,
+    ip: Option<&str>,
+) -> Result<(), RateLimited> {
+    let user_type = user.map(|user| user.user_specs());
+    let mut results = Vec::new();
+    for (key, (limit, _)) in limits(Bucket::Login, Some(username), user_type.as_ref(), ip) {
+        results.push(match get_rate_store().peek(&key).await {
+            Ok((hits, retry_after)) if hits >= limit => Err(RateLimited { retry_after }),
+            Ok(_) => Ok(()),
+            Err(err) => {
+                ConsoleLogger::new().error(&format!("can't read {}. {:?}", key, err));
+                Ok(())
+            }
+        });
+    }
+    longest(results)
+}
@@ -0,0 +1,171 @@
+use crate::{
+    models::{comment::CommentModel, post::PostModel},
+    prelude::Resource,
+    repositories::posts::PostsRepo,
+    utils::logger::{console_logger::ConsoleLogger, Logger},
+    REDIS_ADDRES,
+};
+use actix_web::rt::{self, time};
+use futures::{
+    channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
+    StreamExt,
+};
+use lazy_static::lazy_static;
+use redis::AsyncCommands;
+use serde::Serialize;
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+use uuid::Uuid;
+
+const CHANNEL_PREFIX: &str = "news_service:";
+const RECONNECT_SECS: u64 = 5;
+
+lazy_static! {
+    static ref REDIS_CLIENT: redis::Client =
+        redis::Client::open(REDIS_ADDRES.as_str()).expect("REDIS_ADDRES must be a redis url");
+    static ref SUBSCRIBERS: Mutex<HashMap<String, Vec<UnboundedSender<String>>>> =
+        Mutex::new(HashMap::new());
+}
+
+pub enum Channel {
+    PostComments(Uuid),
+    Notifications(String),
+    Tag(String),
+}
+
+impl Channel {
+    fn name(&self) -> String {
+        match self {
+            Channel::PostComments(post) => format!("{}post:{}", CHANNEL_PREFIX, post),
+            Channel::Notifications(username) => {
+                format!("{}notifications:{}", CHANNEL_PREFIX, username)
+            }
+            Channel::Tag(tag) => format!("{}tag:{}", CHANNEL_PREFIX, tag),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub enum RealtimeEventKind {
+    NewPost,
+    NewComment,
+    NewMark,
+    Notification,
+}
+
+#[derive(Serialize)]
+pub struct CommentEvent {
+    pub post: String,
+    pub commented: String,
+    pub author: String,
+    pub content: String,
+}
+
+#[derive(Serialize)]
+pub struct MarkEvent {
+    pub marked: String,
+    pub username: String,
+    pub liked: bool,
+}
+
+#[derive(Serialize)]
+struct RealtimeEvent<'a, T: Serialize> {
+    channel: String,
+    kind: RealtimeEventKind,
+    payload: &'a T,
+}
+
+/// Sends the event to Redis, every instance listening on it forwards the
+/// event to its own subscribers of the channel.
+pub async fn publish<T: Serialize>(channel: Channel, kind: RealtimeEventKind, payload: &T) {
+    let channel = channel.name();
+    let event = serde_json::to_string(&RealtimeEvent {
+        channel: channel.clone(),
+        kind,
+        payload,
+    })
+    .unwrap();
+    let published = match REDIS_CLIENT.get_multiplexed_async_connection().await {
+        Ok(mut connection) => connection.publish::<_, _, ()>(&channel, event).await,
+        Err(err) => Err(err),
+    };
+    if let Err(err) = published {
+        ConsoleLogger::new().error(&format!("can't publish to {}. {}", channel, err));
+    }
+}
+
+/// Tag channels are open to anyone, so only posts everyone can see are sent
+/// to them.
+pub async fn post_published(post: &PostModel) {
+    if PostsRepo::get_instance()
+        .await
+        .get_visible(post.uuid(), None)
+        .await
+        .is_none()
+    {
+        return;
+    }
+    for tag in post.tags() {
+        publish(Channel::Tag(tag), RealtimeEventKind::NewPost, post).await;
+    }
+}
+
+pub async fn comment_published(comment: &CommentModel, commented: &dyn Resource) {
+    let post = comment.under_post().uuid();
+    publish(
+        Channel::PostComments(post),
+        RealtimeEventKind::NewComment,
+        &CommentEvent {
+            post: post.to_string(),
+            commented: commented.uuid().to_string(),
+            author: comment.author_username(),
+            content: comment.contet(),
+        },
+    )
+    .await;
+}
+
+/// Returns a stream of serialized events published to any of `channels`.
+/// Dropping the receiver unsubscribes it on the next event.
+pub fn subscribe(channels: &[Channel]) -> UnboundedReceiver<String> {
+    let (sender, receiver) = unbounded();
+    let mut subscribers = SUBSCRIBERS.lock().unwrap();
+    for channel in channels {
+        subscribers
+            .entry(channel.name())
+            .or_default()
+            .push(sender.clone());
+    }
+    receiver
+}
+
+fn dispatch(channel: &str, event: String) {
+    let mut subscribers = SUBSCRIBERS.lock().unwrap();
+    if let Some(senders) = subscribers.get_mut(channel) {
+        senders.retain(|sender| sender.unbounded_send(event.clone()).is_ok());
+        if senders.is_empty() {
+            subscribers.remove(channel);
+        }
+    }
+}
+
+async fn listen() -> redis::RedisResult<()> {
+    let mut pubsub = REDIS_CLIENT.get_async_connection().await?.into_pubsub();
+    pubsub.psubscribe(format!("{}*", CHANNEL_PREFIX)).await?;
+    let mut messages = pubsub.on_message();
+    while let Some(message) = messages.next().await {
+        dispatch(message.get_channel_name(), message.get_payload()?);
+    }
+    Ok(())
+}
+
+pub fn start() {
+    rt::spawn(async {
+        let logger = ConsoleLogger::new();
+        loop {
+            if let Err(err) = listen().await {
+                logger.error(&format!("lost connection to redis. {}", err));
+            }
+            time::sleep(Duration::from_secs(RECONNECT_SECS)).await;
+        }
+    });
+}
@@ -0,0 +1,73 @@
+use crate::models::revision::RevisionModel;
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub enum DiffMode {
+    #[default]
+    Lines,
+    Words,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ChangeKind {
+    Unchanged,
+    Inserted,
+    Deleted,
+}
+
+impl From<ChangeTag> for ChangeKind {
+    fn from(tag: ChangeTag) -> Self {
+        match tag {
+            ChangeTag::Equal => ChangeKind::Unchanged,
+            ChangeTag::Insert => ChangeKind::Inserted,
+            ChangeTag::Delete => ChangeKind::Deleted,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevisionDiff {
+    from: i32,
+    to: i32,
+    title: Option<Vec<Change>>,
+    content: Vec<Change>,
+}
+
+/// Neighbouring changes of the same kind are merged into one.
+fn diff_text(old: &str, new: &str, mode: DiffMode) -> Vec<Change> {
+    let diff = match mode {
+        DiffMode::Lines => TextDiff::from_lines(old, new),
+        DiffMode::Words => TextDiff::from_words(old, new),
+    };
+    let mut changes: Vec<Change> = Vec::new();
+    for change in diff.iter_all_changes() {
+        let kind = ChangeKind::from(change.tag());
+        match changes.last_mut() {
+            Some(last) if last.kind == kind => last.value.push_str(change.value()),
+            _ => changes.push(Change {
+                kind,
+                value: change.value().to_string(),
+            }),
+        }
+    }
+    changes
+}
+
+pub fn diff(from: &RevisionModel, to: &RevisionModel, mode: DiffMode) -> RevisionDiff {
+    RevisionDiff {
+        from: from.number(),
+        to: to.number(),
+        title: match (from.title(), to.title()) {
+            (Some(old), Some(new)) => Some(diff_text(&old, &new, DiffMode::Words)),
+            _ => None,
+        },
+        content: diff_text(&from.content(), &to.content(), mode),
+    }
+}
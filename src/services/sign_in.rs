@@ -0,0 +1,70 @@
+use crate::{
+    models::sign_in_attempt::SignInAttemptModel,
+    repositories::sign_in_attempts::SignInAttemptsRepo, types::Limit, utils::config::setting,
+};
+use lazy_static::lazy_static;
+
+/// Failures older than this are forgotten.
+const WINDOW_SECS: i64 = 24 * 60 * 60;
+/// An address signs in to many accounts, it gets this many times more tries.
+const IP_FACTOR: i64 = 5;
+
+lazy_static! {
+    static ref FREE_ATTEMPTS: i64 = setting("SIGN_IN_FREE_ATTEMPTS", 3);
+    static ref LOCKOUT_AFTER: i64 = setting("SIGN_IN_LOCKOUT_AFTER", 10);
+    static ref LOCKOUT_SECS: i64 = setting("SIGN_IN_LOCKOUT_SECS", 900);
+}
+
+/// Seconds to wait after the latest of `failures` failed attempts. The wait
+/// doubles with every failure past the free ones and turns into a lockout
+/// once there are too many.
+fn delay(failures: i64, factor: i64) -> i64 {
+    let free = *FREE_ATTEMPTS * factor;
+    if failures >= *LOCKOUT_AFTER * factor {
+        *LOCKOUT_SECS
+    } else if failures >= free {
+        (1_i64 << (failures - free).min(20)).min(*LOCKOUT_SECS)
+    } else {
+        0
+    }
+}
+
+fn remaining((failures, last_failure): (i64, Option<chrono::NaiveDateTime>), factor: i64) -> i64 {
+    match last_failure {
+        Some(last_failure) => {
+            let wait_until = last_failure + chrono::Duration::seconds(delay(failures, factor));
+            (wait_until - chrono::Utc::now().naive_utc()).num_seconds()
+        }
+        None => 0,
+    }
+}
+
+/// Seconds before `username` may be tried again from `ip`, `None` if it may
+/// be tried now. Unknown usernames are throttled the same way, so the wait
+/// doesn't tell whether an account exists.
+pub async fn retry_after(username: &str, ip: Option<&str>) -> Option<u64> {
+    let repo = SignInAttemptsRepo::get_instance().await;
+    let since = chrono::Utc::now().naive_utc() - chrono::Duration::seconds(WINDOW_SECS);
+    let by_account = remaining(repo.account_failures(username, since).await, 1);
+    let by_ip = match ip {
+        Some(ip) => remaining(repo.ip_failures(ip, since).await, IP_FACTOR),
+        None => 0,
+    };
+    let wait = by_account.max(by_ip);
+    (wait > 0).then_some(wait as u64)
+}
+
+pub async fn record(username: &str, ip: Option<&str>, succeeded: bool) {
+    let _ = SignInAttemptsRepo::get_instance()
+        .await
+        .record(username, ip, succeeded)
+        .await;
+}
+
+/// Sign-ins to the account and every failed attempt on it, the latest first.
+pub async fn security_log(username: &str, limit: Limit) -> Vec<SignInAttemptModel> {
+    SignInAttemptsRepo::get_instance()
+        .await
+        .get_for(username, limit)
+        .await
+}
@@ -0,0 +1,125 @@
+use super::notifications::notify;
+use crate::{
+    dto::NotificationDTO,
+    models::{
+        notification::NotificationKind,
+        suspension::{AppealStatus, SuspensionModel},
+        user::UserModel,
+    },
+    repositories::{suspensions::SuspensionsRepo, users::UserRepo},
+};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub enum SuspensionError {
+    UserDoesntExist,
+    SuspendsAdministrator,
+    SuspensionDoesntExist,
+    NotSuspended,
+    AlreadyAppealed,
+    AppealDoesntExist,
+    ProblemsWithDB,
+}
+
+impl From<sqlx::Error> for SuspensionError {
+    fn from(_: sqlx::Error) -> Self {
+        SuspensionError::ProblemsWithDB
+    }
+}
+
+async fn notify_suspended(username: String, details: String) {
+    notify(NotificationDTO {
+        recipient: username,
+        kind: NotificationKind::Moderation,
+        actor: None,
+        resource: None,
+        details: Some(details),
+    })
+    .await;
+}
+
+/// Administrators can't be suspended.
+pub(super) async fn ensure_suspendable(username: &str) -> Result<(), SuspensionError> {
+    let user = UserRepo::get_instance()
+        .await
+        .get_by_username(username)
+        .await
+        .ok_or(SuspensionError::UserDoesntExist)?;
+    if user.user_specs().is_administrator() {
+        return Err(SuspensionError::SuspendsAdministrator);
+    }
+    Ok(())
+}
+
+pub(super) async fn announce(username: &str, reason: &str, until: Option<NaiveDateTime>) {
+    notify_suspended(
+        username.to_string(),
+        match until {
+            Some(until) => format!("suspended until {until}: {reason}"),
+            None => format!("banned: {reason}"),
+        },
+    )
+    .await;
+}
+
+/// Suspends the user until the given time, or for good without one.
+pub async fn suspend(
+    moderator: &UserModel,
+    username: &str,
+    reason: &str,
+    until: Option<NaiveDateTime>,
+) -> Result<SuspensionModel, SuspensionError> {
+    ensure_suspendable(username).await?;
+    let suspension = SuspensionsRepo::get_instance()
+        .await
+        .suspend(username, reason, &moderator.username(), until)
+        .await?;
+    announce(username, reason, until).await;
+    Ok(suspension)
+}
+
+pub async fn lift(suspension: &SuspensionModel) -> Result<(), SuspensionError> {
+    SuspensionsRepo::get_instance()
+        .await
+        .lift(suspension)
+        .await?;
+    notify_suspended(suspension.username(), "suspension lifted".to_string()).await;
+    Ok(())
+}
+
+/// Each suspension can be appealed once.
+pub async fn appeal(user: &UserModel, text: &str) -> Result<SuspensionModel, SuspensionError> {
+    let repo = SuspensionsRepo::get_instance().await;
+    let suspension = repo
+        .get_active(&user.username())
+        .await
+        .ok_or(SuspensionError::NotSuspended)?;
+    if suspension.appeal_status().is_some() {
+        return Err(SuspensionError::AlreadyAppealed);
+    }
+    repo.appeal(&suspension, text).await?;
+    repo.get_by_uuid(&suspension.uuid())
+        .await
+        .ok_or(SuspensionError::SuspensionDoesntExist)
+}
+
+/// An accepted appeal lifts the suspension.
+pub async fn decide_appeal(
+    suspension: &SuspensionModel,
+    accepted: bool,
+) -> Result<(), SuspensionError> {
+    if suspension.appeal_status() != Some(AppealStatus::Pending) {
+        return Err(SuspensionError::AppealDoesntExist);
+    }
+    let repo = SuspensionsRepo::get_instance().await;
+    if accepted {
+        repo.set_appeal_status(suspension, AppealStatus::Accepted)
+            .await?;
+        return lift(suspension).await;
+    }
+    repo.set_appeal_status(suspension, AppealStatus::Rejected)
+        .await?;
+    notify_suspended(suspension.username(), "appeal rejected".to_string()).await;
+    Ok(())
+}
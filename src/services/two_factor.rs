@@ -0,0 +1,344 @@
+use super::accounts::{hash, hex};
+use crate::{
+    models::{two_factor::TwoFactorModel, user::UserModel},
+    repositories::two_factor::TwoFactorRepo,
+    utils::config::{self, minutes},
+};
+use chrono::NaiveDateTime;
+use lazy_static::lazy_static;
+use openssl::{memcmp, rand::rand_bytes};
+use serde::Serialize;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+const RECOVERY_CODES: usize = 10;
+
+#[derive(Debug, Serialize)]
+pub enum TwoFactorError {
+    AlreadyEnabled,
+    NotEnrolled,
+    CodeRequired,
+    WrongCode,
+    ProblemsWithRandom,
+    /// The secret couldn't be encoded or turned into a TOTP.
+    ProblemsWithSecret,
+    ProblemsWithDB,
+}
+
+impl From<sqlx::Error> for TwoFactorError {
+    fn from(_: sqlx::Error) -> Self {
+        TwoFactorError::ProblemsWithDB
+    }
+}
+
+lazy_static! {
+    static ref ISSUER: String =
+        dotenv::var("TWO_FACTOR_ISSUER").unwrap_or("News service".to_string());
+    static ref REQUIRED_USER_TYPES: Vec<String> =
+        config::list("TWO_FACTOR_REQUIRED_USER_TYPES", "Administrator");
+    static ref SESSION_TTL: chrono::Duration = minutes("TWO_FACTOR_SESSION_TTL_MINUTES", 15);
+}
+
+/// What the authenticator app needs, `provisioning_uri` is meant to be shown
+/// as a QR code.
+#[derive(Serialize)]
+pub struct Enrollment {
+    secret: String,
+    provisioning_uri: String,
+}
+
+/// SHA-1, 6 digits and 30 second steps, the only settings every
+/// authenticator app supports. A code from the step before or after is
+/// accepted as well, in case the clocks drift apart.
+fn totp(secret: &str, username: &str) -> Option<TOTP> {
+    let secret = Secret::Encoded(secret.to_string()).to_bytes().ok()?;
+    Some(TOTP::new_unchecked(
+        Algorithm::SHA1,
+        6,
+        1,
+        30,
+        secret,
+        Some(ISSUER.clone()),
+        username.to_string(),
+    ))
+}
+
+/// The time step `code` was generated for, if it's one of those accepted at
+/// `time`.
+fn step_of(totp: &TOTP, code: &str, time: u64) -> Option<u64> {
+    let code = code.trim();
+    let current = time / totp.step;
+    (current.saturating_sub(totp.skew as u64)..=current + totp.skew as u64).find(|step| {
+        let expected = totp.generate(step * totp.step);
+        expected.len() == code.len() && memcmp::eq(expected.as_bytes(), code.as_bytes())
+    })
+}
+
+/// Accepts a code only once: codes from the step of an accepted one or from
+/// the steps before it are refused, so a captured code can't be replayed.
+async fn accept_code(
+    two_factor: &TwoFactorModel,
+    username: &str,
+    code: &str,
+) -> Result<bool, TwoFactorError> {
+    let time = chrono::Utc::now().timestamp() as u64;
+    let step = totp(&two_factor.secret(), username).and_then(|totp| step_of(&totp, code, time));
+    match step {
+        Some(step) => Ok(TwoFactorRepo::get_instance()
+            .await
+            .accept_step(username, step)
+            .await?),
+        None => Ok(false),
+    }
+}
+
+/// Dashes and case don't matter, so codes can be typed the way they read.
+fn recovery_code_hash(code: &str) -> String {
+    let code: String = code
+        .chars()
+        .filter(|char| char.is_ascii_alphanumeric())
+        .collect();
+    hash(&code.to_lowercase())
+}
+
+/// Returns the codes themselves and the hashes to keep.
+fn recovery_codes() -> Result<(Vec<String>, Vec<String>), TwoFactorError> {
+    let mut codes = Vec::with_capacity(RECOVERY_CODES);
+    for _ in 0..RECOVERY_CODES {
+        let mut bytes = [0; 5];
+        rand_bytes(&mut bytes).map_err(|_| TwoFactorError::ProblemsWithRandom)?;
+        let code = hex(&bytes);
+        codes.push(format!("{}-{}", &code[..5], &code[5..]));
+    }
+    let hashes = codes.iter().map(|code| recovery_code_hash(code)).collect();
+    Ok((codes, hashes))
+}
+
+/// Whether the policy makes `user` use 2FA. Set in .env:
+/// `TWO_FACTOR_REQUIRED_USER_TYPES` lists the user types, administrators by
+/// default.
+pub fn required(user: &UserModel) -> bool {
+    let user_type = user.user_specs();
+    REQUIRED_USER_TYPES
+        .iter()
+        .any(|name| name == user_type.type_name())
+}
+
+/// Administrators the policy makes use 2FA only act as such once they
+/// enabled it.
+pub async fn acts_as_administrator(user: &UserModel) -> bool {
+    user.user_specs().is_administrator() && (!required(user) || is_enabled(&user.username()).await)
+}
+
+pub async fn is_enabled(username: &str) -> bool {
+    TwoFactorRepo::get_instance()
+        .await
+        .get(username)
+        .await
+        .is_some_and(|two_factor| two_factor.is_enabled())
+}
+
+/// Checks the second factor of a request, accounts without 2FA don't need
+/// one. A TOTP code and a recovery code are each good for a single request,
+/// clients making more open a session with `open_session` and send its token
+/// in their place until it expires.
+pub async fn check(username: &str, code: Option<&str>) -> Result<(), TwoFactorError> {
+    let repo = TwoFactorRepo::get_instance().await;
+    let two_factor = match repo.get(username).await {
+        Some(two_factor) if two_factor.is_enabled() => two_factor,
+        _ => return Ok(()),
+    };
+    let code = code.ok_or(TwoFactorError::CodeRequired)?;
+    if repo.has_session(username, &hash(code.trim())).await?
+        || accept_code(&two_factor, username, code).await?
+        || repo
+            .use_recovery_code(username, &recovery_code_hash(code))
+            .await?
+    {
+        Ok(())
+    } else {
+        Err(TwoFactorError::WrongCode)
+    }
+}
+
+/// Creates a new secret, 2FA stays off until a code from it is confirmed.
+pub async fn enroll(user: &UserModel) -> Result<Enrollment, TwoFactorError> {
+    let mut bytes = [0; 20];
+    rand_bytes(&mut bytes).map_err(|_| TwoFactorError::ProblemsWithRandom)?;
+    let Secret::Encoded(secret) = Secret::Raw(bytes.to_vec()).to_encoded() else {
+        return Err(TwoFactorError::ProblemsWithSecret);
+    };
+    if !TwoFactorRepo::get_instance()
+        .await
+        .start_enrollment(&user.username(), &secret)
+        .await?
+    {
+        return Err(TwoFactorError::AlreadyEnabled);
+    }
+    let provisioning_uri = totp(&secret, &user.username())
+        .ok_or(TwoFactorError::ProblemsWithSecret)?
+        .get_url();
+    Ok(Enrollment {
+        secret,
+        provisioning_uri,
+    })
+}
+
+/// Turns 2FA on and returns the recovery codes. They are shown only here,
+/// just their hashes are kept.
+pub async fn confirm(user: &UserModel, code: &str) -> Result<Vec<String>, TwoFactorError> {
+    let repo = TwoFactorRepo::get_instance().await;
+    let two_factor = repo
+        .get(&user.username())
+        .await
+        .ok_or(TwoFactorError::NotEnrolled)?;
+    if two_factor.is_enabled() {
+        return Err(TwoFactorError::AlreadyEnabled);
+    }
+    if !accept_code(&two_factor, &user.username(), code).await? {
+        return Err(TwoFactorError::WrongCode);
+    }
+    let (codes, hashes) = recovery_codes()?;
+    repo.enable(&user.username(), &hashes).await?;
+    Ok(codes)
+}
+
+#[derive(Serialize)]
+pub struct TwoFactorSession {
+    token: String,
+    expires_at: NaiveDateTime,
+}
+
+/// Opens a session for a user who just passed the second factor. Its token
+/// is accepted as `otp` until the session expires, after
+/// `TWO_FACTOR_SESSION_TTL_MINUTES` set in .env, 15 by default.
+pub async fn open_session(user: &UserModel) -> Result<TwoFactorSession, TwoFactorError> {
+    if !is_enabled(&user.username()).await {
+        return Err(TwoFactorError::NotEnrolled);
+    }
+    let mut bytes = [0; 32];
+    rand_bytes(&mut bytes).map_err(|_| TwoFactorError::ProblemsWithRandom)?;
+    let token = hex(&bytes);
+    let expires_at = chrono::Utc::now().naive_utc() + *SESSION_TTL;
+    TwoFactorRepo::get_instance()
+        .await
+        .open_session(&hash(&token), &user.username(), expires_at)
+        .await?;
+    Ok(TwoFactorSession { token, expires_at })
+}
+
+/// The user already passed the second factor while signing in.
+pub async fn disable(user: &UserModel) -> Result<(), TwoFactorError> {
+    if !is_enabled(&user.username()).await {
+        return Err(TwoFactorError::NotEnrolled);
+    }
+    Ok(TwoFactorRepo::get_instance()
+        .await
+        .disable(&user.username())
+        .await?)
+}
+
+pub async fn regenerate_recovery_codes(user: &UserModel) -> Result<Vec<String>, TwoFactorError> {
+    if !is_enabled(&user.username()).await {
+        return Err(TwoFactorError::NotEnrolled);
+    }
+    let (codes, hashes) = recovery_codes()?;
+    TwoFactorRepo::get_instance()
+        .await
+        .replace_recovery_codes(&user.username(), &hashes)
+        .await?;
+    Ok(codes)
+}
+
+#[derive(Serialize)]
+pub struct TwoFactorStatus {
+    enabled: bool,
+    required: bool,
+    recovery_codes_left: i64,
+}
+
+pub async fn status(user: &UserModel) -> TwoFactorStatus {
+    let repo = TwoFactorRepo::get_instance().await;
+    TwoFactorStatus {
+        enabled: is_enabled(&user.username()).await,
+        required: required(user),
+        recovery_codes_left: repo.recovery_codes_left(&user.username()).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_totp() -> TOTP {
+        TOTP::new_unchecked(
+            Algorithm::SHA1,
+            6,
+            1,
+            30,
+            b"12345678901234567890".to_vec(),
+            None,
+            "student".to_string(),
+        )
+    }
+
+    #[test]
+    fn recovery_codes_ignore_dashes_and_case() {
+        assert_eq!(
+            recovery_code_hash("ab12c-3de45"),
+            recovery_code_hash("AB12C3DE45")
+        );
+        assert_eq!(
+            recovery_code_hash(" ab12c-3de45 "),
+            recovery_code_hash("ab12c3de45")
+        );
+        assert_ne!(
+            recovery_code_hash("ab12c-3de45"),
+            recovery_code_hash("ab12c-3de46")
+        );
+    }
+
+    #[test]
+    fn recovery_codes_are_unique_and_hashed() {
+        let (codes, hashes) = recovery_codes().unwrap();
+        assert_eq!(codes.len(), RECOVERY_CODES);
+        assert!(codes
+            .iter()
+            .all(|code| code.len() == 11 && &code[5..6] == "-"));
+        let mut unique = codes.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), RECOVERY_CODES);
+        for (code, code_hash) in codes.iter().zip(&hashes) {
+            assert_ne!(code, code_hash);
+            assert_eq!(&recovery_code_hash(code), code_hash);
+        }
+    }
+
+    #[test]
+    fn codes_map_to_their_step() {
+        let totp = test_totp();
+        // RFC 6238 test vector: 94287082 at 59 s, truncated to 6 digits.
+        assert_eq!(totp.generate(59), "287082");
+        assert_eq!(step_of(&totp, "287082", 59), Some(1));
+        assert_eq!(step_of(&totp, " 287082 ", 59), Some(1));
+    }
+
+    #[test]
+    fn neighbouring_steps_are_accepted() {
+        let totp = test_totp();
+        let code = totp.generate(300);
+        assert_eq!(step_of(&totp, &code, 270), Some(10));
+        assert_eq!(step_of(&totp, &code, 300), Some(10));
+        assert_eq!(step_of(&totp, &code, 330), Some(10));
+        assert_eq!(step_of(&totp, &code, 360), None);
+        assert_eq!(step_of(&totp, &code, 240), None);
+    }
+
+    #[test]
+    fn wrong_codes_have_no_step() {
+        let totp = test_totp();
+        assert_eq!(step_of(&totp, "", 59), None);
+        assert_eq!(step_of(&totp, "28708", 59), None);
+        assert_eq!(step_of(&totp, "2870820", 59), None);
+    }
+}
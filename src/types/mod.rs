@@ -1,8 +1,9 @@
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime};
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
+use std::{fmt::Display, str::FromStr};
+use uuid::Uuid;
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct Class {
     class_char: u8,
     class_num: u8,
@@ -13,6 +14,60 @@ pub struct Limit {
     pub offset: Option<u32>,
 }
 
+/// Weights of the terms that make up the score of a post in the feed.
+#[derive(Clone, Copy)]
+pub struct FeedRanking {
+    pub recency: f64,
+    pub raiting: f64,
+    pub pinned: f64,
+    pub seen: f64,
+}
+
+/// Position in a feed. `generated_at` is fixed by the first page so that the
+/// recency term, and therefore the order, doesn't drift between pages.
+#[derive(Clone)]
+pub struct FeedCursor {
+    pub generated_at: NaiveDateTime,
+    pub score: f64,
+    pub uuid: Uuid,
+}
+
+impl Display for FeedCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}_{}_{}",
+            self.generated_at.and_utc().timestamp_micros(),
+            self.score,
+            self.uuid
+        )
+    }
+}
+
+impl FromStr for FeedCursor {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('_');
+        let (Some(generated_at), Some(score), Some(uuid), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(());
+        };
+        Ok(FeedCursor {
+            generated_at: DateTime::from_timestamp_micros(generated_at.parse().map_err(|_| ())?)
+                .ok_or(())?
+                .naive_utc(),
+            score: score
+                .parse()
+                .ok()
+                .filter(|score: &f64| score.is_finite())
+                .ok_or(())?,
+            uuid: Uuid::from_str(uuid).map_err(|_| ())?,
+        })
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum EditedState {
     Edited { edited_at: NaiveDateTime },
@@ -23,6 +78,32 @@ impl EditedState {
     pub fn is_edited(&self) -> bool {
         matches!(self, Self::Edited { .. })
     }
+
+    pub fn edited_at(&self) -> Option<NaiveDateTime> {
+        match self {
+            Self::Edited { edited_at } => Some(*edited_at),
+            Self::NotEdited => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum PostStatus {
+    Draft,
+    Scheduled { publish_at: NaiveDateTime },
+    Published,
+    Archived,
+}
+
+impl PostStatus {
+    pub fn is_published(&self) -> bool {
+        matches!(self, Self::Published)
+    }
+
+    /// Drafts and scheduled posts are the ones the draft endpoints work on.
+    pub fn is_draft(&self) -> bool {
+        matches!(self, Self::Draft | Self::Scheduled { .. })
+    }
 }
 
 impl Class {
@@ -55,6 +136,30 @@ impl Class {
     pub fn class_num(&self) -> u8 {
         self.class_num
     }
+
+    /// Deserialized classes skip the checks of `Class::from`.
+    pub fn is_valid(&self) -> bool {
+        Class::from(self.class_char, self.class_num).is_ok()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum Audience {
+    Public,
+    Class { class: Class },
+    Grade { class_num: u8 },
+    Teachers { subject: Option<Subject> },
+    Administrators,
+}
+
+impl Audience {
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Audience::Class { class } => class.is_valid(),
+            Audience::Grade { class_num } => (1..=11).contains(class_num),
+            _ => true,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -98,8 +203,8 @@ impl FromStr for Subject {
             "History" => Self::History,
             "Geography" => Self::Geography,
             "Literature" => Self::Literature,
-            "PhysicalEducation" => Self::PhysicalEducation,
-            "ComputerScience" => Self::ComputerScience,
+            "PhysicalEducation" | "Physical Education" => Self::PhysicalEducation,
+            "ComputerScience" | "Computer Science" => Self::ComputerScience,
             "Economics" => Self::Economics,
             "Law" => Self::Law,
             "Education" => Self::Education,
@@ -130,3 +235,80 @@ impl ToString for Subject {
         .to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cursor() -> FeedCursor {
+        FeedCursor {
+            generated_at: DateTime::from_timestamp_micros(1_700_000_000_123_456)
+                .unwrap()
+                .naive_utc(),
+            score: -0.1 + 12.5,
+            uuid: Uuid::from_str("6f1c3a1e-8d7b-4d55-9a47-1f0b2c3d4e5f").unwrap(),
+        }
+    }
+
+    #[test]
+    fn feed_cursor_round_trips() {
+        let cursor = cursor();
+        let parsed = FeedCursor::from_str(&cursor.to_string()).unwrap();
+        assert_eq!(parsed.generated_at, cursor.generated_at);
+        assert_eq!(parsed.score, cursor.score);
+        assert_eq!(parsed.uuid, cursor.uuid);
+    }
+
+    #[test]
+    fn feed_cursor_keeps_negative_scores() {
+        let cursor = FeedCursor {
+            score: -3.75,
+            ..cursor()
+        };
+        let parsed = FeedCursor::from_str(&cursor.to_string()).unwrap();
+        assert_eq!(parsed.score, -3.75);
+    }
+
+    #[test]
+    fn feed_cursor_rejects_malformed_input() {
+        let uuid = "6f1c3a1e-8d7b-4d55-9a47-1f0b2c3d4e5f";
+        assert!(FeedCursor::from_str("").is_err());
+        assert!(FeedCursor::from_str(&format!("1700000000_1.5_{}_extra", uuid)).is_err());
+        assert!(FeedCursor::from_str(&format!("now_1.5_{}", uuid)).is_err());
+        assert!(FeedCursor::from_str("1700000000_1.5_not-a-uuid").is_err());
+    }
+
+    #[test]
+    fn feed_cursor_rejects_non_finite_scores() {
+        let uuid = "6f1c3a1e-8d7b-4d55-9a47-1f0b2c3d4e5f";
+        for score in ["NaN", "inf", "-inf"] {
+            assert!(FeedCursor::from_str(&format!("1700000000_{}_{}", score, uuid)).is_err());
+        }
+    }
+
+    #[test]
+    fn only_drafts_and_scheduled_posts_are_drafts() {
+        assert!(PostStatus::Draft.is_draft());
+        assert!(PostStatus::Scheduled {
+            publish_at: DateTime::from_timestamp(1_700_000_000, 0)
+                .unwrap()
+                .naive_utc()
+        }
+        .is_draft());
+        assert!(!PostStatus::Published.is_draft());
+        assert!(!PostStatus::Archived.is_draft());
+    }
+
+    #[test]
+    fn audience_checks_class_and_grade() {
+        assert!(Audience::Grade { class_num: 11 }.is_valid());
+        assert!(!Audience::Grade { class_num: 0 }.is_valid());
+        assert!(!Audience::Grade { class_num: 12 }.is_valid());
+        let class: Class = serde_json::from_str(r#"{"class_char":49,"class_num":7}"#).unwrap();
+        assert!(!Audience::Class { class }.is_valid());
+        assert!(Audience::Class {
+            class: Class::from(b'B', 7).unwrap()
+        }
+        .is_valid());
+    }
+}
@@ -0,0 +1,40 @@
+use std::str::FromStr;
+
+/// Reads `key` from .env with `parse`, `default` when it's not set. A value
+/// that doesn't parse stops the service instead of being ignored.
+pub fn parsed<T>(key: &str, default: T, parse: impl FnOnce(&str) -> Option<T>) -> T {
+    match dotenv::var(key) {
+        Ok(value) => parse(value.trim()).unwrap_or_else(|| panic!("{key} is invalid: {value}")),
+        Err(_) => default,
+    }
+}
+
+/// Numbers and other values with a `FromStr` implementation.
+pub fn setting<T: FromStr>(key: &str, default: T) -> T {
+    parsed(key, default, |value| value.parse().ok())
+}
+
+/// A comma separated list such as `Student,Other`, lowercased.
+pub fn list(key: &str, default: &str) -> Vec<String> {
+    dotenv::var(key)
+        .unwrap_or(default.to_string())
+        .split(',')
+        .map(|item| item.trim().to_lowercase())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+pub fn minutes(key: &str, default: i64) -> chrono::Duration {
+    chrono::Duration::minutes(setting(key, default))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unset_settings_fall_back_to_the_default() {
+        assert_eq!(setting("CONFIG_TEST_UNSET_SETTING", 7), 7);
+        assert_eq!(list("CONFIG_TEST_UNSET_LIST", "A, b,,"), vec!["a", "b"]);
+    }
+}
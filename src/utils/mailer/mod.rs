@@ -0,0 +1,87 @@
+use self::{outbox_mailer::OutboxMailer, smtp_mailer::SmtpMailer};
+use lazy_static::lazy_static;
+use lettre::{message::header::ContentType, Message};
+use serde::Serialize;
+use std::future::Future;
+
+pub mod outbox_mailer;
+pub mod smtp_mailer;
+
+#[derive(Debug, Serialize)]
+pub enum MailError {
+    InvalidAddress,
+    Unavailable,
+}
+
+/// A plain text email.
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl Mail {
+    fn message(&self, from: &str) -> Result<Message, MailError> {
+        Message::builder()
+            .from(from.parse().map_err(|_| MailError::InvalidAddress)?)
+            .to(self.to.parse().map_err(|_| MailError::InvalidAddress)?)
+            .subject(&self.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(self.body.clone())
+            .map_err(|_| MailError::InvalidAddress)
+    }
+}
+
+pub trait Mailer {
+    fn send(&self, mail: &Mail) -> impl Future<Output = Result<(), MailError>> + Send;
+}
+
+pub enum ConfiguredMailer {
+    Smtp(SmtpMailer),
+    Outbox(OutboxMailer),
+}
+
+impl Mailer for ConfiguredMailer {
+    async fn send(&self, mail: &Mail) -> Result<(), MailError> {
+        match self {
+            ConfiguredMailer::Smtp(mailer) => mailer.send(mail).await,
+            ConfiguredMailer::Outbox(mailer) => mailer.send(mail).await,
+        }
+    }
+}
+
+fn var_or(key: &str, default: &str) -> String {
+    dotenv::var(key).unwrap_or(default.to_string())
+}
+
+lazy_static! {
+    static ref MAILER: ConfiguredMailer = {
+        let from = var_or("MAIL_FROM", "News service <noreply@localhost>");
+        match var_or("MAIL_BACKEND", "outbox").as_str() {
+            "outbox" => ConfiguredMailer::Outbox(OutboxMailer::new(
+                &var_or("MAIL_OUTBOX_DIRECTORY", "outbox"),
+                &from,
+            )),
+            "smtp" => ConfiguredMailer::Smtp(
+                SmtpMailer::new(
+                    &dotenv::var("SMTP_HOST").expect("SMTP_HOST must be set"),
+                    var_or("SMTP_PORT", "1025")
+                        .parse()
+                        .expect("SMTP_PORT must be a number"),
+                    &var_or("SMTP_SECURITY", "none"),
+                    dotenv::var("SMTP_USERNAME")
+                        .ok()
+                        .zip(dotenv::var("SMTP_PASSWORD").ok()),
+                    &from,
+                )
+                .expect("SMTP mailer is misconfigured"),
+            ),
+            backend => panic!("unknown MAIL_BACKEND {backend}, expected outbox or smtp"),
+        }
+    };
+}
+
+/// Mailer selected by `MAIL_BACKEND` in .env, `outbox` when unset.
+pub fn get_mailer() -> &'static ConfiguredMailer {
+    &MAILER
+}
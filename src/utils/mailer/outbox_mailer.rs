@@ -0,0 +1,39 @@
+use super::{Mail, MailError, Mailer};
+use actix_web::web;
+use std::{fs, path::PathBuf};
+use uuid::Uuid;
+
+/// Writes every mail to its own `.eml` file instead of sending it, for
+/// development.
+pub struct OutboxMailer {
+    directory: PathBuf,
+    from: String,
+}
+
+impl OutboxMailer {
+    pub fn new(path: &str, from: &str) -> Self {
+        OutboxMailer {
+            directory: PathBuf::from(path),
+            from: from.to_string(),
+        }
+    }
+}
+
+impl Mailer for OutboxMailer {
+    async fn send(&self, mail: &Mail) -> Result<(), MailError> {
+        let message = mail.message(&self.from)?.formatted();
+        let directory = self.directory.clone();
+        let name = format!(
+            "{}-{}.eml",
+            chrono::Utc::now().format("%Y%m%d%H%M%S"),
+            Uuid::new_v4()
+        );
+        web::block(move || {
+            fs::create_dir_all(&directory)?;
+            fs::write(directory.join(name), message)
+        })
+        .await
+        .map_err(|_| MailError::Unavailable)?
+        .map_err(|_| MailError::Unavailable)
+    }
+}
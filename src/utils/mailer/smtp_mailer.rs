@@ -0,0 +1,49 @@
+use super::{Mail, MailError, Mailer};
+use lettre::{
+    transport::smtp::authentication::Credentials, AsyncSmtpTransport, AsyncTransport,
+    Tokio1Executor,
+};
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: String,
+}
+
+impl SmtpMailer {
+    /// `security` is `none` for local sinks such as MailHog, `starttls` or
+    /// `tls` otherwise.
+    pub fn new(
+        host: &str,
+        port: u16,
+        security: &str,
+        credentials: Option<(String, String)>,
+        from: &str,
+    ) -> Result<Self, MailError> {
+        let builder = match security {
+            "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+            "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+                .map_err(|_| MailError::Unavailable)?,
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(host)
+                .map_err(|_| MailError::Unavailable)?,
+            _ => return Err(MailError::Unavailable),
+        };
+        let builder = match credentials {
+            Some((username, password)) => builder.credentials(Credentials::new(username, password)),
+            None => builder,
+        };
+        Ok(SmtpMailer {
+            transport: builder.port(port).build(),
+            from: from.to_string(),
+        })
+    }
+}
+
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: &Mail) -> Result<(), MailError> {
+        self.transport
+            .send(mail.message(&self.from)?)
+            .await
+            .map_err(|_| MailError::Unavailable)?;
+        Ok(())
+    }
+}
@@ -1,2 +1,7 @@
+pub mod config;
 pub mod logger;
+pub mod mailer;
+pub mod markdown;
+pub mod rate_limit;
 pub mod sql;
+pub mod storage;
@@ -0,0 +1,88 @@
+use super::{RateStore, RateStoreError};
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Windows are dropped once this many keys are kept and they're over.
+const PRUNE_AT: usize = 10_000;
+
+#[derive(Default)]
+pub struct MemoryStore {
+    windows: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+/// Counts a hit at `now` in the window ending at `resets_at`, starting a new
+/// one when it's over.
+fn count((resets_at, hits): &mut (Instant, u32), now: Instant, window: u64) -> (u32, u64) {
+    if *resets_at <= now {
+        *resets_at = now + Duration::from_secs(window);
+        *hits = 0;
+    }
+    *hits += 1;
+    (*hits, resets_at.duration_since(now).as_secs().max(1))
+}
+
+/// Hits in the window at `now` without counting one.
+fn current((resets_at, hits): (Instant, u32), now: Instant) -> (u32, u64) {
+    if resets_at <= now {
+        return (0, 0);
+    }
+    (hits, resets_at.duration_since(now).as_secs().max(1))
+}
+
+impl RateStore for MemoryStore {
+    async fn hit(&self, key: &str, window: u64) -> Result<(u32, u64), RateStoreError> {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        if windows.len() >= PRUNE_AT {
+            windows.retain(|_, (resets_at, _)| *resets_at > now);
+        }
+        let entry = windows
+            .entry(key.to_string())
+            .or_insert((now + Duration::from_secs(window), 0));
+        Ok(count(entry, now, window))
+    }
+
+    async fn peek(&self, key: &str) -> Result<(u32, u64), RateStoreError> {
+        let windows = self.windows.lock().unwrap();
+        Ok(windows
+            .get(key)
+            .map_or((0, 0), |entry| current(*entry, Instant::now())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hits_add_up_within_the_window() {
+        let start = Instant::now();
+        let mut entry = (start + Duration::from_secs(60), 0);
+        assert_eq!(count(&mut entry, start, 60), (1, 60));
+        assert_eq!(
+            count(&mut entry, start + Duration::from_secs(20), 60),
+            (2, 40)
+        );
+        assert_eq!(current(entry, start + Duration::from_secs(30)), (2, 30));
+    }
+
+    #[test]
+    fn window_resets_once_over() {
+        let start = Instant::now();
+        let mut entry = (start + Duration::from_secs(60), 5);
+        let later = start + Duration::from_secs(60);
+        assert_eq!(current(entry, later), (0, 0));
+        assert_eq!(count(&mut entry, later, 60), (1, 60));
+        assert_eq!(entry.0, later + Duration::from_secs(60));
+    }
+
+    #[test]
+    fn retry_after_is_never_zero() {
+        let start = Instant::now();
+        let mut entry = (start + Duration::from_millis(300), 1);
+        assert_eq!(count(&mut entry, start, 60), (2, 1));
+    }
+}
@@ -0,0 +1,66 @@
+use self::{memory_store::MemoryStore, redis_store::RedisStore};
+use crate::REDIS_ADDRES;
+use lazy_static::lazy_static;
+use std::future::Future;
+
+pub mod memory_store;
+pub mod redis_store;
+
+#[derive(Debug)]
+pub enum RateStoreError {
+    Unavailable,
+}
+
+/// Counts hits under string keys in fixed windows.
+pub trait RateStore {
+    /// Counts a hit on `key` and returns the hits in the current window along
+    /// with the seconds left until it resets. The window starts with the
+    /// first hit and lasts `window` seconds.
+    fn hit(
+        &self,
+        key: &str,
+        window: u64,
+    ) -> impl Future<Output = Result<(u32, u64), RateStoreError>> + Send;
+
+    /// Same as `hit` without counting anything. Keys without hits in the
+    /// current window give `(0, 0)`.
+    fn peek(&self, key: &str) -> impl Future<Output = Result<(u32, u64), RateStoreError>> + Send;
+}
+
+pub enum ConfiguredRateStore {
+    Memory(MemoryStore),
+    Redis(RedisStore),
+}
+
+impl RateStore for ConfiguredRateStore {
+    async fn hit(&self, key: &str, window: u64) -> Result<(u32, u64), RateStoreError> {
+        match self {
+            ConfiguredRateStore::Memory(store) => store.hit(key, window).await,
+            ConfiguredRateStore::Redis(store) => store.hit(key, window).await,
+        }
+    }
+
+    async fn peek(&self, key: &str) -> Result<(u32, u64), RateStoreError> {
+        match self {
+            ConfiguredRateStore::Memory(store) => store.peek(key).await,
+            ConfiguredRateStore::Redis(store) => store.peek(key).await,
+        }
+    }
+}
+
+lazy_static! {
+    static ref RATE_STORE: ConfiguredRateStore = match dotenv::var("RATE_LIMIT_BACKEND")
+        .unwrap_or("memory".to_string())
+        .as_str()
+    {
+        "memory" => ConfiguredRateStore::Memory(MemoryStore::default()),
+        "redis" => ConfiguredRateStore::Redis(RedisStore::new(&REDIS_ADDRES)),
+        backend => panic!("unknown RATE_LIMIT_BACKEND {backend}, expected memory or redis"),
+    };
+}
+
+/// Store selected by `RATE_LIMIT_BACKEND` in .env, `memory` when unset. Only
+/// `redis` shares the counters between instances.
+pub fn get_rate_store() -> &'static ConfiguredRateStore {
+    &RATE_STORE
+}
@@ -0,0 +1,60 @@
+use super::{RateStore, RateStoreError};
+
+const KEY_PREFIX: &str = "news_service:rate:";
+
+pub struct RedisStore {
+    client: redis::Client,
+}
+
+impl RedisStore {
+    pub fn new(address: &str) -> Self {
+        RedisStore {
+            client: redis::Client::open(address).expect("REDIS_ADDRES must be a redis url"),
+        }
+    }
+}
+
+impl RateStore for RedisStore {
+    async fn hit(&self, key: &str, window: u64) -> Result<(u32, u64), RateStoreError> {
+        let key = format!("{}{}", KEY_PREFIX, key);
+        let mut connection = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|_| RateStoreError::Unavailable)?;
+        // `NX` keeps the expiry of the first hit, it needs Redis 7.
+        let (hits, ttl): (u32, i64) = redis::pipe()
+            .atomic()
+            .incr(&key, 1)
+            .cmd("EXPIRE")
+            .arg(&key)
+            .arg(window)
+            .arg("NX")
+            .ignore()
+            .ttl(&key)
+            .query_async(&mut connection)
+            .await
+            .map_err(|_| RateStoreError::Unavailable)?;
+        Ok((hits, ttl.max(1) as u64))
+    }
+
+    async fn peek(&self, key: &str) -> Result<(u32, u64), RateStoreError> {
+        let key = format!("{}{}", KEY_PREFIX, key);
+        let mut connection = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|_| RateStoreError::Unavailable)?;
+        let (hits, ttl): (Option<u32>, i64) = redis::pipe()
+            .atomic()
+            .get(&key)
+            .ttl(&key)
+            .query_async(&mut connection)
+            .await
+            .map_err(|_| RateStoreError::Unavailable)?;
+        match hits {
+            Some(hits) => Ok((hits, ttl.max(1) as u64)),
+            None => Ok((0, 0)),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use super::{Storage, StorageError};
+use actix_web::web;
+use std::{fs, io::ErrorKind, path::PathBuf};
+
+pub struct LocalStorage {
+    directory: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(path: &str) -> Self {
+        LocalStorage {
+            directory: PathBuf::from(path),
+        }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.directory.join(key)
+    }
+}
+
+fn storage_error(err: std::io::Error) -> StorageError {
+    match err.kind() {
+        ErrorKind::NotFound => StorageError::NotFound,
+        _ => StorageError::Unavailable,
+    }
+}
+
+impl Storage for LocalStorage {
+    async fn put(
+        &self,
+        key: &str,
+        content: Vec<u8>,
+        _content_type: &str,
+    ) -> Result<(), StorageError> {
+        let path = self.path(key);
+        web::block(move || {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, content)
+        })
+        .await
+        .map_err(|_| StorageError::Unavailable)?
+        .map_err(storage_error)
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, StorageError> {
+        let path = self.path(key);
+        web::block(move || fs::read(path))
+            .await
+            .map_err(|_| StorageError::Unavailable)?
+            .map_err(storage_error)
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        let path = self.path(key);
+        web::block(move || fs::remove_file(path))
+            .await
+            .map_err(|_| StorageError::Unavailable)?
+            .map_err(storage_error)
+    }
+}
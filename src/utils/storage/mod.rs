@@ -0,0 +1,85 @@
+use self::{local_storage::LocalStorage, s3_storage::S3Storage};
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::future::Future;
+
+pub mod local_storage;
+pub mod s3_storage;
+
+#[derive(Debug, Serialize)]
+pub enum StorageError {
+    NotFound,
+    Unavailable,
+}
+
+/// Keeps uploaded files under string keys such as `attachments/<uuid>`.
+pub trait Storage {
+    fn put(
+        &self,
+        key: &str,
+        content: Vec<u8>,
+        content_type: &str,
+    ) -> impl Future<Output = Result<(), StorageError>> + Send;
+    fn get(&self, key: &str) -> impl Future<Output = Result<Vec<u8>, StorageError>> + Send;
+    fn delete(&self, key: &str) -> impl Future<Output = Result<(), StorageError>> + Send;
+}
+
+pub enum ConfiguredStorage {
+    Local(LocalStorage),
+    S3(S3Storage),
+}
+
+impl Storage for ConfiguredStorage {
+    async fn put(
+        &self,
+        key: &str,
+        content: Vec<u8>,
+        content_type: &str,
+    ) -> Result<(), StorageError> {
+        match self {
+            ConfiguredStorage::Local(storage) => storage.put(key, content, content_type).await,
+            ConfiguredStorage::S3(storage) => storage.put(key, content, content_type).await,
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, StorageError> {
+        match self {
+            ConfiguredStorage::Local(storage) => storage.get(key).await,
+            ConfiguredStorage::S3(storage) => storage.get(key).await,
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        match self {
+            ConfiguredStorage::Local(storage) => storage.delete(key).await,
+            ConfiguredStorage::S3(storage) => storage.delete(key).await,
+        }
+    }
+}
+
+fn var_or(key: &str, default: &str) -> String {
+    dotenv::var(key).unwrap_or(default.to_string())
+}
+
+lazy_static! {
+    static ref STORAGE: ConfiguredStorage = match var_or("STORAGE_BACKEND", "local").as_str() {
+        "local" =>
+            ConfiguredStorage::Local(LocalStorage::new(&var_or("STORAGE_DIRECTORY", "storage"))),
+        "s3" => ConfiguredStorage::S3(
+            S3Storage::new(
+                &dotenv::var("S3_ENDPOINT").expect("S3_ENDPOINT must be set"),
+                &dotenv::var("S3_BUCKET").expect("S3_BUCKET must be set"),
+                &dotenv::var("S3_ACCESS_KEY").expect("S3_ACCESS_KEY must be set"),
+                &dotenv::var("S3_SECRET_KEY").expect("S3_SECRET_KEY must be set"),
+                &var_or("S3_REGION", "us-east-1"),
+            )
+            .expect("S3 storage is misconfigured"),
+        ),
+        backend => panic!("unknown STORAGE_BACKEND {backend}, expected local or s3"),
+    };
+}
+
+/// Storage selected by `STORAGE_BACKEND` in .env, `local` when unset.
+pub fn get_storage() -> &'static ConfiguredStorage {
+    &STORAGE
+}
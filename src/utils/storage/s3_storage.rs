@@ -0,0 +1,80 @@
+use super::{Storage, StorageError};
+use object_store::{
+    aws::{AmazonS3, AmazonS3Builder},
+    path::Path,
+    Attribute, Attributes, ObjectStore, PutOptions,
+};
+
+/// Any S3 compatible service, MinIO included: requests are path-style and
+/// plain http endpoints are allowed.
+pub struct S3Storage {
+    store: AmazonS3,
+}
+
+impl S3Storage {
+    pub fn new(
+        endpoint: &str,
+        bucket: &str,
+        access_key: &str,
+        secret_key: &str,
+        region: &str,
+    ) -> Result<Self, object_store::Error> {
+        Ok(S3Storage {
+            store: AmazonS3Builder::new()
+                .with_endpoint(endpoint)
+                .with_bucket_name(bucket)
+                .with_access_key_id(access_key)
+                .with_secret_access_key(secret_key)
+                .with_region(region)
+                .with_allow_http(true)
+                .with_virtual_hosted_style_request(false)
+                .build()?,
+        })
+    }
+}
+
+fn storage_error(err: object_store::Error) -> StorageError {
+    match err {
+        object_store::Error::NotFound { .. } => StorageError::NotFound,
+        _ => StorageError::Unavailable,
+    }
+}
+
+impl Storage for S3Storage {
+    async fn put(
+        &self,
+        key: &str,
+        content: Vec<u8>,
+        content_type: &str,
+    ) -> Result<(), StorageError> {
+        let options = PutOptions {
+            attributes: Attributes::from_iter([(Attribute::ContentType, content_type.to_string())]),
+            ..Default::default()
+        };
+        self.store
+            .put_opts(&Path::from(key), content.into(), options)
+            .await
+            .map(|_| ())
+            .map_err(storage_error)
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, StorageError> {
+        let object = self
+            .store
+            .get(&Path::from(key))
+            .await
+            .map_err(storage_error)?;
+        object
+            .bytes()
+            .await
+            .map(|bytes| bytes.to_vec())
+            .map_err(storage_error)
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        self.store
+            .delete(&Path::from(key))
+            .await
+            .map_err(storage_error)
+    }
+}
@@ -0,0 +1,309 @@
+use crate::{
+    models::banned_word::{BannedWord, WordSeverity},
+    repositories::banned_words::BannedWordsRepo,
+};
+
+const APOSTROPHES: [char; 6] = ['\'', '`', '‘', '’', 'ʻ', 'ʼ'];
+
+fn transliterate(letter: char) -> &'static str {
+    match letter {
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' | 'ғ' => "g",
+        'д' => "d",
+        'е' | 'ё' | 'э' => "e",
+        'ж' => "j",
+        'з' => "z",
+        'и' | 'ы' => "i",
+        'й' => "y",
+        'к' | 'қ' => "k",
+        'л' => "l",
+        'м' => "m",
+        'н' => "n",
+        'о' | 'ў' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'у' => "u",
+        'ф' => "f",
+        'х' | 'ҳ' => "h",
+        'ц' => "ts",
+        'ч' => "ch",
+        'ш' | 'щ' => "sh",
+        'ю' => "yu",
+        'я' => "ya",
+        'ъ' | 'ь' => "",
+        _ => "",
+    }
+}
+
+/// Brings a word to a form shared by its spellings: Cyrillic is
+/// transliterated, Uzbek Latin `q`/`x` become `k`/`h` and apostrophes are
+/// dropped. Digits stay as they are.
+pub fn normalize(word: &str) -> String {
+    let mut normalized = String::new();
+    for letter in word.to_lowercase().chars() {
+        if APOSTROPHES.contains(&letter) {
+            continue;
+        }
+        match letter {
+            'q' => normalized.push('k'),
+            'x' => normalized.push('h'),
+            'w' => normalized.push('v'),
+            letter if letter.is_ascii() => normalized.push(letter),
+            letter => match transliterate(letter) {
+                "" if letter.is_alphanumeric() && !matches!(letter, 'ъ' | 'ь') => {
+                    normalized.push(letter)
+                }
+                latin => normalized.push_str(latin),
+            },
+        }
+    }
+    normalized
+}
+
+fn leet_letter(digit: char) -> Option<char> {
+    match digit {
+        '0' => Some('o'),
+        '1' => Some('i'),
+        '3' => Some('e'),
+        '4' => Some('a'),
+        '5' => Some('s'),
+        _ => None,
+    }
+}
+
+/// The normalized word with digits standing for letters replaced, `None`
+/// unless it mixes letters with such digits. Plain numbers like `45` aren't
+/// words spelled with digits.
+fn unleet(normalized: &str) -> Option<String> {
+    let has_letters = normalized.chars().any(|letter| letter.is_alphabetic());
+    let has_leet = normalized
+        .chars()
+        .any(|letter| leet_letter(letter).is_some());
+    if !has_letters || !has_leet {
+        return None;
+    }
+    Some(
+        normalized
+            .chars()
+            .map(|letter| leet_letter(letter).unwrap_or(letter))
+            .collect(),
+    )
+}
+
+fn collapse(word: &str) -> String {
+    let mut collapsed = String::with_capacity(word.len());
+    for letter in word.chars() {
+        if !collapsed.ends_with(letter) {
+            collapsed.push(letter);
+        }
+    }
+    collapsed
+}
+
+/// Plain words must match exactly. Words spelled with digits also match with
+/// repeated letters collapsed, e.g. `a555` for `ass`, but never when they're
+/// shorter than the banned word.
+fn matches(word: &BannedWord, normalized: &str, unleeted: Option<&str>) -> bool {
+    let (banned, is_stem) = match word.word.strip_suffix('*') {
+        Some(stem) => (normalize(stem), true),
+        None => (normalize(&word.word), false),
+    };
+    let equal = |token: &str, banned: &str| match is_stem {
+        true => token.starts_with(banned),
+        false => token == banned,
+    };
+    if equal(normalized, &banned) {
+        return true;
+    }
+    match unleeted {
+        Some(unleeted) => {
+            equal(unleeted, &banned)
+                || (unleeted.chars().count() >= banned.chars().count()
+                    && equal(&collapse(unleeted), &collapse(&banned)))
+        }
+        None => false,
+    }
+}
+
+/// Text after screening. Words of `Mask` severity are replaced with `*`,
+/// `flagged` lists every banned word found.
+pub struct Screened {
+    pub text: String,
+    pub flagged: Vec<BannedWord>,
+}
+
+pub fn screen(text: &str, words: &[BannedWord]) -> Screened {
+    let mut screened = String::with_capacity(text.len());
+    let mut flagged: Vec<BannedWord> = Vec::new();
+    let mut token = String::new();
+    let mut flush = |token: &mut String, screened: &mut String| {
+        if token.is_empty() {
+            return;
+        }
+        let normalized = normalize(token);
+        let unleeted = unleet(&normalized);
+        let found = words
+            .iter()
+            .filter(|word| matches(word, &normalized, unleeted.as_deref()))
+            .max_by_key(|word| word.severity);
+        match found {
+            Some(word) => {
+                if word.severity == WordSeverity::Mask {
+                    screened.extend(token.chars().map(|_| '*'));
+                } else {
+                    screened.push_str(token);
+                }
+                if !flagged.iter().any(|flagged| flagged.word == word.word) {
+                    flagged.push(word.clone());
+                }
+            }
+            None => screened.push_str(token),
+        }
+        token.clear();
+    };
+    for letter in text.chars() {
+        if letter.is_alphanumeric() || APOSTROPHES.contains(&letter) {
+            token.push(letter);
+        } else {
+            flush(&mut token, &mut screened);
+            screened.push(letter);
+        }
+    }
+    flush(&mut token, &mut screened);
+    Screened {
+        text: screened,
+        flagged,
+    }
+}
+
+pub enum Verdict {
+    Allow,
+    Moderate { words: Vec<String> },
+    Reject { words: Vec<String> },
+}
+
+/// The strictest severity among the screened parts of one item decides.
+pub fn verdict(screened: &[Screened]) -> Verdict {
+    let flagged: Vec<&BannedWord> = screened
+        .iter()
+        .flat_map(|screened| screened.flagged.iter())
+        .collect();
+    let words_of = |severity: WordSeverity| {
+        flagged
+            .iter()
+            .filter(|word| word.severity == severity)
+            .map(|word| word.word.clone())
+            .collect::<Vec<String>>()
+    };
+    match flagged.iter().map(|word| word.severity).max() {
+        Some(WordSeverity::Reject) => Verdict::Reject {
+            words: words_of(WordSeverity::Reject),
+        },
+        Some(WordSeverity::Moderate) => Verdict::Moderate {
+            words: words_of(WordSeverity::Moderate),
+        },
+        _ => Verdict::Allow,
+    }
+}
+
+/// Screens every part of one item, e.g. the title and the content of a post,
+/// against all word lists. Returns the parts with masked words hidden along
+/// with the verdict for the whole item.
+pub async fn review(parts: &[&str]) -> (Vec<String>, Verdict) {
+    let words = BannedWordsRepo::get_instance().await.get_all().await;
+    let screened: Vec<Screened> = parts.iter().map(|part| screen(part, &words)).collect();
+    let verdict = verdict(&screened);
+    (
+        screened.into_iter().map(|screened| screened.text).collect(),
+        verdict,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::banned_word::Language;
+
+    fn banned(word: &str, severity: WordSeverity) -> BannedWord {
+        BannedWord {
+            word: word.to_string(),
+            language: Language::En,
+            severity,
+        }
+    }
+
+    fn flagged(text: &str, words: &[BannedWord]) -> Vec<String> {
+        screen(text, words)
+            .flagged
+            .into_iter()
+            .map(|word| word.word)
+            .collect()
+    }
+
+    #[test]
+    fn normalize_transliterates_and_drops_apostrophes() {
+        assert_eq!(normalize("Салом"), "salom");
+        assert_eq!(normalize("Qo‘shiq"), "koshik");
+        assert_eq!(normalize("xo'p"), "hop");
+        assert_eq!(normalize("объект"), "obekt");
+    }
+
+    #[test]
+    fn normalize_keeps_digits_and_repeated_letters() {
+        assert_eq!(normalize("a55"), "a55");
+        assert_eq!(normalize("45"), "45");
+        assert_eq!(normalize("Pass"), "pass");
+    }
+
+    #[test]
+    fn screen_leaves_ordinary_words_alone() {
+        let words = [banned("ass", WordSeverity::Reject)];
+        for text in ["as", "45", "class", "pass", "a5", "as 45 class"] {
+            assert!(flagged(text, &words).is_empty(), "{text} was flagged");
+        }
+    }
+
+    #[test]
+    fn screen_finds_words_spelled_with_digits() {
+        let words = [banned("ass", WordSeverity::Reject)];
+        for text in ["ass", "ASS", "a55", "4ss", "a5555"] {
+            assert_eq!(flagged(text, &words), vec!["ass"], "{text} wasn't flagged");
+        }
+    }
+
+    #[test]
+    fn screen_matches_stems_by_prefix() {
+        let words = [banned("badw*", WordSeverity::Moderate)];
+        assert_eq!(flagged("badwords here", &words), vec!["badw*"]);
+        assert!(flagged("abadword", &words).is_empty());
+    }
+
+    #[test]
+    fn screen_masks_only_mask_words() {
+        let words = [
+            banned("darn", WordSeverity::Mask),
+            banned("heck", WordSeverity::Moderate),
+        ];
+        let screened = screen("darn it, heck!", &words);
+        assert_eq!(screened.text, "**** it, heck!");
+        assert_eq!(screened.flagged.len(), 2);
+    }
+
+    #[test]
+    fn verdict_takes_the_strictest_severity() {
+        let words = [
+            banned("darn", WordSeverity::Mask),
+            banned("heck", WordSeverity::Reject),
+        ];
+        let screened = [screen("darn", &words), screen("heck", &words)];
+        assert!(matches!(
+            verdict(&screened),
+            Verdict::Reject { words } if words == vec!["heck"]
+        ));
+        assert!(matches!(verdict(&[screen("darn", &words)]), Verdict::Allow));
+    }
+}
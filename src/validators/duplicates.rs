@@ -0,0 +1,37 @@
+use crate::{repositories::comments::CommentsRepo, utils::config::setting};
+use lazy_static::lazy_static;
+use similar::TextDiff;
+
+/// Shorter comments ("thanks!", "+1") are fine to repeat.
+const MIN_LENGTH: usize = 20;
+const SIMILARITY: f32 = 0.9;
+
+lazy_static! {
+    static ref WINDOW_SECS: i64 = setting("DUPLICATE_COMMENT_WINDOW_SECS", 3600);
+}
+
+fn simplify(text: &str) -> String {
+    text.to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Whether `author` wrote nearly the same comment, anywhere, within the
+/// last `DUPLICATE_COMMENT_WINDOW_SECS` seconds.
+pub async fn is_repeated(author: &str, content: &str) -> bool {
+    let content = simplify(content);
+    if content.chars().count() < MIN_LENGTH {
+        return false;
+    }
+    let since = chrono::Utc::now().naive_utc() - chrono::Duration::seconds(*WINDOW_SECS);
+    CommentsRepo::get_instance()
+        .await
+        .get_recent_contents(author, since)
+        .await
+        .iter()
+        .any(|previous| {
+            TextDiff::from_chars(content.as_str(), simplify(previous).as_str()).ratio()
+                >= SIMILARITY
+        })
+}
@@ -1,2 +1,5 @@
+pub mod content_policy;
+pub mod duplicates;
+pub mod premoderation;
 pub mod repository_query;
 pub mod user;
@@ -0,0 +1,45 @@
+use crate::{
+    models::user::UserModel,
+    repositories::{moderation::ModerationRepo, users::UserRepo},
+    services::two_factor,
+    utils::config,
+};
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref USER_TYPES: Vec<String> = config::list("PREMODERATION_USER_TYPES", "");
+    static ref ACCOUNT_AGE_DAYS: i64 = config::setting("PREMODERATION_ACCOUNT_AGE_DAYS", 0);
+    static ref STRIKES: i64 = config::setting("PREMODERATION_STRIKES", 0);
+}
+
+/// Whether what `author` publishes has to be approved before anyone else
+/// sees it. Set in .env: `PREMODERATION_USER_TYPES` lists the user types
+/// (e.g. `Student,Other`), `PREMODERATION_ACCOUNT_AGE_DAYS` covers accounts
+/// younger than that and `PREMODERATION_STRIKES` users with at least that
+/// many strikes. A zero turns the check off, administrators are never held.
+pub async fn required(author: &UserModel) -> bool {
+    let user_type = author.user_specs();
+    if two_factor::acts_as_administrator(author).await {
+        return false;
+    }
+    if USER_TYPES.iter().any(|name| name == user_type.type_name()) {
+        return true;
+    }
+    if *ACCOUNT_AGE_DAYS > 0 {
+        let registered_at = UserRepo::get_instance()
+            .await
+            .registered_at(&author.username())
+            .await;
+        let young_since =
+            chrono::Utc::now().naive_utc() - chrono::Duration::days(*ACCOUNT_AGE_DAYS);
+        if registered_at.is_none_or(|registered_at| registered_at > young_since) {
+            return true;
+        }
+    }
+    *STRIKES > 0
+        && ModerationRepo::get_instance()
+            .await
+            .strikes(&author.username())
+            .await
+            >= *STRIKES
+}
@@ -1,5 +1,6 @@
 use crate::{
-    models::user::UserModel, prelude::Validateble, repositories::users::queries::ChangeQueryParam,
+    dto::UserRegistrationDTO, models::user::UserModel, prelude::Validateble,
+    repositories::users::queries::ChangeQueryParam,
 };
 use regex::Regex;
 use serde::Serialize;
@@ -54,6 +55,45 @@ impl ValidatedChangeQueryParam {
     }
 }
 
+/// Runs the checks a user's parameters go through when they are changed on
+/// the parameters of a new user.
+pub fn validate_registration(dto: &UserRegistrationDTO) -> Result<(), Vec<ValidationError>> {
+    let mut errors = Vec::new();
+    let results = [
+        validate_username(&dto.username),
+        validate_password(dto.password.clone()),
+        validate_name(dto.first_name.clone()),
+        validate_name(dto.last_name.clone()),
+        validate_email(dto.email.clone()),
+        validate_phone_number(dto.phone_number.clone()),
+    ];
+    for result in results {
+        if let Err(mut err) = result {
+            errors.append(&mut err);
+        }
+    }
+    if dto.about.len() > 500 {
+        errors.push(ValidationError::InvalidAbout);
+    }
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(errors)
+}
+
+/// ASCII letters, digits, dots, dashes and underscores, at most 63 of them.
+fn validate_username(username: &str) -> Result<(), Vec<ValidationError>> {
+    if username.is_empty()
+        || username.len() > 63
+        || !username
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || ".-_".contains(char))
+    {
+        return Err(vec![ValidationError::InvalidUsername]);
+    }
+    Ok(())
+}
+
 fn validate_password(password: String) -> Result<(), Vec<ValidationError>> {
     let mut errors = Vec::new();
     if password.len() < 8 {
@@ -75,7 +115,7 @@ fn validate_password(password: String) -> Result<(), Vec<ValidationError>> {
 }
 
 fn validate_email(email: String) -> Result<(), Vec<ValidationError>> {
-    let regex = Regex::new(r#"^[\w-\.]+@([\w-]+\.)+[\w-]{2,4}$"#).unwrap();
+    let regex = Regex::new(r#"^[\w.-]+@([\w-]+\.)+[\w-]{2,}$"#).unwrap();
     if regex.is_match(&email) {
         return Ok(());
     }
@@ -125,6 +165,7 @@ pub enum ValidationError {
     InvalidPhoneNumber(InvalidPhoneNumber),
     InvalidPassword(InvalidPassword),
     InvalidName,
+    InvalidUsername,
     InvalidAbout,
     InvalidEmail,
     InvalidJobTitle,
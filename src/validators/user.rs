@@ -55,7 +55,7 @@ pub(super) fn validate_password(password: String) -> Result<(), Vec<UserValidati
 }
 
 pub(super) fn validate_email(email: String) -> Result<(), Vec<UserValidationError>> {
-    let regex = Regex::new(r#"^[\w-\.]+@([\w-]+\.)+[\w-]{2,4}$"#).unwrap();
+    let regex = Regex::new(r#"^[\w.-]+@([\w-]+\.)+[\w-]{2,}$"#).unwrap();
     if regex.is_match(&email) {
         return Ok(());
     }